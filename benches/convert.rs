@@ -0,0 +1,105 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use pixel_convert::{
+    adaptation::{adapt_slice, adapt_xyz, ChromaticAdaptation},
+    color_space::ColorSpace,
+    plan::ConversionPlan,
+    rgb_color_space::RgbColorSpace,
+    whitepoint::Whitepoint,
+};
+
+/// Image sizes from a thumbnail up to a 4K frame.
+const SIZES: [(usize, usize); 3] = [(64, 64), (1920, 1080), (3840, 2160)];
+
+fn gradient(len: usize) -> Vec<[f32; 4]> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / len as f32;
+            [t, (t * 7.0).fract(), 1.0 - t, 1.0]
+        })
+        .collect()
+}
+
+fn bench_convert(c: &mut Criterion) {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let conversions = [
+        ("srgb_to_srgb", srgb, srgb),
+        (
+            "srgb_to_bt2020",
+            srgb,
+            ColorSpace::Rgb(RgbColorSpace::BT2020),
+        ),
+        ("srgb_to_oklab", srgb, ColorSpace::Oklab),
+    ];
+
+    for (name, from, to) in conversions {
+        let plan = ConversionPlan::new(&from, &to);
+        let mut group = c.benchmark_group(name);
+
+        for (width, height) in SIZES {
+            let image = gradient(width * height);
+            group.throughput(Throughput::Elements((width * height) as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(format!("{width}x{height}")),
+                &image,
+                |b, image| {
+                    b.iter_batched_ref(
+                        || image.clone(),
+                        |pixels| plan.apply_slice(pixels),
+                        BatchSize::LargeInput,
+                    );
+                },
+            );
+        }
+
+        group.finish();
+    }
+}
+
+/// Compares adapting a whole image at once with working out the adaptation
+/// matrix again for every pixel.
+fn bench_adapt(c: &mut Criterion) {
+    let (from, to, method) = (
+        Whitepoint::D65,
+        Whitepoint::D50,
+        ChromaticAdaptation::Bradford,
+    );
+    let mut group = c.benchmark_group("adapt_d65_to_d50");
+
+    for (width, height) in SIZES {
+        let image = gradient(width * height);
+        group.throughput(Throughput::Elements((width * height) as u64));
+        group.bench_with_input(
+            BenchmarkId::new("slice", format!("{width}x{height}")),
+            &image,
+            |b, image| {
+                b.iter_batched_ref(
+                    || image.clone(),
+                    |pixels| adapt_slice(pixels, from, to, method),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("per_pixel", format!("{width}x{height}")),
+            &image,
+            |b, image| {
+                b.iter_batched_ref(
+                    || image.clone(),
+                    |pixels| {
+                        for pixel in pixels.iter_mut() {
+                            let [x, y, z, alpha] = *pixel;
+                            let [x, y, z] = adapt_xyz([x, y, z], from, to, method);
+                            *pixel = [x, y, z, alpha];
+                        }
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_convert, bench_adapt);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pixel_convert::icc::IccProfile;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(profile) = IccProfile::parse(data) {
+        let _ = profile.to_rgb_color_space();
+        let _ = profile.to_xyz([0.5, 0.5, 0.5, 1.0]);
+    }
+});
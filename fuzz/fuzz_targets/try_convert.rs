@@ -0,0 +1,57 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pixel_convert::{
+    color_space::{try_convert, ColorSpace},
+    plan::ConversionPlan,
+    rgb_color_space::RgbColorSpace,
+    whitepoint::Whitepoint,
+};
+
+const SPACES: [ColorSpace; 7] = [
+    ColorSpace::Rgb(RgbColorSpace::SRGB),
+    ColorSpace::Rgb(RgbColorSpace::BT2020),
+    ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3),
+    ColorSpace::Xyz(Whitepoint::D50),
+    ColorSpace::CieLab(Whitepoint::D65),
+    ColorSpace::Oklab,
+    ColorSpace::Oklch,
+];
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&[from, to], data)) = data.split_first_chunk::<2>() else {
+        return;
+    };
+    // The next two floats are a custom white, which may be degenerate.
+    let Some((white, data)) = data.split_first_chunk::<8>() else {
+        return;
+    };
+    let white = [0, 4].map(|i| f32::from_le_bytes(white[i..][..4].try_into().unwrap()));
+    let custom = ColorSpace::Rgb(RgbColorSpace {
+        whitepoint: Whitepoint::Custom(white),
+        ..RgbColorSpace::SRGB
+    });
+    let space = |i: u8| {
+        SPACES
+            .get(usize::from(i) % (SPACES.len() + 1))
+            .copied()
+            .unwrap_or(custom)
+    };
+    let (from, to) = (space(from), space(to));
+
+    // Reinterpret the remaining bytes as pixels, including NaNs, infinities
+    // and subnormals.
+    let mut pixels: Vec<[f32; 4]> = data
+        .chunks_exact(16)
+        .map(|chunk| {
+            std::array::from_fn(|i| f32::from_le_bytes(chunk[i * 4..][..4].try_into().unwrap()))
+        })
+        .collect();
+
+    for &pixel in &pixels {
+        let _ = try_convert(pixel, &from, &to);
+    }
+    if let Ok(plan) = ConversionPlan::try_new(&from, &to) {
+        let _ = plan.try_apply_slice(&mut pixels);
+    }
+});
@@ -0,0 +1,196 @@
+//! Tools for checking colors against the needs of different viewers.
+
+use crate::{matrix::RowMatrix, rgb_color_space::RgbColorSpace, transfer::Transfer};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The Smith and Pokorny cone fundamentals from XYZ that Viénot, Brettel and
+/// Mollon (1999) simulate dichromacy in. The rows are scaled so that `L + M`
+/// is `Y`.
+const SMITH_POKORNY_LMS: RowMatrix = RowMatrix([
+    [0.155_14, 0.543_12, -0.032_86],
+    [-0.155_14, 0.456_84, 0.032_86],
+    [0.0, 0.0, 0.008_01],
+]);
+
+/// A kind of dichromatic color vision deficiency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvdKind {
+    /// Missing long-wavelength (red) cones.
+    Protanopia,
+    /// Missing medium-wavelength (green) cones.
+    Deuteranopia,
+    /// Missing short-wavelength (blue) cones.
+    Tritanopia,
+}
+
+impl CvdKind {
+    /// The cone response that is missing.
+    fn missing_cone(self) -> usize {
+        match self {
+            CvdKind::Protanopia => 0,
+            CvdKind::Deuteranopia => 1,
+            CvdKind::Tritanopia => 2,
+        }
+    }
+
+    /// The linear RGB primary that, alongside white, dichromats of this kind
+    /// see the same as trichromats.
+    fn anchor(self) -> [f32; 3] {
+        match self {
+            CvdKind::Protanopia | CvdKind::Deuteranopia => [0.0, 0.0, 1.0],
+            CvdKind::Tritanopia => [1.0, 0.0, 0.0],
+        }
+    }
+
+    /// Returns the matrix in LMS replacing the missing cone response with a
+    /// combination of the remaining two, such that white and the anchor
+    /// wavelength are left unchanged.
+    fn projection(self, white: [f32; 3], anchor: [f32; 3]) -> RowMatrix {
+        let missing = self.missing_cone();
+        let [i, j] = match missing {
+            0 => [1, 2],
+            1 => [0, 2],
+            _ => [0, 1],
+        };
+
+        let det = white[i] * anchor[j] - white[j] * anchor[i];
+        let a = (white[missing] * anchor[j] - white[j] * anchor[missing]) / det;
+        let b = (white[i] * anchor[missing] - white[missing] * anchor[i]) / det;
+
+        let mut projection = RowMatrix::IDENTITY;
+        projection.0[missing] = [0.0; 3];
+        projection.0[missing][i] = a;
+        projection.0[missing][j] = b;
+        projection
+    }
+}
+
+/// A color vision deficiency simulation for one kind of deficiency in one RGB
+/// space, worked out once for simulating many pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CvdSimulation {
+    /// The linear RGB matrix giving full dichromacy.
+    matrix: RowMatrix,
+    transfer: Transfer,
+}
+
+impl CvdSimulation {
+    /// Creates the simulation of `kind` in `space`, using the single plane
+    /// projection of Viénot, Brettel and Mollon (1999) in the Smith and
+    /// Pokorny LMS space of linear light.
+    ///
+    /// The plane keeps white and the blue primary unchanged. The paper only
+    /// covers protanopia and deuteranopia, so tritanopia keeps the red
+    /// primary instead, as an approximation in the same spirit.
+    pub fn new(kind: CvdKind, space: &RgbColorSpace) -> CvdSimulation {
+        let to_lms = SMITH_POKORNY_LMS * space.to_xyz_matrix();
+        let white = to_lms.mul_vec([1.0; 3]);
+        let anchor = to_lms.mul_vec(kind.anchor());
+
+        CvdSimulation {
+            matrix: to_lms.inv() * kind.projection(white, anchor) * to_lms,
+            transfer: space.transfer,
+        }
+    }
+
+    /// Simulates how `pixel` appears, with `severity` blending from normal
+    /// vision at `0.0` to full dichromacy at `1.0`. Alpha is left untouched.
+    #[must_use]
+    pub fn apply(&self, pixel: [f32; 4], severity: f32) -> [f32; 4] {
+        let [r, g, b, a] = self.transfer.to_optical_display(pixel);
+        let simulated = self.matrix.mul_vec([r, g, b]);
+        let [r, g, b] =
+            core::array::from_fn(|c| [r, g, b][c] + (simulated[c] - [r, g, b][c]) * severity);
+
+        self.transfer.from_optical_display([r, g, b, a])
+    }
+}
+
+/// Simulates how `pixel` in `space` appears to a viewer with the color vision
+/// deficiency `kind`, as [`CvdSimulation`] does. Build a [`CvdSimulation`]
+/// once to simulate many pixels.
+///
+/// `severity` blends from normal vision at `0.0` to full dichromacy at `1.0`.
+/// Alpha is left untouched.
+pub fn simulate_cvd(
+    pixel: [f32; 4],
+    kind: CvdKind,
+    severity: f32,
+    space: &RgbColorSpace,
+) -> [f32; 4] {
+    CvdSimulation::new(kind, space).apply(pixel, severity)
+}
+
+/// Returns the relative luminance of `pixel` in `space`, the `Y` of its
+/// linear light relative to the space's white.
+pub fn relative_luminance(pixel: [f32; 4], space: &RgbColorSpace) -> f32 {
+    space.to_xyz(pixel)[1]
+}
+
+/// Returns the WCAG 2 contrast ratio between `fg` and `bg` in `space`, from
+/// `1.0` for identical luminances up to `21.0` for black on white.
+///
+/// Luminance is computed from the space's own primaries rather than the sRGB
+/// coefficients WCAG quotes. Alpha is ignored.
+pub fn wcag_contrast(fg: [f32; 4], bg: [f32; 4], space: &RgbColorSpace) -> f32 {
+    let fg = relative_luminance(fg, space);
+    let bg = relative_luminance(bg, space);
+
+    (fg.max(bg) + 0.05) / (fg.min(bg) + 0.05)
+}
+
+/// Returns the APCA-W3 (0.0.98G-4g) lightness contrast `Lc` of `text` on
+/// `background`, both opaque sRGB.
+///
+/// The result is positive for dark text on a light background, negative for
+/// light text on a dark background, and `0.0` when the contrast is too low to
+/// be meaningful. Black on white is about `106`. Alpha is ignored.
+pub fn apca_contrast(text: [f32; 4], background: [f32; 4]) -> f32 {
+    const BLACK_THRESHOLD: f32 = 0.022;
+    const BLACK_CLAMP: f32 = 1.414;
+    const DELTA_Y_MIN: f32 = 0.0005;
+    const SCALE: f32 = 1.14;
+    const LOW_CLIP: f32 = 0.1;
+    const LOW_OFFSET: f32 = 0.027;
+
+    // APCA estimates screen luminance with a plain 2.4 gamma rather than the
+    // piecewise sRGB curve.
+    let luminance = |[r, g, b, _]: [f32; 4]| {
+        let y = 0.212_672_9 * r.max(0.0).powf(2.4)
+            + 0.715_152_2 * g.max(0.0).powf(2.4)
+            + 0.072_175 * b.max(0.0).powf(2.4);
+
+        // Soft clamp near black to model flare.
+        if y < BLACK_THRESHOLD {
+            y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
+        } else {
+            y
+        }
+    };
+
+    let text = luminance(text);
+    let background = luminance(background);
+    if (background - text).abs() < DELTA_Y_MIN {
+        return 0.0;
+    }
+
+    let contrast = if background > text {
+        let sapc = (background.powf(0.56) - text.powf(0.57)) * SCALE;
+        if sapc < LOW_CLIP {
+            0.0
+        } else {
+            sapc - LOW_OFFSET
+        }
+    } else {
+        let sapc = (background.powf(0.65) - text.powf(0.62)) * SCALE;
+        if sapc > -LOW_CLIP {
+            0.0
+        } else {
+            sapc + LOW_OFFSET
+        }
+    };
+
+    contrast * 100.0
+}
@@ -0,0 +1,121 @@
+//! Chromatic adaptation between whitepoints.
+
+use crate::{matrix::RowMatrix, whitepoint::Whitepoint};
+
+/// A von Kries style chromatic adaptation transform, named by the cone
+/// response space the white is scaled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaticAdaptation {
+    /// Scales XYZ directly, the crudest transform.
+    XyzScaling,
+    /// Scales the Hunt-Pointer-Estevez cone responses.
+    VonKries,
+    /// Scales the sharpened Bradford cone responses, as ICC profiles do.
+    #[default]
+    Bradford,
+}
+
+impl ChromaticAdaptation {
+    pub(crate) fn cone_response(self) -> RowMatrix {
+        match self {
+            ChromaticAdaptation::XyzScaling => RowMatrix::IDENTITY,
+            ChromaticAdaptation::VonKries => ConeResponse::Hpe.matrix(),
+            ChromaticAdaptation::Bradford => RowMatrix([
+                [0.895_1, 0.266_4, -0.161_4],
+                [-0.750_2, 1.713_5, 0.036_7],
+                [0.038_9, -0.068_5, 1.029_6],
+            ]),
+        }
+    }
+
+    /// Returns the matrix adapting XYZ values seen under `from` to how they
+    /// appear under `to`.
+    pub fn matrix(self, from: Whitepoint, to: Whitepoint) -> RowMatrix {
+        let cone = self.cone_response();
+        let from = cone.mul_vec(from.to_xyz());
+        let to = cone.mul_vec(to.to_xyz());
+        let scale = RowMatrix::diagonal(core::array::from_fn(|i| to[i] / from[i]));
+
+        cone.inv() * scale * cone
+    }
+}
+
+/// A matrix from XYZ to a space of cone responses, or an approximation of
+/// one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConeResponse {
+    /// The Hunt-Pointer-Estevez matrix, normalized to equal energy white.
+    Hpe,
+    /// The sharpened matrix of CIECAM02's chromatic adaptation.
+    Cat02,
+    /// The matrix of CAM16, which fixes CAT02 giving negative responses
+    /// for some colors.
+    Cat16,
+    /// The first matrix of Oklab, optimized with it for perceptual
+    /// uniformity rather than to match physiology.
+    #[default]
+    OklabM1,
+}
+
+impl ConeResponse {
+    /// Returns the matrix from XYZ to these cone responses as published.
+    pub fn matrix(self) -> RowMatrix {
+        match self {
+            ConeResponse::Hpe => RowMatrix([
+                [0.400_24, 0.707_6, -0.080_81],
+                [-0.226_3, 1.165_32, 0.045_7],
+                [0.0, 0.0, 0.918_22],
+            ]),
+            ConeResponse::Cat02 => RowMatrix([
+                [0.732_8, 0.429_6, -0.162_4],
+                [-0.703_6, 1.697_5, 0.006_1],
+                [0.003_0, 0.013_6, 0.983_4],
+            ]),
+            ConeResponse::Cat16 => RowMatrix([
+                [0.401_288, 0.650_173, -0.051_461],
+                [-0.250_268, 1.204_414, 0.045_854],
+                [-0.002_079, 0.048_952, 0.953_127],
+            ]),
+            ConeResponse::OklabM1 => RowMatrix([
+                [0.818_933, 0.361_866_74, -0.128_859_71],
+                [0.032_984_544, 0.929_311_9, 0.036_145_64],
+                [0.048_200_3, 0.264_366_27, 0.633_851_7],
+            ]),
+        }
+    }
+
+    /// Returns [`Self::matrix`] with each row scaled so that `white` has a
+    /// response of exactly `[1, 1, 1]`.
+    pub fn matrix_normalized_to(self, white: Whitepoint) -> RowMatrix {
+        let matrix = self.matrix();
+        let response = matrix.mul_vec(white.to_xyz());
+        RowMatrix::diagonal(response.map(|r| 1.0 / r)) * matrix
+    }
+}
+
+/// Adapts an XYZ value seen under `from` to how it appears under `to`.
+pub fn adapt_xyz(
+    xyz: [f32; 3],
+    from: Whitepoint,
+    to: Whitepoint,
+    method: ChromaticAdaptation,
+) -> [f32; 3] {
+    method.matrix(from, to).mul_vec(xyz)
+}
+
+/// Adapts every pixel of `pixels` in place from `from` to `to`, working out
+/// the adaptation matrix once. The color channels must be XYZ or linear RGB,
+/// and alpha is left untouched.
+pub fn adapt_slice(
+    pixels: &mut [[f32; 4]],
+    from: Whitepoint,
+    to: Whitepoint,
+    method: ChromaticAdaptation,
+) {
+    let matrix = method.matrix(from, to);
+    for pixel in pixels {
+        let [c0, c1, c2, alpha] = *pixel;
+        let [c0, c1, c2] = matrix.mul_vec([c0, c1, c2]);
+        *pixel = [c0, c1, c2, alpha];
+    }
+}
@@ -0,0 +1,49 @@
+//! Perceptual color adjustments made in Oklch.
+
+use crate::{
+    color_space::{convert, convert_with_intent, ColorSpace},
+    hue::hue_add,
+    plan::RenderingIntent,
+    rgb_color_space::RgbColorSpace,
+};
+
+/// Converts `pixel` in `space` to Oklch, edits it with `edit` and converts it
+/// back, desaturating it into the gamut of `space` if the edit left it.
+fn edit_oklch(
+    pixel: [f32; 4],
+    space: &RgbColorSpace,
+    edit: impl FnOnce([f32; 4]) -> [f32; 4],
+) -> [f32; 4] {
+    let space = ColorSpace::Rgb(*space);
+    let oklch = convert(pixel, &space, &ColorSpace::Oklch);
+
+    convert_with_intent(
+        edit(oklch),
+        &ColorSpace::Oklch,
+        &space,
+        RenderingIntent::Perceptual,
+    )
+}
+
+/// Adds `amount` to the Oklab lightness of `pixel` in `space`, where `1.0`
+/// is the full range from black to white and negative amounts darken.
+pub fn lighten(pixel: [f32; 4], amount: f32, space: &RgbColorSpace) -> [f32; 4] {
+    edit_oklch(pixel, space, |[l, c, h, a]| {
+        [(l + amount).clamp(0.0, 1.0), c, h, a]
+    })
+}
+
+/// Scales the Oklch chroma of `pixel` in `space` by `1.0 + amount`, so
+/// `0.5` increases it by half and `-1.0` removes it entirely. Grays have no
+/// chroma to scale and stay gray.
+pub fn saturate(pixel: [f32; 4], amount: f32, space: &RgbColorSpace) -> [f32; 4] {
+    edit_oklch(pixel, space, |[l, c, h, a]| {
+        [l, (c * (1.0 + amount)).max(0.0), h, a]
+    })
+}
+
+/// Rotates the Oklch hue of `pixel` in `space` by `degrees`, wrapping around
+/// the hue circle.
+pub fn rotate_hue(pixel: [f32; 4], degrees: f32, space: &RgbColorSpace) -> [f32; 4] {
+    edit_oklch(pixel, space, |[l, c, h, a]| [l, c, hue_add(h, degrees), a])
+}
@@ -0,0 +1,126 @@
+//! Blending and compositing of colors in linear light.
+
+use crate::{plan::AlphaMode, rgb_color_space::RgbColorSpace};
+
+/// A separable blend mode, combining each channel of the base and top colors
+/// independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The top color replaces the base.
+    #[default]
+    Normal,
+    /// The product of the colors, which only ever darkens.
+    Multiply,
+    /// The inverse of multiplying the inverted colors, which only ever
+    /// lightens.
+    Screen,
+    /// Multiply where the base is dark and screen where it is light.
+    Overlay,
+    /// The sum of the colors, which may exceed `1.0`.
+    Add,
+}
+
+impl BlendMode {
+    fn blend_channel(self, base: f32, top: f32) -> f32 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Multiply => base * top,
+            BlendMode::Screen => base + top - base * top,
+            BlendMode::Overlay => {
+                if base <= 0.5 {
+                    2.0 * base * top
+                } else {
+                    1.0 - 2.0 * (1.0 - base) * (1.0 - top)
+                }
+            }
+            BlendMode::Add => base + top,
+        }
+    }
+}
+
+/// Blends `top` onto `base`, both in `space`, with `mode`.
+///
+/// The blend is computed in linear light, where multiplying by white leaves a
+/// color unchanged and multiplying by black gives black, and the result is
+/// then mixed over `base` by the top's alpha. The result's alpha is the two
+/// alphas composited with source-over.
+pub fn blend(base: [f32; 4], top: [f32; 4], mode: BlendMode, space: &RgbColorSpace) -> [f32; 4] {
+    let base = space.transfer.to_optical_display(base);
+    let top = space.transfer.to_optical_display(top);
+    let coverage = top[3];
+
+    let mut blended: [f32; 4] = core::array::from_fn(|c| {
+        let channel = mode.blend_channel(base[c], top[c]);
+        base[c] + (channel - base[c]) * coverage
+    });
+    blended[3] = coverage + base[3] * (1.0 - coverage);
+
+    space.transfer.from_optical_display(blended)
+}
+
+/// The alpha below which [`unmatte`] gives up recovering a color, about a
+/// quarter of an 8-bit code.
+pub const UNMATTE_MIN_ALPHA: f32 = 1.0 / 1024.0;
+
+/// Composites the straight `pixel` over the opaque `background`, both in
+/// `space`, in linear light. Alpha is kept, so that [`unmatte`] can recover
+/// the pixel.
+#[must_use]
+pub fn matte(pixel: [f32; 4], background: [f32; 3], space: &RgbColorSpace) -> [f32; 4] {
+    let [r, g, b, alpha] = space.transfer.to_optical_display(pixel);
+    let [br, bg, bb] = background;
+    let [br, bg, bb, _] = space.transfer.to_optical_display([br, bg, bb, 1.0]);
+
+    let over = |c: f32, background: f32| c * alpha + background * (1.0 - alpha);
+    space
+        .transfer
+        .from_optical_display([over(r, br), over(g, bg), over(b, bb), alpha])
+}
+
+/// Recovers the straight color of `composited`, a pixel in `space` that was
+/// composited over the opaque `background` as by [`matte`] with its alpha
+/// kept, in linear light.
+///
+/// Removing the background divides by alpha, so any error in `composited`,
+/// such as from quantizing it, is magnified by `1 / alpha` and the result
+/// becomes meaningless as alpha approaches zero. Below
+/// [`UNMATTE_MIN_ALPHA`] there is too little of the pixel left to recover
+/// and the color channels become zero, as for fully transparent pixels in
+/// [`AlphaMode::to_straight`](crate::plan::AlphaMode::to_straight).
+#[must_use]
+pub fn unmatte(composited: [f32; 4], background: [f32; 3], space: &RgbColorSpace) -> [f32; 4] {
+    let alpha = composited[3];
+    if alpha < UNMATTE_MIN_ALPHA {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+
+    let [r, g, b, _] = space.transfer.to_optical_display(composited);
+    let [br, bg, bb] = background;
+    let [br, bg, bb, _] = space.transfer.to_optical_display([br, bg, bb, 1.0]);
+
+    let unover = |c: f32, background: f32| (c - background * (1.0 - alpha)) / alpha;
+    space
+        .transfer
+        .from_optical_display([unover(r, br), unover(g, bg), unover(b, bb), alpha])
+}
+
+/// Composites `top` over `bottom`, both in `space` with straight alpha, with
+/// the Porter-Duff source-over operator in linear light.
+///
+/// Unlike [`blend`] with [`BlendMode::Normal`], this accounts for the
+/// bottom's alpha, so a translucent top over a translucent bottom gets the
+/// right mix of both. The result has straight alpha, and is transparent
+/// black if both inputs are fully transparent.
+#[must_use]
+pub fn source_over(top: [f32; 4], bottom: [f32; 4], space: &RgbColorSpace) -> [f32; 4] {
+    let premultiply =
+        |pixel| AlphaMode::Premultiplied.from_straight(space.transfer.to_optical_display(pixel));
+    let top = premultiply(top);
+    let bottom = premultiply(bottom);
+
+    let coverage = top[3];
+    let composited = core::array::from_fn(|c| top[c] + bottom[c] * (1.0 - coverage));
+    space
+        .transfer
+        .from_optical_display(AlphaMode::Premultiplied.to_straight(composited))
+}
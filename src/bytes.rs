@@ -0,0 +1,204 @@
+//! Converting raw pixel bytes, such as from a file or stdin, without first
+//! building typed pixels.
+
+use alloc::vec::Vec;
+
+use crate::{
+    channel::{ChannelEncoding, Rounding},
+    plan::ConversionPlan,
+};
+
+/// The order of the channels within a pixel. For spaces other than RGB the
+/// red, green and blue positions hold the first, second and third channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelOrder {
+    Rgb,
+    Rgba,
+    Bgr,
+    Bgra,
+    Argb,
+}
+
+impl ChannelOrder {
+    /// Returns the number of channels in a pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            ChannelOrder::Rgb | ChannelOrder::Bgr => 3,
+            ChannelOrder::Rgba | ChannelOrder::Bgra | ChannelOrder::Argb => 4,
+        }
+    }
+
+    /// Returns the positions of red, green and blue within a pixel, and of
+    /// alpha if there is one.
+    fn positions(self) -> ([usize; 3], Option<usize>) {
+        match self {
+            ChannelOrder::Rgb => ([0, 1, 2], None),
+            ChannelOrder::Rgba => ([0, 1, 2], Some(3)),
+            ChannelOrder::Bgr => ([2, 1, 0], None),
+            ChannelOrder::Bgra => ([2, 1, 0], Some(3)),
+            ChannelOrder::Argb => ([1, 2, 3], Some(0)),
+        }
+    }
+}
+
+/// The order of the bytes of a multi-byte channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    /// The byte order of the target, as in buffers filled from typed `u16`
+    /// slices.
+    #[default]
+    Native,
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first, as in PNG.
+    Big,
+}
+
+impl Endian {
+    /// Reads a `u16` stored in this byte order.
+    pub fn read_u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Endian::Native => u16::from_ne_bytes(bytes),
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    /// Returns the bytes storing `v` in this byte order.
+    pub fn write_u16(self, v: u16) -> [u8; 2] {
+        match self {
+            Endian::Native => v.to_ne_bytes(),
+            Endian::Little => v.to_le_bytes(),
+            Endian::Big => v.to_be_bytes(),
+        }
+    }
+}
+
+/// How each channel is stored, as an unsigned integer covering `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelFormat {
+    U8,
+    /// A `u16` in the given byte order, which applies to alpha as much as
+    /// the color channels.
+    U16(Endian),
+}
+
+impl ChannelFormat {
+    /// Returns the number of bytes in a channel.
+    pub fn bytes(self) -> usize {
+        match self {
+            ChannelFormat::U8 => 1,
+            ChannelFormat::U16(_) => 2,
+        }
+    }
+
+    fn read(self, bytes: &[u8]) -> f32 {
+        match self {
+            ChannelFormat::U8 => bytes[0].to_normalized(),
+            ChannelFormat::U16(endian) => endian.read_u16([bytes[0], bytes[1]]).to_normalized(),
+        }
+    }
+
+    fn write(self, v: f32, bytes: &mut [u8]) {
+        match self {
+            ChannelFormat::U8 => bytes[0] = u8::from_normalized(v, Rounding::default()),
+            ChannelFormat::U16(endian) => bytes
+                .copy_from_slice(&endian.write_u16(u16::from_normalized(v, Rounding::default()))),
+        }
+    }
+}
+
+/// Whether the channels of each pixel are stored together or each channel
+/// of the whole image is stored in its own plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interleaving {
+    /// Every pixel's channels are adjacent, as in `RGBRGB...`.
+    #[default]
+    Interleaved,
+    /// Planes of one channel follow each other in [`ChannelOrder`], as in
+    /// `RR...GG...BB...`.
+    Planar,
+}
+
+/// How pixels are laid out in a byte buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelLayout {
+    pub order: ChannelOrder,
+    pub format: ChannelFormat,
+    pub interleaving: Interleaving,
+}
+
+impl PixelLayout {
+    /// Returns the number of bytes a pixel takes up.
+    pub fn pixel_size(self) -> usize {
+        self.order.channels() * self.format.bytes()
+    }
+
+    /// Returns the range of bytes holding the channel at `position` of pixel
+    /// `index` of `count`.
+    fn channel_bytes(self, position: usize, index: usize, count: usize) -> core::ops::Range<usize> {
+        let sample = match self.interleaving {
+            Interleaving::Interleaved => index * self.order.channels() + position,
+            Interleaving::Planar => position * count + index,
+        };
+        let start = sample * self.format.bytes();
+        start..start + self.format.bytes()
+    }
+
+    /// Reads pixel `index` of `count` from `bytes`, treating it as opaque if
+    /// there is no alpha channel.
+    fn read_pixel(self, bytes: &[u8], index: usize, count: usize) -> [f32; 4] {
+        let (color, alpha) = self.order.positions();
+        let read = |position| {
+            self.format
+                .read(&bytes[self.channel_bytes(position, index, count)])
+        };
+        let [c0, c1, c2] = color.map(read);
+        [c0, c1, c2, alpha.map_or(1.0, read)]
+    }
+
+    /// Writes `pixel` as pixel `index` of `count` in `bytes`, dropping alpha
+    /// if there is no alpha channel.
+    fn write_pixel(self, bytes: &mut [u8], index: usize, count: usize, pixel: [f32; 4]) {
+        let (color, alpha) = self.order.positions();
+        let positions = color.into_iter().map(Some).chain([alpha]);
+        for (position, v) in positions.zip(pixel) {
+            if let Some(position) = position {
+                self.format
+                    .write(v, &mut bytes[self.channel_bytes(position, index, count)]);
+            }
+        }
+    }
+}
+
+/// Converts the pixels in `input` with `plan`, replacing the contents of
+/// `output` with the result in the same `layout`.
+///
+/// Channels are rounded to the nearest integer on output. Pixels without an
+/// alpha channel are converted as opaque.
+///
+/// # Panics
+///
+/// Panics if the length of `input` is not a multiple of
+/// [`PixelLayout::pixel_size`].
+pub fn convert_bytes(
+    input: &[u8],
+    layout: PixelLayout,
+    plan: &ConversionPlan,
+    output: &mut Vec<u8>,
+) {
+    let pixel_size = layout.pixel_size();
+    assert!(
+        input.len().is_multiple_of(pixel_size),
+        "{} bytes is not a whole number of {pixel_size} byte pixels",
+        input.len()
+    );
+
+    let count = input.len() / pixel_size;
+    output.clear();
+    output.resize(input.len(), 0);
+    for index in 0..count {
+        let pixel = plan.apply(layout.read_pixel(input, index, count));
+        layout.write_pixel(output, index, count, pixel);
+    }
+}
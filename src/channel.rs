@@ -0,0 +1,231 @@
+//! Storing normalized channel values as integers.
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// How values are rounded when they are quantized to integers.
+///
+/// The modes only differ for values exactly halfway between two integers,
+/// which is where results from different tools usually disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Rounds toward zero, so positive values never round up.
+    Truncate,
+    /// Rounds to the nearest integer with halves away from zero.
+    #[default]
+    Nearest,
+    /// Rounds to the nearest integer with halves to the even neighbor, which
+    /// avoids biasing the average of many values upward.
+    NearestEven,
+}
+
+impl Rounding {
+    /// Rounds `v` to an integral value with this mode.
+    pub fn round(self, v: f32) -> f32 {
+        match self {
+            Rounding::Truncate => v.trunc(),
+            Rounding::Nearest => v.round(),
+            Rounding::NearestEven => v.round_ties_even(),
+        }
+    }
+}
+
+/// A type a normalized channel value can be stored as.
+pub trait ChannelEncoding: Copy {
+    /// The normalized value a color difference of zero is stored as, which
+    /// is the middle code `2ⁿ⁻¹` for unsigned integers and zero for types
+    /// centered on zero.
+    const CHROMA_OFFSET: f32;
+
+    /// Returns the normalized value this stores.
+    fn to_normalized(self) -> f32;
+
+    /// Stores the normalized value `v`, rounding with `rounding` and
+    /// clamping to the range of the type.
+    fn from_normalized(v: f32, rounding: Rounding) -> Self;
+}
+
+/// Unsigned integers store `[0, 1]` across their full range.
+macro_rules! impl_unsigned_channel_encoding {
+    ($($ty:ty),*) => {$(
+        impl ChannelEncoding for $ty {
+            const CHROMA_OFFSET: f32 = (<$ty>::MAX / 2 + 1) as f32 / <$ty>::MAX as f32;
+
+            fn to_normalized(self) -> f32 {
+                f32::from(self) / f32::from(<$ty>::MAX)
+            }
+
+            fn from_normalized(v: f32, rounding: Rounding) -> Self {
+                // NaN becomes zero through the saturating cast.
+                rounding.round(v.clamp(0.0, 1.0) * f32::from(<$ty>::MAX)) as $ty
+            }
+        }
+    )*};
+}
+
+impl_unsigned_channel_encoding!(u8, u16);
+
+/// Signed integers store `[-0.5, 0.5]`, the range of color differences,
+/// symmetrically so that zero is neutral. The most negative value of two's
+/// complement has no positive counterpart and is read as the next one up.
+macro_rules! impl_signed_channel_encoding {
+    ($($ty:ty),*) => {$(
+        impl ChannelEncoding for $ty {
+            const CHROMA_OFFSET: f32 = 0.0;
+
+            fn to_normalized(self) -> f32 {
+                f32::from(self.max(-<$ty>::MAX)) / (2.0 * f32::from(<$ty>::MAX))
+            }
+
+            fn from_normalized(v: f32, rounding: Rounding) -> Self {
+                rounding.round(v.clamp(-0.5, 0.5) * 2.0 * f32::from(<$ty>::MAX)) as $ty
+            }
+        }
+    )*};
+}
+
+impl_signed_channel_encoding!(i8, i16);
+
+impl ChannelEncoding for f32 {
+    const CHROMA_OFFSET: f32 = 0.0;
+
+    fn to_normalized(self) -> f32 {
+        self
+    }
+
+    fn from_normalized(v: f32, _rounding: Rounding) -> Self {
+        v
+    }
+}
+
+/// The number of significant bits in unsigned integer codes stored in a
+/// `u16`, such as the 12 or 14-bit linear data of camera raw files, whose
+/// largest code `2ⁿ - 1` is full scale.
+///
+/// [`ChannelEncoding`] for `u16` is `BitDepth(16)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitDepth(pub u8);
+
+impl BitDepth {
+    /// Returns the largest code, which stores `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the depth isn't between 1 and 16 bits.
+    pub fn max_code(self) -> u16 {
+        assert!(
+            (1..=16).contains(&self.0),
+            "a bit depth of {} doesn't fit in a u16",
+            self.0
+        );
+        (u32::MAX >> (32 - u32::from(self.0))) as u16
+    }
+
+    /// Returns the normalized value `code` stores. Codes above
+    /// [`Self::max_code`] give values above `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the depth isn't between 1 and 16 bits.
+    pub fn to_normalized(self, code: u16) -> f32 {
+        f32::from(code) / f32::from(self.max_code())
+    }
+
+    /// Stores the normalized value `v`, rounding with `rounding` and
+    /// clamping to the codes of this depth.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the depth isn't between 1 and 16 bits.
+    pub fn from_normalized(self, v: f32, rounding: Rounding) -> u16 {
+        let max = f32::from(self.max_code());
+        // NaN becomes zero through the saturating cast.
+        rounding.round(v.clamp(0.0, 1.0) * max) as u16
+    }
+}
+
+/// Which codes of a bit depth black and white are stored at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignalRange {
+    /// Black is code zero and white the largest code.
+    #[default]
+    Full,
+    /// The narrow range, or studio swing, of video from ITU-R BT.601, BT.709
+    /// and BT.2100, with black at code `16` and white at `235` scaled to the
+    /// bit depth, so `64` and `940` for 10 bits. The codes beyond them hold
+    /// undershoot and overshoot, which normalize to below `0.0` and above
+    /// `1.0`.
+    ///
+    /// This is the range of luma and of R'G'B'. Color differences are stored
+    /// around the middle code over a different range.
+    Narrow,
+}
+
+impl SignalRange {
+    /// Returns the codes black and white are stored at for `depth`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the depth isn't between 1 and 16 bits, or for the narrow
+    /// range, between 8 and 16 bits.
+    pub fn black_and_white(self, depth: BitDepth) -> [u16; 2] {
+        match self {
+            SignalRange::Full => [0, depth.max_code()],
+            SignalRange::Narrow => {
+                assert!(
+                    (8..=16).contains(&depth.0),
+                    "the narrow range needs 8 to 16 bits, not {}",
+                    depth.0
+                );
+                let scale = 1 << (depth.0 - 8);
+                [16 * scale, 235 * scale]
+            }
+        }
+    }
+
+    /// Returns the normalized value `code` stores at `depth` in this range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` isn't valid for this range, as for
+    /// [`Self::black_and_white`].
+    pub fn to_normalized(self, code: u16, depth: BitDepth) -> f32 {
+        let [black, white] = self.black_and_white(depth).map(f32::from);
+        (f32::from(code) - black) / (white - black)
+    }
+
+    /// Stores the normalized value `v` at `depth` in this range, rounding with
+    /// `rounding` and clamping to the codes of the depth.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` isn't valid for this range, as for
+    /// [`Self::black_and_white`].
+    pub fn from_normalized(self, v: f32, depth: BitDepth, rounding: Rounding) -> u16 {
+        let [black, white] = self.black_and_white(depth).map(f32::from);
+        let code = rounding.round(black + v * (white - black));
+        // NaN becomes zero through the saturating cast.
+        code.clamp(0.0, f32::from(depth.max_code())) as u16
+    }
+}
+
+/// Quantizes every channel of `pixel`, including alpha, to `T`.
+pub fn quantize_pixel<T: ChannelEncoding>(pixel: [f32; 4], rounding: Rounding) -> [T; 4] {
+    pixel.map(|c| T::from_normalized(c, rounding))
+}
+
+/// Returns the normalized values of every channel of `pixel`.
+pub fn dequantize_pixel<T: ChannelEncoding>(pixel: [T; 4]) -> [f32; 4] {
+    pixel.map(T::to_normalized)
+}
+
+/// Reduces a `u16` channel to a `u8` one, rounding to nearest in integer
+/// arithmetic. Both span `[0, 1]`, so this is `round(v / 257)`, which
+/// shifting right by eight only approximates, rounding down and darkening
+/// everything by half a code on average. No `u16` is exactly halfway
+/// between two `u8` codes, so this agrees with every [`Rounding`] to
+/// nearest.
+pub fn u16_to_u8(v: u16) -> u8 {
+    // Can't overflow as 65535 * 255 + 32895 < 2³².
+    ((u32::from(v) * 255 + 32895) >> 16) as u8
+}
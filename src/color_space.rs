@@ -0,0 +1,603 @@
+//! Color spaces beyond RGB and conversion between any two of them.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    adaptation::ConeResponse,
+    matrix::RowMatrix,
+    plan::{ConversionOptions, ConversionPlan, ConvertError, DomainError, RenderingIntent, Step},
+    rgb_color_space::RgbColorSpace,
+    transfer::Transfer,
+    whitepoint::Whitepoint,
+    yuv::YuvColorSpace,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// A color space that pixels can be converted between.
+///
+/// Pixels are `[f32; 4]` with alpha last, which is never altered by
+/// conversion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    /// An RGB color space.
+    Rgb(RgbColorSpace),
+    /// A Y'CbCr encoding of an RGB color space as `[Y', Cb, Cr]`.
+    Yuv(YuvColorSpace),
+    /// The hue, whiteness and blackness of an RGB color space's encoded
+    /// values as `[H, W, B]`, with the hue in degrees and the others in
+    /// `[0, 1]`. CSS Color 4 defines this over sRGB.
+    Hwb(RgbColorSpace),
+    /// CIE 1931 XYZ relative to the given white, with `Y = 1` for white as
+    /// in [`YScale::Unit`].
+    Xyz(Whitepoint),
+    /// CIE xyY relative to the given white as `[x, y, Y]`, with `Y = 1` for
+    /// white. Black has no chromaticity of its own and is given the white's.
+    Xyy(Whitepoint),
+    /// CIE 1976 L\*a\*b\* relative to the given white, with `L* = 100` for
+    /// white.
+    CieLab(Whitepoint),
+    /// Björn Ottosson's Oklab, relative to D65 with `L = 1` for white.
+    Oklab,
+    /// The cylindrical form of Oklab as `[L, C, h]` with the hue in degrees.
+    Oklch,
+    /// Okhsl as `[H, S, L]`, see [`okhsl`](crate::okhsl).
+    Okhsl,
+    /// Okhsv as `[H, S, V]`, see [`okhsl`](crate::okhsl).
+    Okhsv,
+}
+
+/// Björn Ottosson's matrix from XYZ to Oklab's cone responses, with each row
+/// rescaled so that D65 as defined here maps exactly to `[1, 1, 1]` and grays
+/// have no chroma.
+pub(crate) const OKLAB_M1: RowMatrix = RowMatrix([
+    [0.819_022_4, 0.361_906_26, -0.128_873_8],
+    [0.032_983_656, 0.929_286_86, 0.036_144_666],
+    [0.048_177_194, 0.264_239_53, 0.633_547_8],
+]);
+
+/// Björn Ottosson's matrix from Oklab's compressed cone responses to Lab.
+pub(crate) const OKLAB_M2: RowMatrix = RowMatrix([
+    [0.210_454_26, 0.793_617_8, -0.004_072_047],
+    [1.977_998_5, -2.428_592_2, 0.450_593_7],
+    [0.025_904_037, 0.782_771_77, -0.808_675_77],
+]);
+
+impl ColorSpace {
+    /// Returns the white that this space's XYZ values are relative to.
+    pub fn whitepoint(&self) -> Whitepoint {
+        match self {
+            ColorSpace::Rgb(space) | ColorSpace::Hwb(space) => space.whitepoint,
+            ColorSpace::Yuv(space) => space.rgb().whitepoint,
+            ColorSpace::Xyz(whitepoint)
+            | ColorSpace::Xyy(whitepoint)
+            | ColorSpace::CieLab(whitepoint) => *whitepoint,
+            ColorSpace::Oklab | ColorSpace::Oklch | ColorSpace::Okhsl | ColorSpace::Okhsv => {
+                Whitepoint::D65
+            }
+        }
+    }
+
+    /// Returns the luminance of the darkest color this space can encode,
+    /// relative to its white's luminance of one.
+    ///
+    /// This is the decoded value of an encoded zero for spaces built on RGB,
+    /// and zero for the rest. Every built-in transfer decodes zero to zero or
+    /// below, which is taken as zero, so only a [`Transfer::Custom`] curve
+    /// with a raised black gives a black above zero.
+    pub fn black_luminance(&self) -> f32 {
+        let transfer = match self {
+            ColorSpace::Rgb(space) | ColorSpace::Hwb(space) => space.transfer,
+            ColorSpace::Yuv(space) => space.rgb().transfer,
+            _ => return 0.0,
+        };
+        transfer.to_optical_display([0.0, 0.0, 0.0, 1.0])[1].max(0.0)
+    }
+
+    /// Looks up a color space by its CSS Color 4 name, such as `"srgb"`,
+    /// `"display-p3"` or `"oklch"`, ignoring ASCII case.
+    ///
+    /// `"lab"` and `"xyz-d50"` are relative to D50 as in CSS, and `"xyz"` is
+    /// the same as `"xyz-d65"`. The video spaces without a CSS name are
+    /// available as `"bt601-525"`, `"bt601-625"`, `"bt709"` and `"dci-p3"`.
+    pub fn from_name(name: &str) -> Option<ColorSpace> {
+        const NAMES: [(&str, ColorSpace); 15] = [
+            ("srgb", ColorSpace::Rgb(RgbColorSpace::SRGB)),
+            ("srgb-linear", ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB)),
+            ("display-p3", ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3)),
+            ("rec2020", ColorSpace::Rgb(RgbColorSpace::BT2020)),
+            ("bt601-525", ColorSpace::Rgb(RgbColorSpace::BT601_525)),
+            ("bt601-625", ColorSpace::Rgb(RgbColorSpace::BT601_625)),
+            ("bt709", ColorSpace::Rgb(RgbColorSpace::BT709)),
+            ("dci-p3", ColorSpace::Rgb(RgbColorSpace::DCI_P3)),
+            ("xyz", ColorSpace::Xyz(Whitepoint::D65)),
+            ("xyz-d65", ColorSpace::Xyz(Whitepoint::D65)),
+            ("xyz-d50", ColorSpace::Xyz(Whitepoint::D50)),
+            ("hwb", ColorSpace::Hwb(RgbColorSpace::SRGB)),
+            ("lab", ColorSpace::CieLab(Whitepoint::D50)),
+            ("oklab", ColorSpace::Oklab),
+            ("oklch", ColorSpace::Oklch),
+        ];
+
+        NAMES
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, space)| *space)
+    }
+
+    /// Returns the space this space's values are linear light in: the RGB
+    /// space itself with a linear transfer for spaces built on RGB, and XYZ
+    /// relative to [`Self::whitepoint`] for the rest.
+    pub fn linear(&self) -> ColorSpace {
+        match self {
+            ColorSpace::Rgb(space) | ColorSpace::Hwb(space) => ColorSpace::Rgb(RgbColorSpace {
+                transfer: Transfer::Linear,
+                ..*space
+            }),
+            ColorSpace::Yuv(space) => ColorSpace::Rgb(RgbColorSpace {
+                transfer: Transfer::Linear,
+                ..*space.rgb()
+            }),
+            _ => ColorSpace::Xyz(self.whitepoint()),
+        }
+    }
+
+    /// Returns the steps taking a pixel in this space to XYZ relative to
+    /// [`Self::whitepoint`].
+    pub(crate) fn steps_to_xyz(&self) -> Vec<Step> {
+        match self {
+            ColorSpace::Rgb(space) => vec![
+                Step::DecodeTransfer(space.transfer),
+                Step::Matrix(space.to_xyz_matrix()),
+            ],
+            ColorSpace::Yuv(space) => {
+                let mut steps = vec![Step::Matrix(space.differencing().yuv_to_rgb_matrix())];
+                steps.extend(ColorSpace::Rgb(*space.rgb()).steps_to_xyz());
+                steps
+            }
+            ColorSpace::Hwb(space) => {
+                let mut steps = vec![Step::HwbToRgb];
+                steps.extend(ColorSpace::Rgb(*space).steps_to_xyz());
+                steps
+            }
+            ColorSpace::Xyz(_) => vec![],
+            ColorSpace::Xyy(_) => vec![Step::XyyToXyz],
+            ColorSpace::CieLab(whitepoint) => vec![Step::LabToXyz(*whitepoint)],
+            ColorSpace::Oklab => oklab_steps_to_xyz(ConeResponse::OklabM1),
+            ColorSpace::Oklch => {
+                let mut steps = vec![Step::LchToLab];
+                steps.extend(ColorSpace::Oklab.steps_to_xyz());
+                steps
+            }
+            ColorSpace::Okhsl => {
+                let mut steps = vec![Step::OkhslToOklab];
+                steps.extend(ColorSpace::Oklab.steps_to_xyz());
+                steps
+            }
+            ColorSpace::Okhsv => {
+                let mut steps = vec![Step::OkhsvToOklab];
+                steps.extend(ColorSpace::Oklab.steps_to_xyz());
+                steps
+            }
+        }
+    }
+
+    /// Returns the steps taking XYZ relative to [`Self::whitepoint`] into this
+    /// space.
+    pub(crate) fn steps_from_xyz(&self) -> Vec<Step> {
+        match self {
+            ColorSpace::Rgb(space) => vec![
+                Step::Matrix(space.from_xyz_matrix()),
+                Step::EncodeTransfer(space.transfer),
+            ],
+            ColorSpace::Yuv(space) => {
+                let mut steps = ColorSpace::Rgb(*space.rgb()).steps_from_xyz();
+                steps.push(Step::Matrix(space.differencing().rgb_to_yuv_matrix()));
+                steps
+            }
+            ColorSpace::Hwb(space) => {
+                let mut steps = ColorSpace::Rgb(*space).steps_from_xyz();
+                steps.push(Step::RgbToHwb);
+                steps
+            }
+            ColorSpace::Xyz(_) => vec![],
+            ColorSpace::Xyy(whitepoint) => vec![Step::XyzToXyy(*whitepoint)],
+            ColorSpace::CieLab(whitepoint) => vec![Step::XyzToLab(*whitepoint)],
+            ColorSpace::Oklab => oklab_steps_from_xyz(ConeResponse::OklabM1),
+            ColorSpace::Oklch => {
+                let mut steps = ColorSpace::Oklab.steps_from_xyz();
+                steps.push(Step::LabToLch);
+                steps
+            }
+            ColorSpace::Okhsl => {
+                let mut steps = ColorSpace::Oklab.steps_from_xyz();
+                steps.push(Step::OklabToOkhsl);
+                steps
+            }
+            ColorSpace::Okhsv => {
+                let mut steps = ColorSpace::Oklab.steps_from_xyz();
+                steps.push(Step::OklabToOkhsv);
+                steps
+            }
+        }
+    }
+
+    /// Converts `pixel` in this space to XYZ relative to [`Self::whitepoint`].
+    #[must_use]
+    pub fn to_xyz(&self, pixel: [f32; 4]) -> [f32; 4] {
+        self.steps_to_xyz()
+            .iter()
+            .fold(pixel, |pixel, step| step.apply(pixel))
+    }
+
+    /// Converts `xyz` relative to [`Self::whitepoint`] into this space.
+    #[must_use]
+    pub fn from_xyz(&self, xyz: [f32; 4]) -> [f32; 4] {
+        self.steps_from_xyz()
+            .iter()
+            .fold(xyz, |pixel, step| step.apply(pixel))
+    }
+}
+
+/// Returns the matrix from XYZ relative to D65 to the cone responses of an
+/// Oklab-like space using `cones`, scaled so that D65 has no chroma.
+fn oklab_cone_matrix(cones: ConeResponse) -> RowMatrix {
+    match cones {
+        ConeResponse::OklabM1 => OKLAB_M1,
+        _ => cones.matrix_normalized_to(Whitepoint::D65),
+    }
+}
+
+fn oklab_steps_to_xyz(cones: ConeResponse) -> Vec<Step> {
+    vec![
+        Step::Matrix(OKLAB_M2.inv()),
+        Step::Cube,
+        Step::Matrix(oklab_cone_matrix(cones).inv()),
+    ]
+}
+
+fn oklab_steps_from_xyz(cones: ConeResponse) -> Vec<Step> {
+    vec![
+        Step::Matrix(oklab_cone_matrix(cones)),
+        Step::Cbrt,
+        Step::Matrix(OKLAB_M2),
+    ]
+}
+
+/// Converts `xyz` relative to D65 to an Oklab-like space that compresses the
+/// cone responses of `cones` instead of Oklab's own, for comparing how much
+/// the choice of cone space matters.
+///
+/// With [`ConeResponse::OklabM1`] this is exactly [`ColorSpace::Oklab`].
+pub fn xyz_to_oklab_with_cones(xyz: [f32; 4], cones: ConeResponse) -> [f32; 4] {
+    oklab_steps_from_xyz(cones)
+        .iter()
+        .fold(xyz, |pixel, step| step.apply(pixel))
+}
+
+/// Converts from the Oklab-like space of [`xyz_to_oklab_with_cones`] back to
+/// XYZ relative to D65.
+pub fn oklab_with_cones_to_xyz(lab: [f32; 4], cones: ConeResponse) -> [f32; 4] {
+    oklab_steps_to_xyz(cones)
+        .iter()
+        .fold(lab, |pixel, step| step.apply(pixel))
+}
+
+/// Converts `pixel` from `from` to `to`, adapting between their whitepoints
+/// with the Bradford transform if they differ.
+///
+/// When converting many pixels between the same spaces build a
+/// [`ConversionPlan`] once instead.
+pub fn convert(pixel: [f32; 4], from: &ColorSpace, to: &ColorSpace) -> [f32; 4] {
+    ConversionPlan::new(from, to).apply(pixel)
+}
+
+/// Decodes `pixel` in `space` to linear light in [`ColorSpace::linear`].
+pub fn to_linear(pixel: [f32; 4], space: &ColorSpace) -> [f32; 4] {
+    convert(pixel, space, &space.linear())
+}
+
+/// Encodes `linear`, in [`ColorSpace::linear`] of `space`, as a pixel in
+/// `space`, inverting [`to_linear`]. This makes pixels with chosen linear
+/// values, such as a mid gray of `0.5` in linear light, without working out
+/// their encoding by hand.
+pub fn encode_linear(linear: [f32; 4], space: &ColorSpace) -> [f32; 4] {
+    convert(linear, &space.linear(), space)
+}
+
+/// Converts `pixel` from `from` to `to` following `intent`.
+pub fn convert_with_intent(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    to: &ColorSpace,
+    intent: RenderingIntent,
+) -> [f32; 4] {
+    ConversionPlan::with_intent(from, to, intent).apply(pixel)
+}
+
+/// Converts `pixel` from `from` to `to` following `options`.
+pub fn convert_with_options(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    to: &ColorSpace,
+    options: ConversionOptions,
+) -> [f32; 4] {
+    ConversionPlan::with_options(from, to, options).apply(pixel)
+}
+
+/// Converts `pixel` from `from` to `to` by way of `intermediate`, as two
+/// chained [`ConversionPlan`]s rather than one through XYZ.
+///
+/// Without anything done in between this matches [`convert`] up to
+/// rounding, unless the intermediate clips or quantizes. To blur, blend or
+/// otherwise work on the pixels in `intermediate`, plan the two halves
+/// separately and work between them.
+pub fn convert_via(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    intermediate: &ColorSpace,
+    to: &ColorSpace,
+) -> [f32; 4] {
+    let into = ConversionPlan::new(from, intermediate);
+    let out_of = ConversionPlan::new(intermediate, to);
+    out_of.apply(into.apply(pixel))
+}
+
+/// Converts `pixel` from `from` to `to` like [`convert`], returning an error
+/// instead of a NaN or infinite result, or when a transfer is given a value
+/// outside its domain as with [`convert_checked`], or when either space is
+/// degenerate as [`ConversionPlan::try_new`] checks. This never panics
+/// whatever the input.
+pub fn try_convert(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    to: &ColorSpace,
+) -> Result<[f32; 4], ConvertError> {
+    ConversionPlan::try_new(from, to)?.try_apply(pixel)
+}
+
+/// The luminance XYZ values are normalized to for a perfect white.
+///
+/// This crate works with [`YScale::Unit`], while CIE publications and many
+/// color science references use [`YScale::Hundred`]. Mixing them up gives
+/// CIELAB lightness that is off by a factor of `100^(1/3)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum YScale {
+    /// White has `Y = 1`.
+    #[default]
+    Unit,
+    /// White has `Y = 100`.
+    Hundred,
+}
+
+impl YScale {
+    /// Returns the `Y` of white on this scale.
+    pub fn white_luminance(self) -> f32 {
+        match self {
+            YScale::Unit => 1.0,
+            YScale::Hundred => 100.0,
+        }
+    }
+}
+
+/// The luminance of a space's reference white, which a white of `Y = 1` is
+/// shown at.
+///
+/// Giving a conversion different references with [`ConversionOptions`]
+/// keeps absolute luminance by scaling linear values by the ratio of their
+/// whites, so the brighter reference gives smaller values for the same
+/// light.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Luminance {
+    /// The 100 cd/m² of SDR video and the web.
+    #[default]
+    Sdr,
+    /// The 160 cd/m² reference display white of Adobe RGB (1998).
+    AdobeRgb,
+    /// The 1000 cd/m² peak of the P3 displays HDR content is mastered on.
+    /// Theatrical DCI projection is far dimmer, at 48 cd/m².
+    DciP3,
+}
+
+impl Luminance {
+    /// Returns the luminance of the reference white in cd/m², or nits.
+    pub fn to_nits(self) -> f32 {
+        match self {
+            Luminance::Sdr => 100.0,
+            Luminance::AdobeRgb => 160.0,
+            Luminance::DciP3 => 1000.0,
+        }
+    }
+}
+
+/// Converts the relative luminance `y` from a space with the reference white
+/// `from` to one with `to`, keeping its absolute luminance, as
+/// [`ConversionOptions`] does for whole colors.
+///
+/// Chromaticity is unaffected by the reference, so this is all there is to
+/// converting luminance alone, such as for exposure metadata. The references
+/// are given directly because an [`RgbColorSpace`] doesn't carry one.
+pub fn convert_luminance(y: f32, from: Luminance, to: Luminance) -> f32 {
+    y * from.to_nits() / to.to_nits()
+}
+
+/// Rescales the XYZ channels of `xyz` from `from` to `to`, leaving alpha
+/// untouched.
+pub fn rescale_xyz(xyz: [f32; 4], from: YScale, to: YScale) -> [f32; 4] {
+    let scale = to.white_luminance() / from.white_luminance();
+    let [x, y, z, alpha] = xyz;
+    [x * scale, y * scale, z * scale, alpha]
+}
+
+/// Converts `xyz` on `scale` relative to `whitepoint` to CIELAB.
+pub fn xyz_to_cielab(xyz: [f32; 4], whitepoint: Whitepoint, scale: YScale) -> [f32; 4] {
+    xyz_to_lab(rescale_xyz(xyz, scale, YScale::Unit), whitepoint)
+}
+
+/// Converts CIELAB relative to `whitepoint` to XYZ on `scale`.
+pub fn cielab_to_xyz(lab: [f32; 4], whitepoint: Whitepoint, scale: YScale) -> [f32; 4] {
+    rescale_xyz(lab_to_xyz(lab, whitepoint), YScale::Unit, scale)
+}
+
+/// Converts `oklab` to CIELAB relative to `whitepoint` through XYZ.
+///
+/// Oklab is defined relative to D65, so for any other white the color is
+/// adapted with the Bradford transform, and Oklab white always becomes
+/// `L* = 100` with no chroma.
+pub fn oklab_to_cielab(oklab: [f32; 4], whitepoint: Whitepoint) -> [f32; 4] {
+    convert(oklab, &ColorSpace::Oklab, &ColorSpace::CieLab(whitepoint))
+}
+
+/// Converts CIELAB relative to `whitepoint` to Oklab, the inverse of
+/// [`oklab_to_cielab`].
+pub fn cielab_to_oklab(lab: [f32; 4], whitepoint: Whitepoint) -> [f32; 4] {
+    convert(lab, &ColorSpace::CieLab(whitepoint), &ColorSpace::Oklab)
+}
+
+/// Converts `pixel` from `from` to `to` like [`convert`], returning an error
+/// if a transfer would decode a value outside its
+/// [`Transfer::signal_range`](crate::transfer::Transfer::signal_range), such
+/// as `2.0` in sRGB, or encode one outside its
+/// [`Transfer::linear_range`](crate::transfer::Transfer::linear_range), such
+/// as negative light in a log curve.
+pub fn convert_checked(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    to: &ColorSpace,
+) -> Result<[f32; 4], DomainError> {
+    ConversionPlan::new(from, to).apply_checked(pixel)
+}
+
+/// Converts `xyz` to xyY, giving black the chromaticity of `whitepoint`.
+pub fn xyz_to_xyy([x, y, z, alpha]: [f32; 4], whitepoint: Whitepoint) -> [f32; 4] {
+    let sum = x + y + z;
+    if sum == 0.0 {
+        let [white_x, white_y] = whitepoint.to_xy();
+        return [white_x, white_y, y, alpha];
+    }
+
+    [x / sum, y / sum, y, alpha]
+}
+
+/// Converts `xyy` to XYZ. A chromaticity with `y = 0` only describes black,
+/// which this returns.
+pub fn xyy_to_xyz([x, y, big_y, alpha]: [f32; 4]) -> [f32; 4] {
+    if y == 0.0 {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+
+    let scale = big_y / y;
+    [x * scale, big_y, (1.0 - x - y) * scale, alpha]
+}
+
+const LAB_EPSILON: f32 = 216.0 / 24389.0;
+const LAB_KAPPA: f32 = 24389.0 / 27.0;
+
+pub(crate) fn xyz_to_lab([x, y, z, alpha]: [f32; 4], whitepoint: Whitepoint) -> [f32; 4] {
+    let white = whitepoint.to_xyz();
+    let f = |t: f32| {
+        if t > LAB_EPSILON {
+            t.cbrt()
+        } else {
+            (LAB_KAPPA * t + 16.0) / 116.0
+        }
+    };
+    let [fx, fy, fz] = [x / white[0], y / white[1], z / white[2]].map(f);
+
+    [
+        116.0 * fy - 16.0,
+        500.0 * (fx - fy),
+        200.0 * (fy - fz),
+        alpha,
+    ]
+}
+
+pub(crate) fn lab_to_xyz([l, a, b, alpha]: [f32; 4], whitepoint: Whitepoint) -> [f32; 4] {
+    let white = whitepoint.to_xyz();
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let f_inv = |t: f32| {
+        if t * t * t > LAB_EPSILON {
+            t * t * t
+        } else {
+            (116.0 * t - 16.0) / LAB_KAPPA
+        }
+    };
+    let y = if l > LAB_KAPPA * LAB_EPSILON {
+        fy * fy * fy
+    } else {
+        l / LAB_KAPPA
+    };
+
+    [
+        f_inv(fx) * white[0],
+        y * white[1],
+        f_inv(fz) * white[2],
+        alpha,
+    ]
+}
+
+pub(crate) fn lab_to_lch([l, a, b, alpha]: [f32; 4]) -> [f32; 4] {
+    [
+        l,
+        a.hypot(b),
+        b.atan2(a).to_degrees().rem_euclid(360.0),
+        alpha,
+    ]
+}
+
+/// Converts RGB to `[H, S, V]` hue, saturation and value, with the hue in
+/// degrees. Grays have a hue of zero.
+pub fn rgb_to_hsv([r, g, b, alpha]: [f32; 4]) -> [f32; 4] {
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    let sector = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+
+    [sector * 60.0, saturation, max, alpha]
+}
+
+/// Converts `[H, S, V]` to RGB, inverting [`rgb_to_hsv`].
+pub fn hsv_to_rgb([h, s, v, alpha]: [f32; 4]) -> [f32; 4] {
+    let channel = |n: f32| {
+        let k = (n + h / 60.0).rem_euclid(6.0);
+        v - v * s * k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    [channel(5.0), channel(3.0), channel(1.0), alpha]
+}
+
+/// Converts `[H, S, V]` to `[H, W, B]` hue, whiteness and blackness.
+pub fn hsv_to_hwb([h, s, v, alpha]: [f32; 4]) -> [f32; 4] {
+    [h, (1.0 - s) * v, 1.0 - v, alpha]
+}
+
+/// Converts `[H, W, B]` to `[H, S, V]`.
+///
+/// Whiteness and blackness adding up to more than one describe a gray, and
+/// are scaled down to add up to one as CSS Color 4 specifies.
+pub fn hwb_to_hsv([h, w, b, alpha]: [f32; 4]) -> [f32; 4] {
+    let sum = w + b;
+    let (w, b) = if sum > 1.0 {
+        (w / sum, b / sum)
+    } else {
+        (w, b)
+    };
+    let v = 1.0 - b;
+    let s = if v == 0.0 { 0.0 } else { 1.0 - w / v };
+    [h, s, v, alpha]
+}
+
+pub(crate) fn lch_to_lab([l, c, h, alpha]: [f32; 4]) -> [f32; 4] {
+    let (sin, cos) = h.to_radians().sin_cos();
+    [l, c * cos, c * sin, alpha]
+}
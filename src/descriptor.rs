@@ -0,0 +1,199 @@
+//! Conversion plans described by a small JSON or TOML document, so that
+//! conversions can be chosen in configuration rather than in code.
+//!
+//! A descriptor names the source and destination color spaces as accepted by
+//! [`ColorSpace::from_name`] and optionally a rendering intent, which also
+//! selects how out of gamut colors are mapped:
+//!
+//! ```toml
+//! source = "srgb"
+//! destination = "display-p3"
+//! intent = "perceptual"
+//! ```
+//!
+//! or equivalently
+//!
+//! ```json
+//! { "source": "srgb", "destination": "display-p3", "intent": "perceptual" }
+//! ```
+//!
+//! Only flat documents with string values are accepted.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{
+    color_space::ColorSpace,
+    plan::{ConversionPlan, RenderingIntent},
+};
+
+/// Why a descriptor could not be turned into a [`ConversionPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// The document is not a flat JSON object or TOML table of strings. The
+    /// line is counted from 1.
+    Syntax { line: usize },
+    /// A key other than `source`, `destination` or `intent`.
+    UnknownKey(String),
+    /// A key given more than once.
+    DuplicateKey(String),
+    /// A required key that is missing.
+    MissingKey(&'static str),
+    /// A name [`ColorSpace::from_name`] doesn't know.
+    UnknownColorSpace(String),
+    /// An intent other than `absolute`, `relative-colorimetric`, `perceptual`
+    /// or `saturation`.
+    UnknownIntent(String),
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorError::Syntax { line } => write!(f, "syntax error on line {line}"),
+            DescriptorError::UnknownKey(key) => write!(f, "unknown key `{key}`"),
+            DescriptorError::DuplicateKey(key) => write!(f, "key `{key}` is given more than once"),
+            DescriptorError::MissingKey(key) => write!(f, "missing key `{key}`"),
+            DescriptorError::UnknownColorSpace(name) => write!(f, "unknown color space `{name}`"),
+            DescriptorError::UnknownIntent(name) => write!(f, "unknown rendering intent `{name}`"),
+        }
+    }
+}
+
+impl core::error::Error for DescriptorError {}
+
+impl ConversionPlan {
+    /// Plans the conversion described by `descriptor`, a JSON object if it
+    /// starts with `{` and a TOML table otherwise. See the
+    /// [module documentation](crate::descriptor) for the format.
+    pub fn from_descriptor(descriptor: &str) -> Result<ConversionPlan, DescriptorError> {
+        let entries = if descriptor.trim_start().starts_with('{') {
+            parse_json(descriptor)?
+        } else {
+            parse_toml(descriptor)?
+        };
+
+        let mut source = None;
+        let mut destination = None;
+        let mut intent = None;
+        for (key, value) in entries {
+            let slot = match key.as_str() {
+                "source" => &mut source,
+                "destination" => &mut destination,
+                "intent" => &mut intent,
+                _ => return Err(DescriptorError::UnknownKey(key)),
+            };
+            if slot.replace(value).is_some() {
+                return Err(DescriptorError::DuplicateKey(key));
+            }
+        }
+
+        let color_space = |name: Option<String>, key| {
+            let name = name.ok_or(DescriptorError::MissingKey(key))?;
+            ColorSpace::from_name(&name).ok_or(DescriptorError::UnknownColorSpace(name))
+        };
+        let source = color_space(source, "source")?;
+        let destination = color_space(destination, "destination")?;
+        let intent = match intent.as_deref() {
+            None => RenderingIntent::default(),
+            Some("absolute") => RenderingIntent::Absolute,
+            Some("relative-colorimetric") => RenderingIntent::RelativeColorimetric,
+            Some("perceptual") => RenderingIntent::Perceptual,
+            Some("saturation") => RenderingIntent::Saturation,
+            Some(name) => return Err(DescriptorError::UnknownIntent(name.to_string())),
+        };
+
+        Ok(ConversionPlan::with_intent(&source, &destination, intent))
+    }
+}
+
+/// Splits a TOML document of `key = "value"` lines into its entries.
+fn parse_toml(descriptor: &str) -> Result<Vec<(String, String)>, DescriptorError> {
+    let mut entries = Vec::new();
+    for (index, line) in descriptor.lines().enumerate() {
+        let syntax = DescriptorError::Syntax { line: index + 1 };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, rest) = line.split_once('=').ok_or(syntax.clone())?;
+        let key = key.trim();
+        let is_bare_key = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !is_bare_key {
+            return Err(syntax);
+        }
+
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''));
+        let quote = quote.ok_or(syntax.clone())?;
+        let (value, rest) = rest[1..].split_once(quote).ok_or(syntax.clone())?;
+        let rest = rest.trim_start();
+        if value.contains('\\') || !(rest.is_empty() || rest.starts_with('#')) {
+            return Err(syntax);
+        }
+
+        entries.push((key.to_string(), value.to_string()));
+    }
+    Ok(entries)
+}
+
+/// Splits a JSON object whose values are all strings into its entries.
+fn parse_json(descriptor: &str) -> Result<Vec<(String, String)>, DescriptorError> {
+    let mut rest = descriptor.trim_start();
+    let syntax = |rest: &str| DescriptorError::Syntax {
+        line: descriptor[..descriptor.len() - rest.len()]
+            .matches('\n')
+            .count()
+            + 1,
+    };
+
+    // Strips `token` and any whitespace after it from the front of `rest`.
+    let expect = |rest: &mut &str, token: char| match rest.strip_prefix(token) {
+        Some(after) => {
+            *rest = after.trim_start();
+            Ok(())
+        }
+        None => Err(syntax(rest)),
+    };
+    // Strips a string without escapes from the front of `rest` and returns
+    // its contents.
+    let string = |rest: &mut &str| {
+        let (value, after) = rest
+            .strip_prefix('"')
+            .and_then(|after| after.split_once('"'))
+            .filter(|(value, _)| !value.contains('\\'))
+            .ok_or_else(|| syntax(rest))?;
+        *rest = after.trim_start();
+        Ok(value.to_string())
+    };
+
+    let mut entries = Vec::new();
+    expect(&mut rest, '{')?;
+    if rest.starts_with('}') {
+        expect(&mut rest, '}')?;
+    } else {
+        loop {
+            let key = string(&mut rest)?;
+            expect(&mut rest, ':')?;
+            entries.push((key, string(&mut rest)?));
+            if rest.starts_with(',') {
+                expect(&mut rest, ',')?;
+            } else {
+                expect(&mut rest, '}')?;
+                break;
+            }
+        }
+    }
+
+    if rest.is_empty() {
+        Ok(entries)
+    } else {
+        Err(syntax(rest))
+    }
+}
@@ -0,0 +1,327 @@
+//! Heuristics for catching common mistakes with image data, perceptual
+//! thresholds for analyzing it, and plots for inspecting color spaces.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    color_space::{convert, ColorSpace},
+    plan::ConversionPlan,
+    rgb_color_space::RgbColorSpace,
+    spectral::spectral_locus,
+    transfer::{transfer_eo_srgb, transfer_oe_srgb, Transfer},
+    whitepoint::Whitepoint,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// How many times image data has been encoded with the sRGB curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodedState {
+    /// Linear light, never encoded.
+    Linear,
+    /// Encoded once, as it should be for display.
+    Srgb,
+    /// Encoded twice, which looks washed out and too bright.
+    DoubleSrgb,
+}
+
+/// The most likely [`EncodedState`] of an image and how sure the guess is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncodingGuess {
+    pub state: EncodedState,
+    /// From `0.0` when the alternatives are equally likely to `1.0` when the
+    /// guess fits the image exactly.
+    pub confidence: f32,
+}
+
+/// The mean sRGB encoded luma expected of a typical image, that of the 18%
+/// gray that scenes average out to.
+const EXPECTED_MEAN: f32 = 0.46;
+
+/// Guesses whether `image` holds linear light, sRGB encoded values or values
+/// encoded with sRGB twice, which is a common cause of washed out images.
+///
+/// Each possibility is tested by decoding the image's luma accordingly and
+/// comparing its mean after a single encode to the middle gray that scenes
+/// average out to. This works well for photographs and other natural images,
+/// but images that are mostly dark or mostly bright by design will be
+/// misjudged, so treat a low confidence as no answer at all. An empty image
+/// gives [`EncodedState::Srgb`] with no confidence.
+pub fn estimate_encoded_state(image: &[[f32; 4]]) -> EncodingGuess {
+    if image.is_empty() {
+        return EncodingGuess {
+            state: EncodedState::Srgb,
+            confidence: 0.0,
+        };
+    }
+
+    let mut sums = [0.0_f64; 3];
+    for [r, g, b, _] in image {
+        let luma = (0.2126 * r + 0.7152 * g + 0.0722 * b).clamp(0.0, 1.0);
+        // Each state's luma as it would look encoded once.
+        sums[0] += f64::from(transfer_oe_srgb(luma));
+        sums[1] += f64::from(luma);
+        sums[2] += f64::from(transfer_eo_srgb(luma));
+    }
+
+    let states = [
+        EncodedState::Linear,
+        EncodedState::Srgb,
+        EncodedState::DoubleSrgb,
+    ];
+    let mut distances: [(EncodedState, f32); 3] = core::array::from_fn(|i| {
+        let mean = (sums[i] / image.len() as f64) as f32;
+        (states[i], (mean - EXPECTED_MEAN).abs())
+    });
+    distances.sort_unstable_by(|a, b| a.1.total_cmp(&b.1));
+
+    let (state, best) = distances[0];
+    let second = distances[1].1;
+    EncodingGuess {
+        state,
+        confidence: if second > 0.0 {
+            (second - best) / (second + best)
+        } else {
+            0.0
+        },
+    }
+}
+
+/// The pixels of an image found by [`detect_clipping`] at the ends of the
+/// range.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClippingMask {
+    /// The number of pixels with a color channel at or near `1.0`, such as
+    /// blown highlights.
+    pub clipped: usize,
+    /// The number of pixels with a color channel at or near `0.0`, such as
+    /// crushed shadows.
+    pub crushed: usize,
+    /// Whether each pixel of the image is clipped, crushed or both, in the
+    /// same order.
+    pub mask: Vec<bool>,
+}
+
+/// Finds the pixels of `image` with a color channel at most `threshold`
+/// from either end of `[0, 1]`, where detail has probably been lost before
+/// the image reached you. Alpha is ignored.
+///
+/// A saturated color can be both clipped in one channel and crushed in
+/// another, and counts as both. A threshold of zero only finds channels
+/// exactly at or beyond the ends.
+pub fn detect_clipping(image: &[[f32; 4]], threshold: f32) -> ClippingMask {
+    let mut result = ClippingMask {
+        mask: Vec::with_capacity(image.len()),
+        ..ClippingMask::default()
+    };
+    for [r, g, b, _] in image {
+        let max = r.max(*g).max(*b);
+        let min = r.min(*g).min(*b);
+        let clipped = max >= 1.0 - threshold;
+        let crushed = min <= threshold;
+        result.clipped += usize::from(clipped);
+        result.crushed += usize::from(crushed);
+        result.mask.push(clipped || crushed);
+    }
+    result
+}
+
+/// The change in a channel used to estimate how fast CIELAB changes with it
+/// in [`jnd_step`].
+const JND_PROBE: f32 = 1e-3;
+
+/// Returns the change in each channel of `color` in `space` that moves it by
+/// roughly one just noticeable difference, a CIELAB ΔE of 1 relative to the
+/// space's white.
+///
+/// Banding appears where neighboring codes are more than a step apart, and
+/// dither noise can be this large without being seen. Each step is found
+/// from how fast CIELAB changes as that channel alone changes near `color`,
+/// so it is only accurate for small steps, and a channel that doesn't change
+/// the color there, such as the hue of a gray, has an infinite step. Alpha
+/// isn't a color channel and its step is always zero.
+pub fn jnd_step(color: [f32; 4], space: &ColorSpace) -> [f32; 4] {
+    let lab_space = ColorSpace::CieLab(space.whitepoint());
+    let plan = ConversionPlan::new(space, &lab_space);
+    let lab = plan.apply(color);
+
+    let mut steps = [0.0; 4];
+    for (channel, step) in steps.iter_mut().enumerate().take(3) {
+        // Probe toward the middle of the usual range so as not to step past
+        // the clamping of a transfer.
+        let probe = if color[channel] > 0.5 {
+            -JND_PROBE
+        } else {
+            JND_PROBE
+        };
+        let mut probed = color;
+        probed[channel] += probe;
+        let probed = plan.apply(probed);
+
+        let delta_e = (0..3)
+            .map(|c| (probed[c] - lab[c]).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        *step = if delta_e > 0.0 {
+            JND_PROBE / delta_e
+        } else {
+            f32::INFINITY
+        };
+    }
+    steps
+}
+
+/// A known conversion that [`self_test`] found to give the wrong result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTestError {
+    /// What was converted.
+    pub check: &'static str,
+    pub expected: [f32; 4],
+    pub actual: [f32; 4],
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gave {:?} instead of {:?}",
+            self.check, self.actual, self.expected
+        )
+    }
+}
+
+impl core::error::Error for SelfTestError {}
+
+/// The largest relative error [`self_test`] allows, far more than rounding
+/// error but far less than the error of a broken power or logarithm.
+const SELF_TEST_TOLERANCE: f32 = 1e-4;
+
+/// Runs a handful of conversions with published results, returning the
+/// first that is wrong.
+///
+/// This is a quick check that the floating point behavior of the build is
+/// sound, such as that fast math optimizations or a `libm` port haven't
+/// broken the transfers, for running once at startup or in a health check.
+pub fn self_test() -> Result<(), SelfTestError> {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let white = [1.0, 1.0, 1.0, 1.0];
+    let red = [1.0, 0.0, 0.0, 1.0];
+    let gray = Transfer::Srgb.from_optical_display([0.18, 0.18, 0.18, 1.0]);
+    let oklab_gray = convert(gray, &srgb, &ColorSpace::Oklab);
+
+    let checks = [
+        (
+            "sRGB white to XYZ",
+            convert(white, &srgb, &ColorSpace::Xyz(Whitepoint::D65)),
+            [0.950_456, 1.0, 1.089_058, 1.0],
+        ),
+        (
+            "sRGB encoding of 18% gray",
+            gray,
+            [0.461_356, 0.461_356, 0.461_356, 1.0],
+        ),
+        (
+            "18% gray round trip through Oklab",
+            convert(oklab_gray, &ColorSpace::Oklab, &srgb),
+            gray,
+        ),
+        (
+            "sRGB red to Oklab",
+            convert(red, &srgb, &ColorSpace::Oklab),
+            [0.627_955, 0.224_863, 0.125_846, 1.0],
+        ),
+        (
+            "sRGB red to CIELAB",
+            convert(red, &srgb, &ColorSpace::CieLab(Whitepoint::D50)),
+            [54.290_54, 80.804_95, 69.890_96, 1.0],
+        ),
+    ];
+
+    for (check, actual, expected) in checks {
+        let close = actual
+            .iter()
+            .zip(expected)
+            .all(|(a, e)| (a - e).abs() <= SELF_TEST_TOLERANCE * e.abs().max(1.0));
+        if !close {
+            return Err(SelfTestError {
+                check,
+                expected,
+                actual,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The largest chromaticities shown by [`plot_gamuts_svg`], which hold the
+/// whole spectral locus.
+const PLOT_XY_MAX: [f32; 2] = [0.8, 0.9];
+
+/// The size in SVG user units of a chromaticity difference of one.
+const PLOT_SCALE: f32 = 1000.0;
+
+/// The colors spaces are drawn in, repeating if there are more spaces.
+const PLOT_COLORS: [&str; 6] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4",
+];
+
+/// Returns the position in the plot of the chromaticity `[x, y]`, with `y`
+/// increasing upward.
+fn plot_position([x, y]: [f32; 2]) -> [f32; 2] {
+    [x * PLOT_SCALE, (PLOT_XY_MAX[1] - y) * PLOT_SCALE]
+}
+
+/// Writes `chromaticities` as the `points` attribute of an SVG polygon.
+fn write_points(
+    writer: &mut impl fmt::Write,
+    chromaticities: impl IntoIterator<Item = [f32; 2]>,
+) -> fmt::Result {
+    write!(writer, r#"points=""#)?;
+    for (i, xy) in chromaticities.into_iter().enumerate() {
+        let [x, y] = plot_position(xy);
+        let separator = if i == 0 { "" } else { " " };
+        write!(writer, "{separator}{x:.1},{y:.1}")?;
+    }
+    write!(writer, r#"""#)
+}
+
+/// Writes an SVG plot of the CIE 1931 xy chromaticity diagram to `writer`,
+/// showing the spectral locus closed by the line of purples, and for each
+/// of `spaces` the triangle of its primaries and a dot at its whitepoint.
+///
+/// Each space is drawn in its own color, in the order given. To write to an
+/// `std::io::Write` instead, write to a `String` first.
+pub fn plot_gamuts_svg(spaces: &[&RgbColorSpace], mut writer: impl fmt::Write) -> fmt::Result {
+    let [width, height] = PLOT_XY_MAX.map(|c| c * PLOT_SCALE);
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )?;
+    writeln!(
+        writer,
+        r#"<rect width="{width}" height="{height}" fill="white"/>"#
+    )?;
+
+    write!(writer, r#"<polygon fill="none" stroke="black" "#)?;
+    write_points(&mut writer, spectral_locus())?;
+    writeln!(writer, "/>")?;
+
+    for (space, color) in spaces.iter().zip(PLOT_COLORS.iter().cycle()) {
+        write!(
+            writer,
+            r#"<polygon fill="none" stroke="{color}" stroke-width="2" "#
+        )?;
+        write_points(&mut writer, space.primaries.chromaticities())?;
+        writeln!(writer, "/>")?;
+
+        let [x, y] = plot_position(space.whitepoint.to_xy());
+        writeln!(
+            writer,
+            r#"<circle cx="{x:.1}" cy="{y:.1}" r="5" fill="{color}"/>"#
+        )?;
+    }
+
+    writeln!(writer, "</svg>")
+}
@@ -0,0 +1,135 @@
+//! View transforms taking scene-linear light, such as a renderer's output, to
+//! a display.
+
+use crate::{
+    color_space::ColorSpace,
+    plan::{ConversionPlan, RenderingIntent},
+    rgb_color_space::RgbColorSpace,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// How scene light above the display's white is brought into range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMap {
+    /// Leaves the light unchanged, so anything above white is clipped by
+    /// gamut mapping.
+    None,
+    /// Reinhard's `x / (1 + x)` applied to the largest color channel, with
+    /// the others scaled along so that hue and saturation are kept. Every
+    /// level of light stays distinct and approaches white without reaching
+    /// it.
+    #[default]
+    Reinhard,
+}
+
+impl ToneMap {
+    /// Tone maps the scene-linear color channels of `pixel`, leaving alpha
+    /// untouched.
+    #[must_use]
+    pub fn apply(self, pixel: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, alpha] = pixel;
+        let max = r.max(g).max(b);
+        match self {
+            ToneMap::Reinhard if max > 0.0 => {
+                let gain = 1.0 / (1.0 + max);
+                [r * gain, g * gain, b * gain, alpha]
+            }
+            _ => pixel,
+        }
+    }
+}
+
+/// How colors outside the display's gamut after tone mapping are brought
+/// into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GamutMap {
+    /// Clamps each linear channel to `[0, 1]`, as
+    /// [`RenderingIntent::Saturation`] does.
+    #[default]
+    Clip,
+    /// Desaturates toward the gray of the same luminance, as
+    /// [`RenderingIntent::Perceptual`] does.
+    Desaturate,
+}
+
+impl GamutMap {
+    fn intent(self) -> RenderingIntent {
+        match self {
+            GamutMap::Clip => RenderingIntent::Saturation,
+            GamutMap::Desaturate => RenderingIntent::Perceptual,
+        }
+    }
+}
+
+/// The stages taking scene-linear pixels to encoded display pixels, in the
+/// order they are applied:
+///
+/// 1. Exposure, scaling the light by `2^exposure`.
+/// 2. Tone mapping, in the scene's linear RGB.
+/// 3. Gamut mapping into the display's primaries.
+/// 4. Encoding with the display's transfer.
+///
+/// The default takes linear sRGB to sRGB with no exposure change, Reinhard
+/// tone mapping and clipping, which is a reasonable view of a renderer's
+/// output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayTransform {
+    /// The RGB space of the scene-linear input. Its transfer is ignored, as
+    /// the input is always linear.
+    pub scene: RgbColorSpace,
+    /// The exposure adjustment in stops, positive to brighten.
+    pub exposure: f32,
+    pub tone_map: ToneMap,
+    pub gamut_map: GamutMap,
+    /// The display space the output is encoded in.
+    pub display: RgbColorSpace,
+}
+
+impl Default for DisplayTransform {
+    fn default() -> DisplayTransform {
+        DisplayTransform {
+            scene: RgbColorSpace::LINEAR_SRGB,
+            exposure: 0.0,
+            tone_map: ToneMap::default(),
+            gamut_map: GamutMap::default(),
+            display: RgbColorSpace::SRGB,
+        }
+    }
+}
+
+impl DisplayTransform {
+    /// Returns the plan gamut mapping and encoding tone mapped scene light for
+    /// the display.
+    fn display_plan(&self) -> ConversionPlan {
+        let scene = ColorSpace::Rgb(self.scene).linear();
+        ConversionPlan::with_intent(
+            &scene,
+            &ColorSpace::Rgb(self.display),
+            self.gamut_map.intent(),
+        )
+    }
+
+    fn apply_with(&self, plan: &ConversionPlan, pixel: [f32; 4]) -> [f32; 4] {
+        let gain = self.exposure.exp2();
+        let [r, g, b, alpha] = pixel;
+        plan.apply(self.tone_map.apply([r * gain, g * gain, b * gain, alpha]))
+    }
+
+    /// Takes the scene-linear `pixel` to the display, leaving alpha
+    /// untouched.
+    #[must_use]
+    pub fn apply(&self, pixel: [f32; 4]) -> [f32; 4] {
+        self.apply_with(&self.display_plan(), pixel)
+    }
+
+    /// Takes each scene-linear pixel of `pixels` to the display in place, as
+    /// with [`Self::apply`].
+    pub fn apply_slice(&self, pixels: &mut [[f32; 4]]) {
+        let plan = self.display_plan();
+        for pixel in pixels {
+            *pixel = self.apply_with(&plan, *pixel);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Comparisons between the gamuts of RGB color spaces.
+
+use alloc::vec::Vec;
+
+use crate::{
+    color_space::{convert, convert_with_intent, ColorSpace},
+    plan::{ConversionPlan, RenderingIntent},
+    rgb_color_space::RgbColorSpace,
+    transfer::Transfer,
+};
+
+/// How far outside `[0, 1]` a linear channel may be and still count as in
+/// gamut, absorbing rounding in the conversion from Oklch.
+const GAMUT_EPSILON: f32 = 1e-5;
+
+/// How far below `1.0` a gamut coverage may be and still count as enclosed,
+/// absorbing rounding in the clipping, and the least area a gamut must have
+/// over another to count as larger.
+const COVERAGE_EPSILON: f32 = 1e-4;
+
+/// Returns the area of the polygon with `vertices` in order.
+fn polygon_area(vertices: &[[f32; 2]]) -> f32 {
+    let twice_area: f32 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+/// Returns the vertices of `triangle` counter-clockwise.
+fn counter_clockwise(mut triangle: [[f32; 2]; 3]) -> [[f32; 2]; 3] {
+    let [a, b, c] = triangle;
+    if (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) < 0.0 {
+        triangle.swap(1, 2);
+    }
+    triangle
+}
+
+/// Clips `subject` to the convex counter-clockwise polygon `clip` with the
+/// Sutherland-Hodgman algorithm.
+fn clip_polygon(subject: &[[f32; 2]], clip: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let mut output = subject.to_vec();
+
+    for (&a, &b) in clip.iter().zip(clip.iter().cycle().skip(1)) {
+        let side = |p: [f32; 2]| (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0]);
+        let input = core::mem::take(&mut output);
+
+        for (&p, &q) in input.iter().zip(input.iter().cycle().skip(1)) {
+            let (side_p, side_q) = (side(p), side(q));
+            if side_p >= 0.0 {
+                output.push(p);
+            }
+            if (side_p >= 0.0) != (side_q >= 0.0) {
+                let t = side_p / (side_p - side_q);
+                output.push([p[0] + (q[0] - p[0]) * t, p[1] + (q[1] - p[1]) * t]);
+            }
+        }
+    }
+
+    output
+}
+
+/// Returns the area of the triangle the primaries of `space` span on the CIE
+/// 1931 xy chromaticity diagram.
+pub fn gamut_area(space: &RgbColorSpace) -> f32 {
+    polygon_area(&space.primaries.chromaticities())
+}
+
+/// Returns the fraction of the xy chromaticity area of `outer`'s gamut that
+/// `inner`'s gamut covers, from `0.0` to `1.0`.
+///
+/// For example sRGB covers about 53% of BT.2020 here. The commonly quoted
+/// figure of about 36% is instead sRGB's share of every chromaticity inside
+/// the spectral locus.
+pub fn gamut_coverage(inner: &RgbColorSpace, outer: &RgbColorSpace) -> f32 {
+    let inner = counter_clockwise(inner.primaries.chromaticities());
+    let outer = counter_clockwise(outer.primaries.chromaticities());
+
+    polygon_area(&clip_polygon(&inner, &outer)) / polygon_area(&outer)
+}
+
+/// Returns whether the gamut of `outer` encloses the gamut of `inner` on the
+/// xy chromaticity diagram, allowing for rounding.
+pub fn gamut_encloses(outer: &RgbColorSpace, inner: &RgbColorSpace) -> bool {
+    let inner = counter_clockwise(inner.primaries.chromaticities());
+    let outer = counter_clockwise(outer.primaries.chromaticities());
+
+    polygon_area(&clip_polygon(&inner, &outer)) >= polygon_area(&inner) * (1.0 - COVERAGE_EPSILON)
+}
+
+/// Returns a linear working space whose gamut encloses the gamuts of both
+/// `input` and `output` with room to spare, for intermediate operations such
+/// as grading or compositing that would otherwise clip colors near their
+/// edges.
+///
+/// This is the narrowest of linear BT.2020, ACEScg and ACES2065-1 whose
+/// gamut encloses both and is larger than each, so sRGB in and out gives
+/// BT.2020, and BT.2020 with DCI-P3 gives ACEScg as DCI-P3's red is just
+/// outside BT.2020. Failing that it is the first of them, or of `input` and
+/// `output` made linear, that encloses both at all, and failing that
+/// ACES2065-1. That encloses every visible color, but not the red of ACEScg,
+/// which is slightly outside the spectral locus.
+pub fn recommend_working_space(input: &RgbColorSpace, output: &RgbColorSpace) -> RgbColorSpace {
+    let linear = |space: &RgbColorSpace| RgbColorSpace {
+        transfer: Transfer::Linear,
+        ..*space
+    };
+    let standard = [
+        linear(&RgbColorSpace::BT2020),
+        RgbColorSpace::ACES_CG,
+        RgbColorSpace::ACES2065_1,
+    ];
+    let encloses_both = |candidate: &RgbColorSpace| {
+        gamut_encloses(candidate, input) && gamut_encloses(candidate, output)
+    };
+    let larger_than_both = |candidate: &RgbColorSpace| {
+        let area = gamut_area(candidate) * (1.0 - COVERAGE_EPSILON);
+        gamut_area(input) < area && gamut_area(output) < area
+    };
+
+    standard
+        .into_iter()
+        .find(|candidate| encloses_both(candidate) && larger_than_both(candidate))
+        .or_else(|| {
+            standard
+                .into_iter()
+                .chain([linear(input), linear(output)])
+                .find(encloses_both)
+        })
+        .unwrap_or(RgbColorSpace::ACES2065_1)
+}
+
+/// Converts `pixel` from `from` into `to`, compressing its Oklch chroma
+/// toward the gray of the same lightness and hue so it falls within the gamut
+/// of `to`.
+///
+/// Chroma is measured as a fraction of the gamut boundary's chroma at the
+/// pixel's lightness and hue. Colors below `threshold` of the boundary are
+/// left untouched, and beyond it chroma rolls off smoothly and monotonically,
+/// reaching the boundary at `2.0 - threshold` and staying on it for anything
+/// further out. A `threshold` of `1.0` clips chroma to the boundary instead,
+/// and lower thresholds keep more of the differences between out of gamut
+/// colors at the cost of desaturating more of the gamut. Values outside
+/// `[0, 1]` are clamped.
+pub fn compress_to_gamut(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    to: &RgbColorSpace,
+    threshold: f32,
+) -> [f32; 4] {
+    let [l, c, h, alpha] = convert(pixel, from, &ColorSpace::Oklch);
+    let to_space = ColorSpace::Rgb(*to);
+
+    let chroma = if l > 0.0 && l < 1.0 && c > 0.0 {
+        let boundary = max_chroma(l, h, to);
+        if boundary > 0.0 {
+            boundary * compress_distance(c / boundary, threshold.clamp(0.0, 1.0))
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+
+    // The compressed color is on or within the boundary up to the precision
+    // of the search, clipping removes what little is left over.
+    convert_with_intent(
+        [l.clamp(0.0, 1.0), chroma, h, alpha],
+        &ColorSpace::Oklch,
+        &to_space,
+        RenderingIntent::Saturation,
+    )
+}
+
+/// Desaturates HDR highlights in linear `space` whose largest channel is
+/// above `threshold` toward white, keeping their Oklch hue, and brings them
+/// into the gamut of `space`. The transfer of `space` is ignored, as both
+/// `linear` and the result are linear light.
+///
+/// Clipping each channel of a blown highlight separately shifts its hue, as
+/// the channels over `1.0` lose more than the others, which gives the cyan
+/// and magenta fringes around bright lights. Here the chroma is instead
+/// scaled by `threshold` over the largest channel, so the brighter the
+/// light the whiter it gets, and limited to the gamut at the highlight's
+/// lightness, itself limited to white's. Colors with every channel at most
+/// `threshold` are returned unchanged, so `threshold` should be in
+/// `(0, 1]`.
+pub fn desaturate_highlights(linear: [f32; 4], threshold: f32, space: &RgbColorSpace) -> [f32; 4] {
+    let max = linear[0].max(linear[1]).max(linear[2]);
+    if max <= threshold {
+        return linear;
+    }
+
+    let linear_space = ColorSpace::Rgb(RgbColorSpace {
+        transfer: Transfer::Linear,
+        ..*space
+    });
+    let [l, c, h, alpha] = convert(linear, &linear_space, &ColorSpace::Oklch);
+    let l = l.min(1.0);
+    let chroma = (c * threshold / max).min(max_chroma(l, h, space));
+
+    // As in `compress_to_gamut`, clipping removes the rounding error of the
+    // boundary search.
+    convert_with_intent(
+        [l, chroma, h, alpha],
+        &ColorSpace::Oklch,
+        &linear_space,
+        RenderingIntent::Saturation,
+    )
+}
+
+/// Returns the largest Oklch chroma within the gamut of `space` at lightness
+/// `l` and hue `h` in degrees, such as for the end of a chroma slider.
+///
+/// The boundary is found by bisection on linear RGB, so the chroma is in
+/// gamut, allowing for rounding, and within about `1e-6` of the boundary.
+/// Black, white and lightnesses beyond them have no chroma.
+pub fn max_chroma(l: f32, h: f32, space: &RgbColorSpace) -> f32 {
+    if l <= 0.0 || l >= 1.0 {
+        return 0.0;
+    }
+
+    let linear = ColorSpace::Rgb(RgbColorSpace {
+        transfer: Transfer::Linear,
+        ..*space
+    });
+    let plan = ConversionPlan::new(&ColorSpace::Oklch, &linear);
+    let in_gamut = |c: f32| {
+        plan.apply([l, c, h, 1.0])[..3]
+            .iter()
+            .all(|v| (-GAMUT_EPSILON..=1.0 + GAMUT_EPSILON).contains(v))
+    };
+
+    let (mut low, mut high) = (0.0, 0.5);
+    while in_gamut(high) && high < 8.0 {
+        (low, high) = (high, high * 2.0);
+    }
+    for _ in 0..24 {
+        let mid = (low + high) / 2.0;
+        if in_gamut(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
+
+/// Maps `distance`, a chroma as a fraction of the boundary chroma, onto
+/// `[0, 1]`. Distances up to `threshold` are unchanged and the rest follow a
+/// quadratic ease out, continuous in value and slope at the threshold, which
+/// reaches `1.0` with zero slope at `2.0 - threshold`.
+fn compress_distance(distance: f32, threshold: f32) -> f32 {
+    if distance <= threshold {
+        return distance;
+    }
+    if threshold >= 1.0 {
+        return distance.min(1.0);
+    }
+
+    let x = ((distance - threshold) / (2.0 * (1.0 - threshold))).min(1.0);
+    threshold + (1.0 - threshold) * x * (2.0 - x)
+}
@@ -0,0 +1,45 @@
+//! Arithmetic on hues in degrees, as in the hue channels of HWB, CIE LCh and
+//! Oklch.
+//!
+//! Hues wrap around at 360°, so adding to them, interpolating between them
+//! and measuring between them each have to account for the seam between
+//! 359° and 0°. Every hue returned is in `[0, 360)`.
+
+use crate::interpolate::HueInterpolation;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Wraps `hue` into `[0, 360)`.
+///
+/// `rem_euclid` alone gives 360 for negative hues too small to be told apart
+/// from it, which is taken as 0.
+fn wrap(hue: f32) -> f32 {
+    let wrapped = hue.rem_euclid(360.0);
+    if wrapped >= 360.0 {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+/// Returns `hue` turned by `delta` degrees, which may be negative or more
+/// than a full turn.
+pub fn hue_add(hue: f32, delta: f32) -> f32 {
+    wrap(hue + delta)
+}
+
+/// Returns the hue `t` of the way from `a` to `b`, going around the hue
+/// circle as `direction` says.
+///
+/// With [`HueInterpolation::Shorter`] this crosses the seam when that is
+/// shorter, so halfway from 350° to 10° is 0° rather than 180°.
+pub fn hue_lerp(a: f32, b: f32, t: f32, direction: HueInterpolation) -> f32 {
+    direction.interpolate(a, b, t)
+}
+
+/// Returns the length of the shorter arc between `a` and `b`, in `[0, 180]`.
+pub fn hue_distance(a: f32, b: f32) -> f32 {
+    let delta = wrap(b - a);
+    delta.min(360.0 - delta)
+}
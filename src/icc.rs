@@ -0,0 +1,702 @@
+//! Reading the RGB matrix/TRC ICC profiles embedded in many images.
+//!
+//! A matrix/TRC profile describes an RGB space with a tone reproduction curve
+//! (TRC) per channel, decoding to linear light, and the XYZ of each primary
+//! as adapted to the D50 profile connection space (PCS). These are the
+//! `rTRC`, `gTRC`, `bTRC`, `rXYZ`, `gXYZ` and `bXYZ` tags, read alongside the
+//! media white `wtpt` and the chromatic adaptation `chad` of version 4
+//! profiles. Profiles built from lookup tables, such as for CMYK printers,
+//! aren't supported.
+//!
+//! [`RgbColorSpace::to_icc_v2`] writes such a profile for a color space, to
+//! tag files with.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    adaptation::ChromaticAdaptation,
+    matrix::RowMatrix,
+    primaries::Primaries,
+    rgb_color_space::RgbColorSpace,
+    transfer::{invert_increasing, CustomCurve, CustomTransfer, Transfer},
+    whitepoint::{Whitepoint, NAMED_WHITES},
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The length of the profile header, after which comes the tag table.
+const HEADER_LEN: usize = 128;
+
+/// The number of entries in the tone curve tables of written profiles, as in
+/// the widely used sRGB profiles.
+const WRITTEN_CURVE_LEN: usize = 1024;
+
+/// How close a chromaticity recovered from a profile must be to a named
+/// one's to be taken as it rather than as a custom one. Profiles store XYZ
+/// to about five decimal places and adapt it to D50 and back, which moves
+/// chromaticities by around `1e-4`.
+const CHROMATICITY_TOLERANCE: f32 = 1e-3;
+
+/// How close a tone curve must be to a [`Transfer`] everywhere in `[0, 1]`
+/// to be taken as it, which is loose enough for 16-bit tables and tight
+/// enough to tell a 2.2 gamma from the sRGB curve.
+const CURVE_TOLERANCE: f32 = 2e-3;
+
+/// The named transfers [`IccProfile::to_rgb_color_space`] recognizes, which
+/// are those a tone curve on `[0, 1]` can describe. Any other pure power law
+/// is recognized as [`Transfer::Gamma`].
+const KNOWN_TRANSFERS: [Transfer; 4] = [
+    Transfer::Linear,
+    Transfer::Srgb,
+    Transfer::Bt709,
+    Transfer::Dci,
+];
+
+/// Why a profile could not be read or turned into an [`RgbColorSpace`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum IccError {
+    /// The data ends before a structure it declares.
+    Truncated,
+    /// The data lacks the `acsp` signature of an ICC profile.
+    NotAProfile,
+    /// The profile describes a color space other than RGB, such as CMYK.
+    UnsupportedColorSpace([u8; 4]),
+    /// The profile connection space is CIELAB, which matrix/TRC profiles
+    /// can't use.
+    UnsupportedPcs([u8; 4]),
+    /// A tag a matrix/TRC profile needs is missing, as it is from profiles
+    /// built from lookup tables.
+    MissingTag([u8; 4]),
+    /// A tag holds a type that can't be read for it.
+    UnsupportedTagType { tag: [u8; 4], kind: [u8; 4] },
+    /// A `para` curve with a function type other than 0 to 4.
+    UnsupportedParametricFunction(u16),
+    /// The colorants or the white are zero, collinear or not finite, so they
+    /// span no gamut.
+    DegenerateColorants,
+    /// The tone curves differ between channels or match no [`Transfer`].
+    UnknownTransfer,
+}
+
+/// Writes a four character signature, such as a tag name.
+struct Signature([u8; 4]);
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0
+            .iter()
+            .try_for_each(|&c| write!(f, "{}", char::from(c)))
+    }
+}
+
+impl fmt::Display for IccError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IccError::Truncated => write!(f, "the ICC profile is truncated"),
+            IccError::NotAProfile => write!(f, "the data is not an ICC profile"),
+            IccError::UnsupportedColorSpace(space) => {
+                write!(f, "unsupported ICC color space `{}`", Signature(*space))
+            }
+            IccError::UnsupportedPcs(pcs) => {
+                write!(f, "unsupported ICC connection space `{}`", Signature(*pcs))
+            }
+            IccError::MissingTag(tag) => write!(f, "missing ICC tag `{}`", Signature(*tag)),
+            IccError::UnsupportedTagType { tag, kind } => write!(
+                f,
+                "unsupported type `{}` for ICC tag `{}`",
+                Signature(*kind),
+                Signature(*tag)
+            ),
+            IccError::UnsupportedParametricFunction(function) => {
+                write!(f, "unsupported parametric curve function {function}")
+            }
+            IccError::DegenerateColorants => write!(f, "the ICC colorants span no gamut"),
+            IccError::UnknownTransfer => write!(f, "the tone curves match no known transfer"),
+        }
+    }
+}
+
+impl core::error::Error for IccError {}
+
+/// The parameters of an ICC parametric curve in the form of function type 4,
+/// which decodes `x` as
+///
+/// ```text
+/// (a * x + b)^g + e   if x >= d
+/// c * x + f           otherwise
+/// ```
+///
+/// The other function types are this with some parameters zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParametricCurve {
+    pub g: f32,
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl ParametricCurve {
+    /// Returns the curve of function type `function` with `params`, as many
+    /// as the type has.
+    fn from_function(function: u16, params: &[f32]) -> Option<ParametricCurve> {
+        let curve = match (function, params) {
+            (0, &[g]) => ParametricCurve::gamma(g),
+            (1, &[g, a, b]) => ParametricCurve {
+                g,
+                a,
+                b,
+                c: 0.0,
+                d: -b / a,
+                e: 0.0,
+                f: 0.0,
+            },
+            (2, &[g, a, b, c]) => ParametricCurve {
+                g,
+                a,
+                b,
+                c: 0.0,
+                d: -b / a,
+                e: c,
+                f: c,
+            },
+            (3, &[g, a, b, c, d]) => ParametricCurve {
+                g,
+                a,
+                b,
+                c,
+                d,
+                e: 0.0,
+                f: 0.0,
+            },
+            (4, &[g, a, b, c, d, e, f]) => ParametricCurve {
+                g,
+                a,
+                b,
+                c,
+                d,
+                e,
+                f,
+            },
+            _ => return None,
+        };
+        Some(curve)
+    }
+
+    /// Returns the pure power law `x^g`.
+    pub fn gamma(g: f32) -> ParametricCurve {
+        ParametricCurve {
+            g,
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    /// Decodes `x` into linear light.
+    pub fn eval(&self, x: f32) -> f32 {
+        if x >= self.d {
+            (self.a * x + self.b).max(0.0).powf(self.g) + self.e
+        } else {
+            self.c * x + self.f
+        }
+    }
+}
+
+/// A tone reproduction curve decoding an encoded channel into linear light.
+///
+/// It implements [`CustomTransfer`], so a curve that is no [`Transfer`] can
+/// still be used as [`Transfer::Custom`] once given a `'static` lifetime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IccCurve {
+    /// A parametric curve, from a `para` tag or the gamma of a `curv` tag.
+    Parametric(ParametricCurve),
+    /// Evenly spaced samples over `[0, 1]` of a `curv` tag, normalized to
+    /// `[0, 1]` and interpolated linearly.
+    Table(Vec<f32>),
+}
+
+impl IccCurve {
+    /// Decodes `x` into linear light. Tables clamp `x` to `[0, 1]`.
+    pub fn eval(&self, x: f32) -> f32 {
+        match self {
+            IccCurve::Parametric(curve) => curve.eval(x),
+            IccCurve::Table(table) => {
+                let last = table.len() - 1;
+                let position = x.clamp(0.0, 1.0) * last as f32;
+                let index = (position as usize).min(last.saturating_sub(1));
+                let t = position - index as f32;
+                let next = table[(index + 1).min(last)];
+                table[index] + (next - table[index]) * t
+            }
+        }
+    }
+}
+
+impl CustomTransfer for IccCurve {
+    fn eotf(&self, v: f32) -> f32 {
+        self.eval(v)
+    }
+
+    fn oetf(&self, v: f32) -> f32 {
+        invert_increasing(|x| self.eval(x), v, 0.0..=1.0)
+    }
+}
+
+/// The contents of an RGB matrix/TRC profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    /// The matrix taking linear RGB to XYZ relative to the D50 PCS, whose
+    /// columns are the `rXYZ`, `gXYZ` and `bXYZ` tags.
+    pub colorants: RowMatrix,
+    /// The XYZ of the media white from the `wtpt` tag. Version 4 profiles
+    /// give D50 here and the adaptation from the actual white in
+    /// [`Self::chromatic_adaptation`].
+    pub media_white: [f32; 3],
+    /// The matrix of the `chad` tag adapting XYZ under the space's own white
+    /// to D50, if present.
+    pub chromatic_adaptation: Option<RowMatrix>,
+    /// The `rTRC`, `gTRC` and `bTRC` curves decoding each channel.
+    pub curves: [IccCurve; 3],
+}
+
+impl IccProfile {
+    /// Reads the matrix/TRC profile in `bytes`, such as one embedded in a PNG
+    /// `iCCP` chunk or a JPEG `APP2` segment once decompressed or
+    /// reassembled.
+    pub fn parse(bytes: &[u8]) -> Result<IccProfile, IccError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(IccError::Truncated);
+        }
+        if bytes[36..40] != *b"acsp" {
+            return Err(IccError::NotAProfile);
+        }
+        let color_space = read_signature(bytes, 16)?;
+        if color_space != *b"RGB " {
+            return Err(IccError::UnsupportedColorSpace(color_space));
+        }
+        let pcs = read_signature(bytes, 20)?;
+        if pcs != *b"XYZ " {
+            return Err(IccError::UnsupportedPcs(pcs));
+        }
+
+        let tags = Tags::read(bytes)?;
+        let xyz = |tag| read_xyz(tags.require(tag)?, tag);
+        let colorants = RowMatrix::from_columns([xyz(*b"rXYZ")?, xyz(*b"gXYZ")?, xyz(*b"bXYZ")?]);
+        let media_white = xyz(*b"wtpt")?;
+        let chromatic_adaptation = match tags.find(*b"chad")? {
+            Some(data) => Some(read_chad(data)?),
+            None => None,
+        };
+        let curve = |tag| read_curve(tags.require(tag)?, tag);
+        let curves = [curve(*b"rTRC")?, curve(*b"gTRC")?, curve(*b"bTRC")?];
+
+        Ok(IccProfile {
+            colorants,
+            media_white,
+            chromatic_adaptation,
+            curves,
+        })
+    }
+
+    /// Decodes the encoded RGB `pixel` into XYZ relative to the D50 PCS, as
+    /// [`ColorSpace::Xyz(Whitepoint::D50)`](crate::color_space::ColorSpace::Xyz),
+    /// leaving alpha untouched. This works for every matrix/TRC profile,
+    /// including those [`Self::to_rgb_color_space`] doesn't recognize.
+    pub fn to_xyz(&self, pixel: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, alpha] = pixel;
+        let linear = [
+            self.curves[0].eval(r),
+            self.curves[1].eval(g),
+            self.curves[2].eval(b),
+        ];
+        let [x, y, z] = self.colorants.mul_vec(linear);
+        [x, y, z, alpha]
+    }
+
+    /// Returns the white of the space before it was adapted to D50, from
+    /// the inverse of the `chad` tag if there is one and the media white
+    /// otherwise.
+    pub fn whitepoint(&self) -> Whitepoint {
+        let white = match self.chromatic_adaptation.and_then(RowMatrix::try_inv) {
+            Some(inverse) => inverse.mul_vec(Whitepoint::D50.to_xyz()),
+            None => self.media_white,
+        };
+        let xy = xyz_to_xy(white);
+        NAMED_WHITES
+            .into_iter()
+            .find(|named| is_near(named.to_xy(), xy))
+            .unwrap_or(Whitepoint::Custom(xy))
+    }
+
+    /// Returns the [`RgbColorSpace`] the profile describes, if its curves
+    /// are the same [`Transfer`].
+    ///
+    /// The colorants are adapted back from D50 to [`Self::whitepoint`] with
+    /// the `chad` tag, or with Bradford as the ICC specifies when there is
+    /// none. Whites and primaries are matched to within `1e-3` in xy and
+    /// curves to within `2e-3`, absorbing the rounding of the profile, so a
+    /// 2.2 gamma isn't taken as the sRGB curve. Primaries matching none of
+    /// [`Primaries`], such as ProPhoto RGB's, are kept as
+    /// [`Primaries::Custom`], and curves that are a pure power law, such as
+    /// Adobe RGB's gamma of 563/256, as [`Transfer::Gamma`].
+    ///
+    /// Curves matching no [`Transfer`] fail with
+    /// [`IccError::UnknownTransfer`], but can still be used through
+    /// [`Self::to_rgb_color_space_with_curve`] or decoded with
+    /// [`Self::to_xyz`].
+    pub fn to_rgb_color_space(&self) -> Result<RgbColorSpace, IccError> {
+        let transfer = self.transfer().ok_or(IccError::UnknownTransfer)?;
+        self.rgb_color_space(transfer)
+    }
+
+    /// Returns the [`RgbColorSpace`] the profile describes as
+    /// [`Self::to_rgb_color_space`] does, but decoding with the profile's own
+    /// tone curve as [`Transfer::Custom`] when it matches no [`Transfer`],
+    /// as the measured curves of calibrated displays don't.
+    ///
+    /// The curve is borrowed for `'static`, so leak a `Box` of profiles read
+    /// at runtime. Profiles whose channels have different curves still fail
+    /// with [`IccError::UnknownTransfer`].
+    pub fn to_rgb_color_space_with_curve(&'static self) -> Result<RgbColorSpace, IccError> {
+        let [red, green, blue] = &self.curves;
+        let transfer = match self.transfer() {
+            Some(transfer) => transfer,
+            None if red == green && red == blue => Transfer::Custom(CustomCurve(red)),
+            None => return Err(IccError::UnknownTransfer),
+        };
+        self.rgb_color_space(transfer)
+    }
+
+    /// Returns the [`Transfer`] every curve is within [`CURVE_TOLERANCE`]
+    /// of, trying [`KNOWN_TRANSFERS`] before the power law through the red
+    /// curve's value at `0.5`.
+    fn transfer(&self) -> Option<Transfer> {
+        let gamma = match &self.curves[0] {
+            IccCurve::Parametric(curve) if *curve == ParametricCurve::gamma(curve.g) => curve.g,
+            curve => curve.eval(0.5).ln() / 0.5_f32.ln(),
+        };
+
+        KNOWN_TRANSFERS
+            .into_iter()
+            .chain((gamma.is_finite() && gamma > 0.0).then_some(Transfer::Gamma(gamma)))
+            .find(|transfer| {
+                self.curves.iter().all(|curve| {
+                    (0..=255).all(|i| {
+                        let x = i as f32 / 255.0;
+                        (curve.eval(x) - transfer.to_optical_scalar(x)).abs() <= CURVE_TOLERANCE
+                    })
+                })
+            })
+    }
+
+    /// Returns the space of the profile's primaries and white with
+    /// `transfer`.
+    fn rgb_color_space(&self, transfer: Transfer) -> Result<RgbColorSpace, IccError> {
+        let whitepoint = self.whitepoint();
+        let from_d50 = match self.chromatic_adaptation.and_then(RowMatrix::try_inv) {
+            Some(inverse) => inverse,
+            None => ChromaticAdaptation::Bradford.matrix(Whitepoint::D50, whitepoint),
+        };
+        let native = (from_d50 * self.colorants).transpose().0;
+        let chromaticities = native.map(xyz_to_xy);
+        let primaries = Primaries::find(chromaticities, CHROMATICITY_TOLERANCE)
+            .unwrap_or(Primaries::Custom(chromaticities));
+        if primaries
+            .try_to_xyz(whitepoint)
+            .and_then(RowMatrix::try_inv)
+            .is_none()
+        {
+            return Err(IccError::DegenerateColorants);
+        }
+
+        Ok(RgbColorSpace {
+            primaries,
+            whitepoint,
+            transfer,
+        })
+    }
+}
+
+impl RgbColorSpace {
+    /// Writes a version 2 matrix/TRC display profile describing this space,
+    /// with the `desc`, `cprt`, `wtpt`, `rXYZ`, `gXYZ`, `bXYZ`, `rTRC`,
+    /// `gTRC` and `bTRC` tags.
+    ///
+    /// The colorants are adapted to the D50 PCS with Bradford and the media
+    /// white is this space's white, as version 2 display profiles have it.
+    /// The tone curve is a table of 1024 entries, or the identity for
+    /// [`Transfer::Linear`], as parametric curves are a version 4 addition.
+    /// A table can only hold values in `[0, 1]`, so curves decoding beyond
+    /// that, such as the log curves, are clipped.
+    pub fn to_icc_v2(&self) -> Vec<u8> {
+        let to_pcs = ChromaticAdaptation::Bradford.matrix(self.whitepoint, Whitepoint::D50)
+            * self.to_xyz_matrix();
+        let [red, green, blue] = to_pcs.transpose().0;
+
+        let description = format!(
+            "{:?} primaries, {:?} white, {:?} transfer",
+            self.primaries, self.whitepoint, self.transfer
+        );
+        let curve = match self.transfer {
+            Transfer::Linear => curve_tag(&[]),
+            transfer => curve_tag(&core::array::from_fn::<_, WRITTEN_CURVE_LEN, _>(|i| {
+                let x = i as f32 / (WRITTEN_CURVE_LEN - 1) as f32;
+                transfer.to_optical_scalar(x)
+            })),
+        };
+        let tags = [
+            (*b"desc", description_tag(&description)),
+            (*b"cprt", text_tag("No copyright, use freely")),
+            (*b"wtpt", xyz_tag(self.whitepoint.to_xyz())),
+            (*b"rXYZ", xyz_tag(red)),
+            (*b"gXYZ", xyz_tag(green)),
+            (*b"bXYZ", xyz_tag(blue)),
+            (*b"rTRC", curve.clone()),
+            (*b"gTRC", curve.clone()),
+            (*b"bTRC", curve),
+        ];
+
+        let mut header = [0; HEADER_LEN];
+        header[8..12].copy_from_slice(&0x0210_0000_u32.to_be_bytes());
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(b"RGB ");
+        header[20..24].copy_from_slice(b"XYZ ");
+        header[36..40].copy_from_slice(b"acsp");
+        for (i, v) in Whitepoint::D50.to_xyz().into_iter().enumerate() {
+            header[68 + 4 * i..][..4].copy_from_slice(&s15_fixed16(v));
+        }
+
+        let mut profile = header.to_vec();
+        profile.extend((tags.len() as u32).to_be_bytes());
+        let mut offset = HEADER_LEN + 4 + 12 * tags.len();
+        for (tag, data) in &tags {
+            profile.extend(tag);
+            profile.extend((offset as u32).to_be_bytes());
+            profile.extend((data.len() as u32).to_be_bytes());
+            offset += data.len().next_multiple_of(4);
+        }
+        // Tag data is aligned to four bytes.
+        for (_, data) in &tags {
+            profile.extend(data);
+            profile.resize(profile.len().next_multiple_of(4), 0);
+        }
+
+        let len = (profile.len() as u32).to_be_bytes();
+        profile[..4].copy_from_slice(&len);
+        profile
+    }
+}
+
+/// Returns `v` as an `s15Fixed16Number`.
+fn s15_fixed16(v: f32) -> [u8; 4] {
+    ((v * 65536.0).round() as i32).to_be_bytes()
+}
+
+/// Returns the type signature and reserved bytes every tag starts with.
+fn tag_start(kind: &[u8; 4]) -> Vec<u8> {
+    let mut data = kind.to_vec();
+    data.extend([0; 4]);
+    data
+}
+
+fn xyz_tag(xyz: [f32; 3]) -> Vec<u8> {
+    let mut data = tag_start(b"XYZ ");
+    data.extend(xyz.into_iter().flat_map(s15_fixed16));
+    data
+}
+
+/// Returns a `curv` tag of `table`, clipped to `[0, 1]`, which is the
+/// identity when empty.
+fn curve_tag(table: &[f32]) -> Vec<u8> {
+    let mut data = tag_start(b"curv");
+    data.extend((table.len() as u32).to_be_bytes());
+    data.extend(table.iter().flat_map(|v| {
+        let code = (v.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        code.to_be_bytes()
+    }));
+    data
+}
+
+/// Returns `text` with anything but printable ASCII replaced, as the text
+/// types of version 2 hold.
+fn ascii(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_graphic() || c == ' ' {
+                c
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+/// Returns a `text` tag of `text`.
+fn text_tag(text: &str) -> Vec<u8> {
+    let mut data = tag_start(b"text");
+    data.extend(ascii(text).bytes());
+    data.push(0);
+    data
+}
+
+/// Returns a version 2 `desc` tag of `text`, with empty Unicode and
+/// ScriptCode descriptions.
+fn description_tag(text: &str) -> Vec<u8> {
+    let text = ascii(text);
+    let mut data = tag_start(b"desc");
+    data.extend((text.len() as u32 + 1).to_be_bytes());
+    data.extend(text.bytes());
+    data.push(0);
+    // The Unicode language and length, then the ScriptCode code, length and
+    // its fixed 67 bytes.
+    data.extend([0; 4 + 4 + 2 + 1 + 67]);
+    data
+}
+
+/// Returns whether the chromaticities `a` and `b` agree to within
+/// [`CHROMATICITY_TOLERANCE`].
+fn is_near(a: [f32; 2], b: [f32; 2]) -> bool {
+    (a[0] - b[0]).abs() <= CHROMATICITY_TOLERANCE && (a[1] - b[1]).abs() <= CHROMATICITY_TOLERANCE
+}
+
+fn xyz_to_xy([x, y, z]: [f32; 3]) -> [f32; 2] {
+    let sum = x + y + z;
+    [x / sum, y / sum]
+}
+
+/// The tag table of a profile.
+struct Tags<'a> {
+    bytes: &'a [u8],
+    table: &'a [u8],
+}
+
+impl<'a> Tags<'a> {
+    fn read(bytes: &'a [u8]) -> Result<Tags<'a>, IccError> {
+        let count = read_u32(bytes, HEADER_LEN)? as usize;
+        let table = count
+            .checked_mul(12)
+            .and_then(|len| bytes.get(HEADER_LEN + 4..)?.get(..len))
+            .ok_or(IccError::Truncated)?;
+        Ok(Tags { bytes, table })
+    }
+
+    /// Returns the data of the first tag named `tag`, if any.
+    fn find(&self, tag: [u8; 4]) -> Result<Option<&'a [u8]>, IccError> {
+        let Some(entry) = self.table.chunks_exact(12).find(|entry| entry[..4] == tag) else {
+            return Ok(None);
+        };
+        let offset = read_u32(entry, 4)? as usize;
+        let len = read_u32(entry, 8)? as usize;
+        let data = offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or(IccError::Truncated)?;
+        Ok(Some(data))
+    }
+
+    fn require(&self, tag: [u8; 4]) -> Result<&'a [u8], IccError> {
+        self.find(tag)?.ok_or(IccError::MissingTag(tag))
+    }
+}
+
+fn read_bytes<const N: usize>(bytes: &[u8], offset: usize) -> Result<[u8; N], IccError> {
+    bytes
+        .get(offset..)
+        .and_then(|rest| rest.first_chunk::<N>())
+        .copied()
+        .ok_or(IccError::Truncated)
+}
+
+fn read_signature(bytes: &[u8], offset: usize) -> Result<[u8; 4], IccError> {
+    read_bytes(bytes, offset)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, IccError> {
+    read_bytes(bytes, offset).map(u16::from_be_bytes)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, IccError> {
+    read_bytes(bytes, offset).map(u32::from_be_bytes)
+}
+
+/// Reads an `s15Fixed16Number`, a signed fixed point number with 16
+/// fractional bits.
+fn read_s15_fixed16(bytes: &[u8], offset: usize) -> Result<f32, IccError> {
+    read_bytes(bytes, offset).map(|b| i32::from_be_bytes(b) as f32 / 65536.0)
+}
+
+/// Checks that the tag data `data` of `tag` has the type `kind`.
+fn expect_type(data: &[u8], tag: [u8; 4], kind: [u8; 4]) -> Result<(), IccError> {
+    match read_signature(data, 0)? {
+        found if found == kind => Ok(()),
+        found => Err(IccError::UnsupportedTagType { tag, kind: found }),
+    }
+}
+
+/// Reads the first XYZ number of an `XYZ ` tag.
+fn read_xyz(data: &[u8], tag: [u8; 4]) -> Result<[f32; 3], IccError> {
+    expect_type(data, tag, *b"XYZ ")?;
+    Ok([
+        read_s15_fixed16(data, 8)?,
+        read_s15_fixed16(data, 12)?,
+        read_s15_fixed16(data, 16)?,
+    ])
+}
+
+/// Reads the row-major 3x3 matrix of a `chad` tag.
+fn read_chad(data: &[u8]) -> Result<RowMatrix, IccError> {
+    expect_type(data, *b"chad", *b"sf32")?;
+    let mut rows = [[0.0; 3]; 3];
+    for (i, value) in rows.iter_mut().flatten().enumerate() {
+        *value = read_s15_fixed16(data, 8 + 4 * i)?;
+    }
+    Ok(RowMatrix(rows))
+}
+
+/// Reads a `curv` or `para` tag.
+fn read_curve(data: &[u8], tag: [u8; 4]) -> Result<IccCurve, IccError> {
+    match read_signature(data, 0)? {
+        kind if kind == *b"curv" => {
+            let count = read_u32(data, 8)? as usize;
+            match count {
+                0 => Ok(IccCurve::Parametric(ParametricCurve::gamma(1.0))),
+                // A single entry is a gamma as an unsigned 8.8 fixed point
+                // number.
+                1 => Ok(IccCurve::Parametric(ParametricCurve::gamma(
+                    f32::from(read_u16(data, 12)?) / 256.0,
+                ))),
+                _ => (0..count)
+                    .map(|i| read_u16(data, 12 + 2 * i).map(|v| f32::from(v) / 65535.0))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(IccCurve::Table),
+            }
+        }
+        kind if kind == *b"para" => {
+            let function = read_u16(data, 8)?;
+            let count = match function {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(IccError::UnsupportedParametricFunction(function)),
+            };
+            let mut params = [0.0; 7];
+            for (i, param) in params[..count].iter_mut().enumerate() {
+                *param = read_s15_fixed16(data, 12 + 4 * i)?;
+            }
+            ParametricCurve::from_function(function, &params[..count])
+                .map(IccCurve::Parametric)
+                .ok_or(IccError::UnsupportedParametricFunction(function))
+        }
+        kind => Err(IccError::UnsupportedTagType { tag, kind }),
+    }
+}
@@ -0,0 +1,110 @@
+//! Interpolating between colors, for mixing and gradients.
+
+use alloc::vec::Vec;
+
+use crate::{color_space::ColorSpace, hue::hue_add};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Which way around the hue circle hues are interpolated, as in CSS Color 4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HueInterpolation {
+    /// Takes the arc of at most 180°.
+    #[default]
+    Shorter,
+    /// Takes the arc of at least 180°, passing through every hue the shorter
+    /// arc doesn't.
+    Longer,
+    /// Takes the arc along which the hue increases.
+    Increasing,
+    /// Takes the arc along which the hue decreases.
+    Decreasing,
+}
+
+impl HueInterpolation {
+    /// Returns the hue `t` of the way from `from` to `to` in degrees, in
+    /// `[0, 360)`.
+    pub fn interpolate(self, from: f32, to: f32, t: f32) -> f32 {
+        let mut delta = (to - from).rem_euclid(360.0);
+        match self {
+            HueInterpolation::Shorter if delta > 180.0 => delta -= 360.0,
+            HueInterpolation::Longer if delta < 180.0 && delta != 0.0 => delta -= 360.0,
+            HueInterpolation::Decreasing if delta != 0.0 => delta -= 360.0,
+            _ => {}
+        }
+        hue_add(from, t * delta)
+    }
+}
+
+/// Returns which channel of `space` is a hue in degrees, if any.
+fn hue_channel(space: &ColorSpace) -> Option<usize> {
+    match space {
+        ColorSpace::Oklch => Some(2),
+        ColorSpace::Hwb(_) | ColorSpace::Okhsl | ColorSpace::Okhsv => Some(0),
+        _ => None,
+    }
+}
+
+/// Returns the color `t` of the way from `a` to `b`, both in `space`, with
+/// every channel including alpha interpolated linearly in `space`.
+///
+/// A hue channel goes around the hue circle as `hue` says. `t` outside
+/// `[0, 1]` extrapolates.
+pub fn mix(
+    a: [f32; 4],
+    b: [f32; 4],
+    t: f32,
+    space: &ColorSpace,
+    hue: HueInterpolation,
+) -> [f32; 4] {
+    let mut mixed: [f32; 4] = core::array::from_fn(|c| a[c] + (b[c] - a[c]) * t);
+    if let Some(channel) = hue_channel(space) {
+        mixed[channel] = hue.interpolate(a[channel], b[channel], t);
+    }
+    mixed
+}
+
+/// Returns `n` colors sampled evenly from the gradient through `stops`, from
+/// position `0.0` to `1.0` inclusive.
+///
+/// Each stop is a position and a color in `space`, and positions between
+/// two stops are [`mix`]ed from them. Positions before the first stop or
+/// after the last take its color, and of several stops at the same position
+/// the last is used after it, giving a hard edge. A single sample is taken
+/// at position `0.0`.
+///
+/// # Panics
+///
+/// Panics if `stops` is empty or its positions aren't in increasing order.
+pub fn gradient(
+    stops: &[(f32, [f32; 4])],
+    n: usize,
+    space: &ColorSpace,
+    hue: HueInterpolation,
+) -> Vec<[f32; 4]> {
+    assert!(!stops.is_empty(), "a gradient needs at least one stop");
+    assert!(
+        stops.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+        "gradient stops must be in increasing order of position"
+    );
+
+    let last = n.saturating_sub(1).max(1) as f32;
+    (0..n)
+        .map(|i| {
+            let position = i as f32 / last;
+            // The first stop after the position, so that the position is in
+            // `[stops[next - 1], stops[next])`.
+            let next = stops.partition_point(|(stop, _)| *stop <= position);
+            match next {
+                0 => stops[0].1,
+                next if next == stops.len() => stops[next - 1].1,
+                next => {
+                    let (start, from) = stops[next - 1];
+                    let (end, to) = stops[next];
+                    mix(from, to, (position - start) / (end - start), space, hue)
+                }
+            }
+        })
+        .collect()
+}
@@ -1,8 +1,51 @@
 //! A crate for converting pixels and images between pixels types and
 //! their respective color-spaces.
+//!
+//! The default `std` feature can be disabled for `no_std` targets with an
+//! allocator, in which case the `libm` feature must be enabled to provide the
+//! floating point functions `std` otherwise would.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("either the `std` or the `libm` feature must be enabled");
+
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+mod math;
+
 mod traits;
 
 mod impls;
+
+pub mod accessibility;
+pub mod adaptation;
+pub mod adjust;
+pub mod blend;
+pub mod bytes;
+pub mod channel;
+pub mod color_space;
+pub mod descriptor;
+pub mod diagnostics;
+pub mod display;
+pub mod gamut;
+pub mod hue;
+pub mod icc;
+pub mod interpolate;
+pub mod lut;
+pub mod matrix;
+pub mod okhsl;
+pub mod palette;
+pub mod plan;
+pub mod png;
+pub mod primaries;
+pub mod reference;
+pub mod rgb_color_space;
+pub mod spectral;
+pub mod swizzle;
+pub mod temperature;
+pub mod transfer;
+pub mod whitepoint;
+pub mod yuv;
@@ -0,0 +1,126 @@
+//! Three dimensional lookup tables for baking conversions.
+
+use alloc::vec::Vec;
+
+use crate::matrix::RowMatrix;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// A color transform sampled on an evenly spaced `size`³ lattice over
+/// `[0, 1]` in each channel, like the tables in `.cube` files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLut {
+    size: usize,
+    /// The samples with red changing fastest and blue slowest, the order
+    /// `.cube` files list them in.
+    entries: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    /// Creates a table of `size`³ entries sampling `transform`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is less than 2, which cannot span `[0, 1]`.
+    pub fn from_fn(size: usize, mut transform: impl FnMut([f32; 3]) -> [f32; 3]) -> CubeLut {
+        assert!(
+            size >= 2,
+            "a cube LUT needs a size of at least 2, got {size}"
+        );
+
+        let scale = (size - 1) as f32;
+        let mut entries = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    entries.push(transform([
+                        r as f32 / scale,
+                        g as f32 / scale,
+                        b as f32 / scale,
+                    ]));
+                }
+            }
+        }
+
+        CubeLut { size, entries }
+    }
+
+    /// Returns the number of samples along each channel.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the samples with red changing fastest and blue slowest.
+    pub fn entries(&self) -> &[[f32; 3]] {
+        &self.entries
+    }
+
+    /// Returns the sample at the lattice point with the given indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is not less than [`Self::size`].
+    pub fn get(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        assert!(
+            r < self.size && g < self.size && b < self.size,
+            "index ({r}, {g}, {b}) is outside a cube LUT of size {}",
+            self.size
+        );
+        self.entries[(b * self.size + g) * self.size + r]
+    }
+
+    /// Looks up the color channels of `pixel` with trilinear interpolation,
+    /// clamping them to `[0, 1]` first and leaving alpha untouched.
+    #[must_use]
+    pub fn apply(&self, pixel: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, alpha] = pixel;
+        let scale = (self.size - 1) as f32;
+        let split = |c: f32| {
+            let position = c.clamp(0.0, 1.0) * scale;
+            let index = (position as usize).min(self.size - 2);
+            (index, position - index as f32)
+        };
+        let ((r0, tr), (g0, tg), (b0, tb)) = (split(r), split(g), split(b));
+
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| -> [f32; 3] {
+            core::array::from_fn(|c| a[c] + (b[c] - a[c]) * t)
+        };
+        let along_r = |g: usize, b: usize| lerp(self.get(r0, g, b), self.get(r0 + 1, g, b), tr);
+        let along_g = |b: usize| lerp(along_r(g0, b), along_r(g0 + 1, b), tg);
+        let [r, g, b] = lerp(along_g(b0), along_g(b0 + 1), tb);
+
+        [r, g, b, alpha]
+    }
+}
+
+/// The BT.2087 matrix from linear BT.709 to linear BT.2020, as published to
+/// four decimal places.
+pub const BT2087_MATRIX: RowMatrix = RowMatrix([
+    [0.6274, 0.3293, 0.0433],
+    [0.0691, 0.9195, 0.0114],
+    [0.0164, 0.0880, 0.8956],
+]);
+
+/// The BT.1886 display gamma BT.2087 linearizes with.
+const BT1886_GAMMA: f32 = 2.4;
+
+/// Bakes the ITU-R BT.2087 conversion of BT.709 signals to BT.2020 signals
+/// into a table of `size`³ entries, with 33 being the usual size.
+///
+/// This is the display-referred conversion, case #1 in BT.2087, which
+/// linearizes with the BT.1886 gamma of 2.4 and re-encodes with its inverse so
+/// that pictures look the same on a BT.2020 display as they did on a BT.709
+/// one.
+///
+/// # Panics
+///
+/// Panics if `size` is less than 2.
+pub fn bt2087_lut(size: usize) -> CubeLut {
+    CubeLut::from_fn(size, |rgb| {
+        let linear = rgb.map(|c| c.max(0.0).powf(BT1886_GAMMA));
+        BT2087_MATRIX
+            .mul_vec(linear)
+            .map(|c| c.max(0.0).powf(1.0 / BT1886_GAMMA))
+    })
+}
@@ -0,0 +1,107 @@
+//! Floating point functions for `no_std` builds, where `f32` and `f64` lack
+//! the methods that need the platform's math library.
+//!
+//! [`Float`] provides them from `libm` under the same names, so modules only
+//! need to import it when the `std` feature is disabled.
+
+pub(crate) trait Float: Sized {
+    fn powf(self, n: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn sqrt(self) -> Self;
+    fn cbrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn exp2(self) -> Self;
+    fn ln(self) -> Self;
+    fn log2(self) -> Self;
+    fn log10(self) -> Self;
+    fn cos(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn abs(self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn trunc(self) -> Self;
+    fn round(self) -> Self;
+    fn round_ties_even(self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($ty:ty, $pow:ident, $sqrt:ident, $cbrt:ident, $exp:ident, $exp2:ident, $log:ident,
+     $log2:ident, $log10:ident, $cos:ident, $sincos:ident, $acos:ident, $atan2:ident,
+     $hypot:ident, $fabs:ident, $fmod:ident, $trunc:ident, $round:ident, $rint:ident) => {
+        impl Float for $ty {
+            fn powf(self, n: Self) -> Self {
+                libm::$pow(self, n)
+            }
+            fn powi(self, n: i32) -> Self {
+                libm::$pow(self, n as $ty)
+            }
+            fn sqrt(self) -> Self {
+                libm::$sqrt(self)
+            }
+            fn cbrt(self) -> Self {
+                libm::$cbrt(self)
+            }
+            fn exp(self) -> Self {
+                libm::$exp(self)
+            }
+            fn exp2(self) -> Self {
+                libm::$exp2(self)
+            }
+            fn ln(self) -> Self {
+                libm::$log(self)
+            }
+            fn log2(self) -> Self {
+                libm::$log2(self)
+            }
+            fn log10(self) -> Self {
+                libm::$log10(self)
+            }
+            fn cos(self) -> Self {
+                libm::$cos(self)
+            }
+            fn sin_cos(self) -> (Self, Self) {
+                libm::$sincos(self)
+            }
+            fn acos(self) -> Self {
+                libm::$acos(self)
+            }
+            fn atan2(self, other: Self) -> Self {
+                libm::$atan2(self, other)
+            }
+            fn hypot(self, other: Self) -> Self {
+                libm::$hypot(self, other)
+            }
+            fn abs(self) -> Self {
+                libm::$fabs(self)
+            }
+            fn rem_euclid(self, rhs: Self) -> Self {
+                let r = libm::$fmod(self, rhs);
+                if r < 0.0 {
+                    r + libm::$fabs(rhs)
+                } else {
+                    r
+                }
+            }
+            fn trunc(self) -> Self {
+                libm::$trunc(self)
+            }
+            fn round(self) -> Self {
+                libm::$round(self)
+            }
+            fn round_ties_even(self) -> Self {
+                libm::$rint(self)
+            }
+        }
+    };
+}
+
+impl_float!(
+    f32, powf, sqrtf, cbrtf, expf, exp2f, logf, log2f, log10f, cosf, sincosf, acosf, atan2f,
+    hypotf, fabsf, fmodf, truncf, roundf, rintf
+);
+impl_float!(
+    f64, pow, sqrt, cbrt, exp, exp2, log, log2, log10, cos, sincos, acos, atan2, hypot, fabs, fmod,
+    trunc, round, rint
+);
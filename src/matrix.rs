@@ -0,0 +1,183 @@
+//! A small 3x3 matrix type for linear color transforms.
+
+use core::ops::Mul;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// How far each element may be from the identity's for
+/// [`RowMatrix::is_near_identity`], a few times the rounding error of
+/// multiplying a matrix by its inverse in `f32`.
+const NEAR_IDENTITY_TOLERANCE: f32 = 1e-6;
+
+/// A 3x3 matrix stored row by row, applied to column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RowMatrix(pub [[f32; 3]; 3]);
+
+impl RowMatrix {
+    pub const IDENTITY: RowMatrix = RowMatrix([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    /// Creates a matrix with `diagonal` on the diagonal and zeros elsewhere.
+    pub fn diagonal(diagonal: [f32; 3]) -> RowMatrix {
+        let [a, b, c] = diagonal;
+        RowMatrix([[a, 0.0, 0.0], [0.0, b, 0.0], [0.0, 0.0, c]])
+    }
+
+    /// Creates a matrix whose columns are `columns`.
+    pub fn from_columns(columns: [[f32; 3]; 3]) -> RowMatrix {
+        RowMatrix(columns).transpose()
+    }
+
+    #[must_use]
+    pub fn transpose(self) -> RowMatrix {
+        let m = self.0;
+        RowMatrix(core::array::from_fn(|i| core::array::from_fn(|j| m[j][i])))
+    }
+
+    #[must_use]
+    pub fn mul_vec(self, v: [f32; 3]) -> [f32; 3] {
+        self.0
+            .map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+    }
+
+    #[must_use]
+    pub fn determinant(self) -> f32 {
+        let m = self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Returns the inverse of the matrix, or `None` if it is singular or so
+    /// close to singular that the inverse overflows `f32`.
+    #[must_use]
+    pub fn try_inv(self) -> Option<RowMatrix> {
+        let m = self.0;
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        let inverse = RowMatrix([
+            [
+                cofactor(1, 2, 1, 2) / det,
+                -cofactor(0, 2, 1, 2) / det,
+                cofactor(0, 1, 1, 2) / det,
+            ],
+            [
+                -cofactor(1, 2, 0, 2) / det,
+                cofactor(0, 2, 0, 2) / det,
+                -cofactor(0, 1, 0, 2) / det,
+            ],
+            [
+                cofactor(1, 2, 0, 1) / det,
+                -cofactor(0, 2, 0, 1) / det,
+                cofactor(0, 1, 0, 1) / det,
+            ],
+        ]);
+        inverse
+            .0
+            .iter()
+            .flatten()
+            .all(|c| c.is_finite())
+            .then_some(inverse)
+    }
+
+    /// Returns the inverse of the matrix.
+    ///
+    /// Every matrix this crate builds from its primaries and whitepoints is
+    /// invertible. Use [`Self::try_inv`] for matrices that might not be.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix is singular, rather than returning a matrix full
+    /// of NaN and infinities that would silently spoil every conversion.
+    #[must_use]
+    pub fn inv(self) -> RowMatrix {
+        self.try_inv()
+            .unwrap_or_else(|| panic!("{self:?} is singular and has no inverse"))
+    }
+
+    /// Returns whether every element is within rounding error of the
+    /// identity, as happens when a matrix is multiplied by its inverse.
+    pub fn is_near_identity(self) -> bool {
+        self.0
+            .iter()
+            .zip(RowMatrix::IDENTITY.0)
+            .all(|(row, identity)| {
+                row.iter()
+                    .zip(identity)
+                    .all(|(a, b)| (a - b).abs() <= NEAR_IDENTITY_TOLERANCE)
+            })
+    }
+
+    /// Returns the ratio of the largest to the smallest singular value, or
+    /// infinity if the matrix is singular.
+    ///
+    /// This bounds how much relative error in a color can be amplified by the
+    /// matrix or its inverse. Conversions between well separated primaries
+    /// are in the tens at most, while primaries that nearly lie on a line give
+    /// values in the thousands and lose most of the precision of an `f32`.
+    pub fn condition_number(self) -> f32 {
+        let Some(inverse) = self.try_inv() else {
+            return f32::INFINITY;
+        };
+
+        // The smallest singular value of a matrix is the reciprocal of the
+        // largest of its inverse, which avoids the cancellation of finding the
+        // smallest eigenvalue directly.
+        let condition = largest_singular_value(self) * largest_singular_value(inverse);
+        if condition.is_finite() {
+            condition as f32
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+/// Returns the largest singular value of `m`, the square root of the largest
+/// eigenvalue of `mᵀm`, using the closed form for symmetric 3x3 matrices.
+fn largest_singular_value(m: RowMatrix) -> f64 {
+    let m = m.0.map(|row| row.map(f64::from));
+    let a: [[f64; 3]; 3] =
+        core::array::from_fn(|i| core::array::from_fn(|j| (0..3).map(|k| m[k][i] * m[k][j]).sum()));
+
+    let off_diagonal = a[0][1] * a[0][1] + a[0][2] * a[0][2] + a[1][2] * a[1][2];
+    let q = (a[0][0] + a[1][1] + a[2][2]) / 3.0;
+    if off_diagonal == 0.0 {
+        return a[0][0].max(a[1][1]).max(a[2][2]).sqrt();
+    }
+
+    let p = (((a[0][0] - q).powi(2)
+        + (a[1][1] - q).powi(2)
+        + (a[2][2] - q).powi(2)
+        + 2.0 * off_diagonal)
+        / 6.0)
+        .sqrt();
+    let b: [[f64; 3]; 3] = core::array::from_fn(|i| {
+        core::array::from_fn(|j| (a[i][j] - if i == j { q } else { 0.0 }) / p)
+    });
+    let half_det = (b[0][0] * (b[1][1] * b[2][2] - b[1][2] * b[2][1])
+        - b[0][1] * (b[1][0] * b[2][2] - b[1][2] * b[2][0])
+        + b[0][2] * (b[1][0] * b[2][1] - b[1][1] * b[2][0]))
+        / 2.0;
+    let phi = half_det.clamp(-1.0, 1.0).acos() / 3.0;
+
+    (q + 2.0 * p * phi.cos()).max(0.0).sqrt()
+}
+
+/// `self * rhs` applies `rhs` first and then `self`.
+impl Mul for RowMatrix {
+    type Output = RowMatrix;
+
+    fn mul(self, rhs: RowMatrix) -> RowMatrix {
+        let (a, b) = (self.0, rhs.0);
+        RowMatrix(core::array::from_fn(|i| {
+            core::array::from_fn(|j| a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j])
+        }))
+    }
+}
@@ -0,0 +1,343 @@
+//! Björn Ottosson's Okhsl and Okhsv, hue, saturation and lightness or value
+//! built on Oklab and scaled to the sRGB gamut for use in color pickers.
+//!
+//! Both are `[H, S, L]` or `[H, S, V]` with the hue in degrees, matching
+//! Oklch, and the other channels in `[0, 1]` across the sRGB gamut. The
+//! conversions follow the published ones, including their polynomial fits of
+//! the gamut boundary, so they are only as exact as those fits.
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// The rows of the matrix from Oklab to the cube roots of its cone
+/// responses, without the lightness column which is all ones.
+const OKLAB_TO_LMS: [[f32; 2]; 3] = [
+    [0.396_337_78, 0.215_803_76],
+    [-0.105_561_346, -0.063_854_17],
+    [-0.089_484_18, -1.291_485_5],
+];
+
+/// The matrix from Oklab's cone responses to linear sRGB.
+const LMS_TO_LINEAR_SRGB: [[f32; 3]; 3] = [
+    [4.076_741_7, -3.307_711_6, 0.230_969_94],
+    [-1.268_438, 2.609_757_4, -0.341_319_38],
+    [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+];
+
+/// Returns the factors by which the cube roots of the cone responses change
+/// with chroma along the hue `[a, b]`.
+fn lms_per_chroma([a, b]: [f32; 2]) -> [f32; 3] {
+    OKLAB_TO_LMS.map(|[ka, kb]| ka * a + kb * b)
+}
+
+fn oklab_to_linear_srgb([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let [kl, km, ks] = lms_per_chroma([a, b]);
+    let lms = [l + kl, l + km, l + ks].map(|c| c * c * c);
+    LMS_TO_LINEAR_SRGB.map(|row| row[0] * lms[0] + row[1] * lms[1] + row[2] * lms[2])
+}
+
+/// Returns the largest saturation `C / L` in the sRGB gamut along the
+/// normalized hue `[a, b]`, from a polynomial fit refined by a step of
+/// Halley's method.
+fn max_saturation(hue: [f32; 2]) -> f32 {
+    let [a, b] = hue;
+    // Which channel of sRGB reaches zero first, and the fit for where.
+    let (k, channel) = if -1.881_703_3 * a - 0.809_364_9 * b > 1.0 {
+        (
+            [
+                1.190_862_8,
+                1.765_767_3,
+                0.596_626_4,
+                0.755_152,
+                0.567_712_4,
+            ],
+            0,
+        )
+    } else if 1.814_441 * a - 1.194_452_8 * b > 1.0 {
+        (
+            [
+                0.739_565_15,
+                -0.459_544,
+                0.082_854_27,
+                0.125_410_7,
+                0.145_032_04,
+            ],
+            1,
+        )
+    } else {
+        (
+            [
+                1.357_336_5,
+                -0.009_157_99,
+                -1.151_302_1,
+                -0.505_596_04,
+                0.006_921_67,
+            ],
+            2,
+        )
+    };
+    let saturation = k[0] + k[1] * a + k[2] * b + k[3] * a * a + k[4] * a * b;
+
+    let w = LMS_TO_LINEAR_SRGB[channel];
+    let per_chroma = lms_per_chroma(hue);
+    let roots = per_chroma.map(|k| 1.0 + saturation * k);
+    let dot = |v: [f32; 3]| w[0] * v[0] + w[1] * v[1] + w[2] * v[2];
+    let f = dot(core::array::from_fn(|i| roots[i] * roots[i] * roots[i]));
+    let f1 = dot(core::array::from_fn(|i| {
+        3.0 * per_chroma[i] * roots[i] * roots[i]
+    }));
+    let f2 = dot(core::array::from_fn(|i| {
+        6.0 * per_chroma[i] * per_chroma[i] * roots[i]
+    }));
+
+    saturation - f * f1 / (f1 * f1 - 0.5 * f * f2)
+}
+
+/// Returns the `[L, C]` of the most chromatic color in the sRGB gamut along
+/// the normalized hue `[a, b]`.
+fn find_cusp(hue: [f32; 2]) -> [f32; 2] {
+    let saturation = max_saturation(hue);
+    let [r, g, b] = oklab_to_linear_srgb([1.0, saturation * hue[0], saturation * hue[1]]);
+    let l = (1.0 / r.max(g).max(b)).cbrt();
+    [l, l * saturation]
+}
+
+/// Returns how far along the line from `[l0, 0]` to `[l1, c1]`, at the
+/// normalized hue `[a, b]`, the boundary of the sRGB gamut is.
+fn find_gamut_intersection(hue: [f32; 2], l1: f32, c1: f32, l0: f32, cusp: [f32; 2]) -> f32 {
+    let [cusp_l, cusp_c] = cusp;
+    if (l1 - l0) * cusp_c - (cusp_l - l0) * c1 <= 0.0 {
+        // The lower half of the gamut triangle, which is exactly linear.
+        return cusp_c * l0 / (c1 * cusp_l + cusp_c * (l0 - l1));
+    }
+
+    // The upper half, which is curved, approximated by the triangle and then
+    // refined by a step of Halley's method on each channel.
+    let t = cusp_c * (l0 - 1.0) / (c1 * (cusp_l - 1.0) + cusp_c * (l0 - l1));
+    let per_chroma = lms_per_chroma(hue);
+    let d_roots = per_chroma.map(|k| l1 - l0 + c1 * k);
+    let (l, c) = (l0 * (1.0 - t) + t * l1, t * c1);
+    let roots = per_chroma.map(|k| l + c * k);
+
+    let lms: [f32; 3] = core::array::from_fn(|i| roots[i] * roots[i] * roots[i]);
+    let lms1: [f32; 3] = core::array::from_fn(|i| 3.0 * d_roots[i] * roots[i] * roots[i]);
+    let lms2: [f32; 3] = core::array::from_fn(|i| 6.0 * d_roots[i] * d_roots[i] * roots[i]);
+
+    let step = LMS_TO_LINEAR_SRGB
+        .iter()
+        .map(|w| {
+            let dot = |v: [f32; 3]| w[0] * v[0] + w[1] * v[1] + w[2] * v[2];
+            let (f, f1, f2) = (dot(lms) - 1.0, dot(lms1), dot(lms2));
+            let u = f1 / (f1 * f1 - 0.5 * f * f2);
+            if u >= 0.0 {
+                -f * u
+            } else {
+                f32::MAX
+            }
+        })
+        .fold(f32::MAX, f32::min);
+
+    t + step
+}
+
+const TOE_K1: f32 = 0.206;
+const TOE_K2: f32 = 0.03;
+const TOE_K3: f32 = (1.0 + TOE_K1) / (1.0 + TOE_K2);
+
+/// Maps Oklab lightness to a lightness estimate closer to CIELAB's in the
+/// darks, which Okhsl and Okhsv use.
+fn toe(x: f32) -> f32 {
+    let y = TOE_K3 * x - TOE_K1;
+    0.5 * (y + (y * y + 4.0 * TOE_K2 * TOE_K3 * x).sqrt())
+}
+
+fn toe_inv(x: f32) -> f32 {
+    (x * x + TOE_K1 * x) / (TOE_K3 * (x + TOE_K2))
+}
+
+/// Returns the slopes `[C / L, C / (1 - L)]` of the two sides of the gamut
+/// triangle with its apex at `cusp`.
+fn cusp_slopes([l, c]: [f32; 2]) -> [f32; 2] {
+    [c / l, c / (1.0 - l)]
+}
+
+/// Returns a smooth polynomial fit of the slopes of the gamut triangle at the
+/// normalized hue `[a, b]`, used for the middle of the saturation range.
+fn mid_slopes([a, b]: [f32; 2]) -> [f32; 2] {
+    let s = 0.115_169_93
+        + 1.0
+            / (7.447_79
+                + 4.159_012_4 * b
+                + a * (-2.195_573_4
+                    + 1.751_984 * b
+                    + a * (-2.137_049_4 - 10.023_01 * b
+                        + a * (-4.248_945_7 + 5.387_708 * b + 4.698_91 * a))));
+    let t = 0.112_396_42
+        + 1.0
+            / (1.613_203_2 - 0.681_243_8 * b
+                + a * (0.403_706_13
+                    + 0.901_481_2 * b
+                    + a * (-0.270_879_44
+                        + 0.612_239_9 * b
+                        + a * (0.002_992_15 - 0.453_995_68 * b - 0.146_618_72 * a))));
+    [s, t]
+}
+
+/// Returns the chroma Okhsl saturations of zero, `0.8` and one are mapped
+/// to at lightness `l` and the normalized hue `[a, b]`.
+fn okhsl_chromas(l: f32, hue: [f32; 2]) -> [f32; 3] {
+    let cusp = find_cusp(hue);
+    let c_max = find_gamut_intersection(hue, l, 1.0, l, cusp);
+    let [s_max, t_max] = cusp_slopes(cusp);
+
+    // Scales the smooth fit to meet the curved part of the gamut.
+    let k = c_max / (l * s_max).min((1.0 - l) * t_max);
+
+    let [s_mid, t_mid] = mid_slopes(hue);
+    let (c_a, c_b) = (l * s_mid, (1.0 - l) * t_mid);
+    let c_mid = 0.9
+        * k
+        * (1.0 / (1.0 / c_a.powi(4) + 1.0 / c_b.powi(4)))
+            .sqrt()
+            .sqrt();
+
+    // Roughly the average slopes over every hue, so that low saturations
+    // look alike whatever the hue.
+    let (c_a, c_b) = (l * 0.4, (1.0 - l) * 0.8);
+    let c_0 = (1.0 / (1.0 / (c_a * c_a) + 1.0 / (c_b * c_b))).sqrt();
+
+    [c_0, c_mid, c_max]
+}
+
+/// The Okhsl saturation that maps to the middle chroma.
+const OKHSL_MID: f32 = 0.8;
+
+/// Returns the normalized hue of Oklab `[a, b]`, or that of red for grays,
+/// whose hue doesn't matter.
+fn normalized_hue(a: f32, b: f32, chroma: f32) -> [f32; 2] {
+    if chroma > 0.0 {
+        [a / chroma, b / chroma]
+    } else {
+        [1.0, 0.0]
+    }
+}
+
+fn hue_degrees(a: f32, b: f32) -> f32 {
+    b.atan2(a).to_degrees().rem_euclid(360.0)
+}
+
+/// Converts Oklab to Okhsl.
+pub fn oklab_to_okhsl([l, a, b, alpha]: [f32; 4]) -> [f32; 4] {
+    let c = a.hypot(b);
+    let h = hue_degrees(a, b);
+    if l <= 0.0 || l >= 1.0 || c == 0.0 {
+        return [h, 0.0, toe(l.clamp(0.0, 1.0)), alpha];
+    }
+
+    let [c_0, c_mid, c_max] = okhsl_chromas(l, normalized_hue(a, b, c));
+    let s = if c < c_mid {
+        let k_1 = OKHSL_MID * c_0;
+        let k_2 = 1.0 - k_1 / c_mid;
+        OKHSL_MID * c / (k_1 + k_2 * c)
+    } else {
+        let k_1 = (1.0 - OKHSL_MID) * c_mid * c_mid / (OKHSL_MID * OKHSL_MID * c_0);
+        let k_2 = 1.0 - k_1 / (c_max - c_mid);
+        let t = (c - c_mid) / (k_1 + k_2 * (c - c_mid));
+        OKHSL_MID + (1.0 - OKHSL_MID) * t
+    };
+
+    [h, s, toe(l), alpha]
+}
+
+/// Converts Okhsl to Oklab, inverting [`oklab_to_okhsl`].
+pub fn okhsl_to_oklab([h, s, l, alpha]: [f32; 4]) -> [f32; 4] {
+    if l >= 1.0 {
+        return [1.0, 0.0, 0.0, alpha];
+    }
+    if l <= 0.0 {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+
+    let (sin, cos) = h.to_radians().sin_cos();
+    let lightness = toe_inv(l);
+    let [c_0, c_mid, c_max] = okhsl_chromas(lightness, [cos, sin]);
+    let c = if s < OKHSL_MID {
+        let t = s / OKHSL_MID;
+        let k_1 = OKHSL_MID * c_0;
+        let k_2 = 1.0 - k_1 / c_mid;
+        t * k_1 / (1.0 - k_2 * t)
+    } else {
+        let t = (s - OKHSL_MID) / (1.0 - OKHSL_MID);
+        let k_1 = (1.0 - OKHSL_MID) * c_mid * c_mid / (OKHSL_MID * OKHSL_MID * c_0);
+        let k_2 = 1.0 - k_1 / (c_max - c_mid);
+        c_mid + t * k_1 / (1.0 - k_2 * t)
+    };
+
+    [lightness, c * cos, c * sin, alpha]
+}
+
+/// The Okhsv saturation of the sRGB gamut's edge from white, before
+/// accounting for the curvature of the gamut.
+const OKHSV_S0: f32 = 0.5;
+
+/// Returns the scale that takes lightness and chroma on the edge of the
+/// idealized gamut triangle at `[l_v, c_v]` onto the real, curved, gamut.
+fn okhsv_scale(l_v: f32, c_v: f32, hue: [f32; 2]) -> f32 {
+    let l_vt = toe_inv(l_v);
+    let c_vt = c_v * l_vt / l_v;
+    let [r, g, b] = oklab_to_linear_srgb([l_vt, hue[0] * c_vt, hue[1] * c_vt]);
+    (1.0 / r.max(g).max(b).max(0.0)).cbrt()
+}
+
+/// Converts Oklab to Okhsv.
+pub fn oklab_to_okhsv([l, a, b, alpha]: [f32; 4]) -> [f32; 4] {
+    let c = a.hypot(b);
+    let h = hue_degrees(a, b);
+    if l <= 0.0 {
+        return [h, 0.0, 0.0, alpha];
+    }
+
+    let hue = normalized_hue(a, b, c);
+    let [s_max, t_max] = cusp_slopes(find_cusp(hue));
+    let k = 1.0 - OKHSV_S0 / s_max;
+
+    // Where the line from black through the color meets the top of the
+    // gamut triangle.
+    let t = t_max / (c + l * t_max);
+    let (l_v, c_v) = (t * l, t * c);
+
+    let scale = okhsv_scale(l_v, c_v, hue);
+    let (l, c) = (l / scale, c / scale);
+    let (l, c) = (toe(l), c * toe(l) / l);
+
+    let v = l / l_v;
+    let s = (OKHSV_S0 + t_max) * c_v / (t_max * OKHSV_S0 + t_max * k * c_v);
+    [h, s, v, alpha]
+}
+
+/// Converts Okhsv to Oklab, inverting [`oklab_to_okhsv`].
+pub fn okhsv_to_oklab([h, s, v, alpha]: [f32; 4]) -> [f32; 4] {
+    if v <= 0.0 {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+
+    let (sin, cos) = h.to_radians().sin_cos();
+    let hue = [cos, sin];
+    let [s_max, t_max] = cusp_slopes(find_cusp(hue));
+    let k = 1.0 - OKHSV_S0 / s_max;
+
+    // The lightness and chroma as if the gamut were a triangle, at v = 1 and
+    // then scaled by v.
+    let denominator = OKHSV_S0 + t_max - t_max * k * s;
+    let l_v = 1.0 - s * OKHSV_S0 / denominator;
+    let c_v = s * t_max * OKHSV_S0 / denominator;
+    let (l, c) = (v * l_v, v * c_v);
+
+    // Undoes the toe and the curvature of the top of the gamut.
+    let l_new = toe_inv(l);
+    let c = c * l_new / l;
+    let scale = okhsv_scale(l_v, c_v, hue);
+    let (l, c) = (l_new * scale, c * scale);
+
+    [l, c * cos, c * sin, alpha]
+}
@@ -0,0 +1,199 @@
+//! Palette extraction and mapping for indexed color.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    color_space::{convert, ColorSpace},
+    rgb_color_space::RgbColorSpace,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+const SRGB: ColorSpace = ColorSpace::Rgb(RgbColorSpace::SRGB);
+
+fn distance_squared(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// Reduces the sRGB `image` to a palette of at most `n_colors` sRGB colors
+/// using median cut in `space`, returning the palette and each pixel's index
+/// into it.
+///
+/// Quantizing in a perceptual space such as [`ColorSpace::Oklab`] gives
+/// noticeably better palettes than quantizing in sRGB itself.
+///
+/// # Panics
+///
+/// Panics if `n_colors` is zero or more than 256, which `u8` indices cannot
+/// address.
+pub fn quantize(
+    image: &[[f32; 4]],
+    n_colors: usize,
+    space: &ColorSpace,
+) -> (Vec<[f32; 4]>, Vec<u8>) {
+    assert!(
+        (1..=256).contains(&n_colors),
+        "n_colors must be between 1 and 256, got {n_colors}"
+    );
+
+    let converted: Vec<[f32; 4]> = image.iter().map(|&p| convert(p, &SRGB, space)).collect();
+    let mut boxes: Vec<Vec<usize>> = vec![(0..image.len()).collect()];
+
+    while boxes.len() < n_colors {
+        // Split the box with the widest channel range at its median.
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, members)| {
+                let (channel, range) = widest_channel(&converted, members);
+                (i, channel, range)
+            })
+            .filter(|&(_, _, range)| range > 0.0)
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+        let Some((i, channel, _)) = widest else {
+            break;
+        };
+
+        let mut members = boxes.swap_remove(i);
+        members.sort_by(|&a, &b| converted[a][channel].total_cmp(&converted[b][channel]));
+        let upper = members.split_off(median_split(&converted, &members, channel));
+        boxes.push(members);
+        boxes.push(upper);
+    }
+
+    let mut indices = vec![0; image.len()];
+    let palette = boxes
+        .iter()
+        .filter(|members| !members.is_empty())
+        .enumerate()
+        .map(|(index, members)| {
+            let mut mean = [0.0; 4];
+            for &member in members {
+                indices[member] = index as u8;
+                for c in 0..4 {
+                    mean[c] += converted[member][c];
+                }
+            }
+            convert(mean.map(|c| c / members.len() as f32), space, &SRGB)
+        })
+        .collect();
+
+    (palette, indices)
+}
+
+/// Returns where to split `members`, sorted by `channel`, as close to the
+/// median as possible without separating pixels with the same value in it,
+/// so that a box of a few distinct colors is split between them.
+fn median_split(pixels: &[[f32; 4]], members: &[usize], channel: usize) -> usize {
+    let median = members.len() / 2;
+    let is_boundary = |&k: &usize| pixels[members[k - 1]][channel] != pixels[members[k]][channel];
+    let below = (1..=median).rev().find(is_boundary);
+    let above = (median..members.len()).find(is_boundary);
+    match (below, above) {
+        (Some(below), Some(above)) if median - below <= above - median => below,
+        (_, Some(above)) => above,
+        (below, None) => below.unwrap_or(median),
+    }
+}
+
+/// Returns the channel of `members` with the widest range and that range.
+fn widest_channel(pixels: &[[f32; 4]], members: &[usize]) -> (usize, f32) {
+    (0..4)
+        .map(|channel| {
+            let (min, max) = members.iter().fold((f32::MAX, f32::MIN), |(min, max), &i| {
+                (min.min(pixels[i][channel]), max.max(pixels[i][channel]))
+            });
+            (channel, max - min)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+/// Returns the index of the entry in the sRGB `palette` closest to the sRGB
+/// `pixel`, measured by Euclidean distance in `space`.
+///
+/// Measuring in a perceptual space such as [`ColorSpace::Oklab`] matches what
+/// viewers consider closest far better than measuring in sRGB. When mapping
+/// many pixels or using a large palette prefer a [`PaletteIndex`].
+///
+/// # Panics
+///
+/// Panics if `palette` is empty.
+pub fn nearest_in_palette(pixel: [f32; 4], palette: &[[f32; 4]], space: &ColorSpace) -> usize {
+    let pixel = convert(pixel, &SRGB, space);
+    palette
+        .iter()
+        .map(|&entry| distance_squared(pixel, convert(entry, &SRGB, space)))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+        .expect("palette must not be empty")
+}
+
+/// A palette converted into a working space once and sorted along its first
+/// channel, for fast repeated nearest-color lookups.
+#[derive(Debug, Clone)]
+pub struct PaletteIndex {
+    space: ColorSpace,
+    /// The converted entries and their original indices, sorted by the first
+    /// channel.
+    entries: Vec<([f32; 4], usize)>,
+}
+
+impl PaletteIndex {
+    /// Builds an index over the sRGB `palette` measuring distance in `space`.
+    pub fn new(palette: &[[f32; 4]], space: &ColorSpace) -> PaletteIndex {
+        let mut entries: Vec<_> = palette
+            .iter()
+            .enumerate()
+            .map(|(i, &entry)| (convert(entry, &SRGB, space), i))
+            .collect();
+        entries.sort_by(|a, b| a.0[0].total_cmp(&b.0[0]));
+
+        PaletteIndex {
+            space: *space,
+            entries,
+        }
+    }
+
+    /// Returns the same index as [`nearest_in_palette`], searching outward
+    /// from the pixel's first channel and stopping once that channel alone is
+    /// further than the best match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the palette is empty.
+    pub fn nearest(&self, pixel: [f32; 4]) -> usize {
+        let pixel = convert(pixel, &SRGB, &self.space);
+        let start = self
+            .entries
+            .partition_point(|(entry, _)| entry[0] < pixel[0]);
+
+        // Seeding from an entry rather than infinity keeps a match for
+        // pixels whose distances are all NaN or infinite.
+        let &(first, index) = self
+            .entries
+            .get(start)
+            .or(self.entries.last())
+            .expect("palette must not be empty");
+        let mut best = (distance_squared(pixel, first), index);
+        let mut search = |entries: &mut dyn Iterator<Item = &([f32; 4], usize)>| {
+            for &(entry, index) in entries {
+                if (entry[0] - pixel[0]).powi(2) > best.0 {
+                    break;
+                }
+                // Ordered as `nearest_in_palette` orders them, so that the
+                // two agree on ties and NaNs.
+                let distance = distance_squared(pixel, entry);
+                if distance.total_cmp(&best.0).then(index.cmp(&best.1)).is_lt() {
+                    best = (distance, index);
+                }
+            }
+        };
+        search(&mut self.entries[start..].iter());
+        search(&mut self.entries[..start].iter().rev());
+
+        best.1
+    }
+}
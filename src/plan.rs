@@ -0,0 +1,804 @@
+//! Precomputed conversions between a pair of color spaces.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    adaptation::ChromaticAdaptation,
+    color_space::{
+        convert_luminance, hsv_to_hwb, hsv_to_rgb, hwb_to_hsv, lab_to_lch, lab_to_xyz, lch_to_lab,
+        rgb_to_hsv, xyy_to_xyz, xyz_to_lab, xyz_to_xyy, ColorSpace, Luminance,
+    },
+    matrix::RowMatrix,
+    okhsl::{okhsl_to_oklab, okhsv_to_oklab, oklab_to_okhsl, oklab_to_okhsv},
+    transfer::{tone_map_bt2390, Direction, Transfer},
+    whitepoint::Whitepoint,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// A single operation in a [`ConversionPlan`]. Every step leaves alpha
+/// untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// Decodes the color channels into linear light.
+    DecodeTransfer(Transfer),
+    /// Encodes the color channels from linear light.
+    EncodeTransfer(Transfer),
+    /// Multiplies the color channels by a matrix.
+    Matrix(RowMatrix),
+    /// Takes the cube root of each color channel.
+    Cbrt,
+    /// Takes the cube root of each color channel with [`approx_cbrt`], to
+    /// within a relative error of [`APPROX_CBRT_ERROR`].
+    ApproxCbrt,
+    /// Cubes each color channel.
+    Cube,
+    /// Converts encoded RGB to HWB.
+    RgbToHwb,
+    /// Converts HWB to encoded RGB.
+    HwbToRgb,
+    /// Converts XYZ to xyY, giving black the white's chromaticity.
+    XyzToXyy(Whitepoint),
+    /// Converts xyY to XYZ.
+    XyyToXyz,
+    /// Converts XYZ to CIELAB relative to the white.
+    XyzToLab(Whitepoint),
+    /// Converts CIELAB relative to the white to XYZ.
+    LabToXyz(Whitepoint),
+    /// Converts Lab-like channels to their cylindrical LCh form.
+    LabToLch,
+    /// Converts cylindrical LCh channels to their Lab-like form.
+    LchToLab,
+    /// Converts Oklab to Okhsl.
+    OklabToOkhsl,
+    /// Converts Okhsl to Oklab.
+    OkhslToOklab,
+    /// Converts Oklab to Okhsv.
+    OklabToOkhsv,
+    /// Converts Okhsv to Oklab.
+    OkhsvToOklab,
+    /// Desaturates linear RGB toward the gray of the same luminance, computed
+    /// with these weights, until every channel is within `[0, 1]`.
+    DesaturateToGamut([f32; 3]),
+    /// Clamps each linear RGB channel to `[0, 1]`.
+    ClipToGamut,
+    /// Multiplies the XYZ channels by `scale` and adds `offset`, moving one
+    /// black point onto another while keeping the white.
+    BlackPointCompensation { scale: f32, offset: [f32; 3] },
+    /// Tone maps linear RGB, with white shown at `dst_peak` cd/m², from a
+    /// peak of `src_peak` cd/m² down to `dst_peak` with
+    /// [`tone_map_bt2390`].
+    ToneMapBt2390 { src_peak: f32, dst_peak: f32 },
+}
+
+/// How colors are mapped between spaces, following the ICC rendering intents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderingIntent {
+    /// Keeps XYZ values exactly, so the source white is reproduced as the
+    /// same color rather than as the destination white. No chromatic
+    /// adaptation is applied, so grays of a D50 source show as warm
+    /// off-whites on a D65 display.
+    Absolute,
+    /// Adapts the source white to the destination white and otherwise keeps
+    /// XYZ values, leaving out of gamut colors out of gamut.
+    #[default]
+    RelativeColorimetric,
+    /// Like [`RenderingIntent::RelativeColorimetric`] but desaturates out of
+    /// gamut colors into an RGB destination's gamut, preserving luminance.
+    Perceptual,
+    /// Like [`RenderingIntent::RelativeColorimetric`] but clips out of gamut
+    /// colors to an RGB destination's gamut, preserving saturation.
+    Saturation,
+}
+
+/// Options for planning a conversion beyond the two spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConversionOptions {
+    pub intent: RenderingIntent,
+    /// Whether to scale XYZ values so that the source's black, as given by
+    /// [`ColorSpace::black_luminance`], becomes the destination's black.
+    ///
+    /// Without it absolute luminances are kept, so a source black brighter
+    /// than the destination's shows as a washed out dark gray and a source
+    /// black darker than it is crushed. The white is kept either way.
+    pub black_point_compensation: bool,
+    /// The luminance of the source's reference white.
+    ///
+    /// If this differs from [`Self::destination_luminance`], linear values
+    /// are scaled by the ratio of the two so that absolute luminance is kept.
+    /// If they are the same, as by default, white maps to white.
+    pub source_luminance: Luminance,
+    /// The luminance of the destination's reference white.
+    pub destination_luminance: Luminance,
+    /// Whether to take the cube roots into Oklab, and the spaces built on
+    /// it, with the faster [`Step::ApproxCbrt`].
+    ///
+    /// This moves Oklab values by at most [`OKLAB_APPROX_ERROR`], well below
+    /// a just noticeable difference, for real-time use. CIELAB is always
+    /// exact.
+    pub approximate_cbrt: bool,
+    /// Whether to bring a source brighter than the destination within the
+    /// destination's range with the BT.2390 EETF, rather than scaling
+    /// absolute luminance past it.
+    ///
+    /// The luminances of the reference whites are taken as the peaks of the
+    /// two displays, so the source white lands on the destination white
+    /// while shadows and mid-tones below the curve's knee keep their
+    /// absolute luminance. Only RGB destinations are tone mapped, in their
+    /// linear RGB, and a source no brighter than the destination is
+    /// unaffected.
+    pub tone_map: bool,
+}
+
+/// The largest relative error of [`approx_cbrt`] for normal numbers.
+pub const APPROX_CBRT_ERROR: f32 = 1.1e-3;
+
+/// The largest difference in any Oklab channel between converting from
+/// sRGB with [`ConversionOptions::approximate_cbrt`] and without it.
+pub const OKLAB_APPROX_ERROR: f32 = 2e-3;
+
+/// Returns an approximate cube root of `x`, to within a relative error of
+/// [`APPROX_CBRT_ERROR`].
+///
+/// The exponent is divided by three in the bits of the float, giving an
+/// estimate within a few percent, which one Newton step then refines. This
+/// avoids the several iterations and careful rounding of an exact `cbrt`.
+pub fn approx_cbrt(x: f32) -> f32 {
+    let magnitude = x.abs();
+    if magnitude < f32::MIN_POSITIVE || !magnitude.is_finite() {
+        // The estimate is poor for zero, subnormals and infinities.
+        return x.cbrt();
+    }
+    let estimate = f32::from_bits(magnitude.to_bits() / 3 + 0x2a51_37a0);
+    let root = (2.0 * estimate + magnitude / (estimate * estimate)) / 3.0;
+    root.copysign(x)
+}
+
+/// How a pixel's color channels are stored relative to its alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    /// The color channels are independent of alpha, as in PNG.
+    #[default]
+    Straight,
+    /// The color channels have been multiplied by alpha, as in many GPU
+    /// textures and compositors.
+    Premultiplied,
+}
+
+impl AlphaMode {
+    /// Returns `pixel`, stored in this mode, with straight color channels.
+    ///
+    /// A fully transparent pixel has no color to recover, so its color
+    /// channels become zero.
+    #[must_use]
+    pub fn to_straight(self, pixel: [f32; 4]) -> [f32; 4] {
+        let [c0, c1, c2, alpha] = pixel;
+        match self {
+            AlphaMode::Straight => pixel,
+            AlphaMode::Premultiplied if alpha == 0.0 => [0.0, 0.0, 0.0, alpha],
+            AlphaMode::Premultiplied => [c0 / alpha, c1 / alpha, c2 / alpha, alpha],
+        }
+    }
+
+    /// Returns the straight `pixel` stored in this mode.
+    #[must_use]
+    pub fn from_straight(self, pixel: [f32; 4]) -> [f32; 4] {
+        let [c0, c1, c2, alpha] = pixel;
+        match self {
+            AlphaMode::Straight => pixel,
+            AlphaMode::Premultiplied => [c0 * alpha, c1 * alpha, c2 * alpha, alpha],
+        }
+    }
+}
+
+impl Step {
+    /// Returns whether applying `next` straight after this step gives back
+    /// the original pixel, up to rounding.
+    fn is_undone_by(&self, next: &Step) -> bool {
+        match (self, next) {
+            (Step::EncodeTransfer(a), Step::DecodeTransfer(b))
+            | (Step::DecodeTransfer(a), Step::EncodeTransfer(b)) => a == b,
+            (Step::XyzToLab(a), Step::LabToXyz(b)) | (Step::LabToXyz(a), Step::XyzToLab(b)) => {
+                a == b
+            }
+            // Not the other way around, as xyY black loses its chromaticity
+            // and HWB grays lose their hue.
+            (Step::XyzToXyy(_), Step::XyyToXyz) | (Step::RgbToHwb, Step::HwbToRgb) => true,
+            (Step::Cbrt | Step::ApproxCbrt, Step::Cube)
+            | (Step::Cube, Step::Cbrt | Step::ApproxCbrt)
+            | (Step::LabToLch, Step::LchToLab)
+            | (Step::OklabToOkhsl, Step::OkhslToOklab)
+            | (Step::OklabToOkhsv, Step::OkhsvToOklab) => true,
+            _ => false,
+        }
+    }
+
+    #[must_use]
+    pub fn apply(&self, pixel: [f32; 4]) -> [f32; 4] {
+        let [c0, c1, c2, alpha] = pixel;
+        match self {
+            Step::DecodeTransfer(transfer) => transfer.to_optical_display(pixel),
+            Step::EncodeTransfer(transfer) => transfer.from_optical_display(pixel),
+            Step::Matrix(matrix) => {
+                let [c0, c1, c2] = matrix.mul_vec([c0, c1, c2]);
+                [c0, c1, c2, alpha]
+            }
+            Step::Cbrt => [c0.cbrt(), c1.cbrt(), c2.cbrt(), alpha],
+            Step::ApproxCbrt => [approx_cbrt(c0), approx_cbrt(c1), approx_cbrt(c2), alpha],
+            Step::Cube => [c0 * c0 * c0, c1 * c1 * c1, c2 * c2 * c2, alpha],
+            Step::RgbToHwb => hsv_to_hwb(rgb_to_hsv(pixel)),
+            Step::HwbToRgb => hsv_to_rgb(hwb_to_hsv(pixel)),
+            Step::XyzToXyy(whitepoint) => xyz_to_xyy(pixel, *whitepoint),
+            Step::XyyToXyz => xyy_to_xyz(pixel),
+            Step::XyzToLab(whitepoint) => xyz_to_lab(pixel, *whitepoint),
+            Step::LabToXyz(whitepoint) => lab_to_xyz(pixel, *whitepoint),
+            Step::LabToLch => lab_to_lch(pixel),
+            Step::LchToLab => lch_to_lab(pixel),
+            Step::OklabToOkhsl => oklab_to_okhsl(pixel),
+            Step::OkhslToOklab => okhsl_to_oklab(pixel),
+            Step::OklabToOkhsv => oklab_to_okhsv(pixel),
+            Step::OkhsvToOklab => okhsv_to_oklab(pixel),
+            Step::DesaturateToGamut(weights) => {
+                let [c0, c1, c2] = desaturate_to_gamut([c0, c1, c2], *weights);
+                [c0, c1, c2, alpha]
+            }
+            Step::ClipToGamut => [
+                c0.clamp(0.0, 1.0),
+                c1.clamp(0.0, 1.0),
+                c2.clamp(0.0, 1.0),
+                alpha,
+            ],
+            Step::BlackPointCompensation { scale, offset } => [
+                c0 * scale + offset[0],
+                c1 * scale + offset[1],
+                c2 * scale + offset[2],
+                alpha,
+            ],
+            Step::ToneMapBt2390 { src_peak, dst_peak } => {
+                let nits = [c0 * dst_peak, c1 * dst_peak, c2 * dst_peak, alpha];
+                let [c0, c1, c2, _] = tone_map_bt2390(nits, *src_peak, *dst_peak);
+                [c0 / dst_peak, c1 / dst_peak, c2 / dst_peak, alpha]
+            }
+        }
+    }
+}
+
+/// The steps converting pixels from one color space to another, worked out
+/// once so that converting many pixels doesn't repeat matrix inversion and
+/// multiplication.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionPlan {
+    steps: Vec<Step>,
+}
+
+impl ConversionPlan {
+    /// Plans the conversion from `from` to `to` through XYZ, adapting between
+    /// their whitepoints with the Bradford transform if they differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either space is degenerate, as [`Self::try_new`] reports.
+    pub fn new(from: &ColorSpace, to: &ColorSpace) -> ConversionPlan {
+        ConversionPlan::with_intent(from, to, RenderingIntent::default())
+    }
+
+    /// Plans the conversion from `from` to `to` like [`Self::new`], returning
+    /// an error instead of panicking if either space is degenerate, such as
+    /// an RGB space whose custom whitepoint has a `y` of zero or NaN.
+    pub fn try_new(from: &ColorSpace, to: &ColorSpace) -> Result<ConversionPlan, ConvertError> {
+        check_space(from)?;
+        check_space(to)?;
+        Ok(ConversionPlan::new(from, to))
+    }
+
+    /// Plans the conversion from `from` to `to` through XYZ following
+    /// `intent`.
+    pub fn with_intent(
+        from: &ColorSpace,
+        to: &ColorSpace,
+        intent: RenderingIntent,
+    ) -> ConversionPlan {
+        ConversionPlan::with_options(
+            from,
+            to,
+            ConversionOptions {
+                intent,
+                ..ConversionOptions::default()
+            },
+        )
+    }
+
+    /// Plans the conversion from `from` to `to` through XYZ following
+    /// `options`.
+    pub fn with_options(
+        from: &ColorSpace,
+        to: &ColorSpace,
+        options: ConversionOptions,
+    ) -> ConversionPlan {
+        let (from_luminance, to_luminance) =
+            (options.source_luminance, options.destination_luminance);
+        if from == to && from_luminance == to_luminance {
+            return ConversionPlan { steps: Vec::new() };
+        }
+
+        let intent = options.intent;
+        let mut steps = from.steps_to_xyz();
+        let (from_white, to_white) = (from.whitepoint(), to.whitepoint());
+        if from_white != to_white && intent != RenderingIntent::Absolute {
+            steps.push(Step::Matrix(
+                ChromaticAdaptation::Bradford.matrix(from_white, to_white),
+            ));
+        }
+
+        let (from_black, to_black) = (from.black_luminance(), to.black_luminance());
+        if options.black_point_compensation && from_black != to_black && from_black < 1.0 {
+            // The white the XYZ values are relative to at this point, which
+            // is kept while the blacks are moved along the gray axis toward it.
+            let white = if intent == RenderingIntent::Absolute {
+                from_white
+            } else {
+                to_white
+            }
+            .to_xyz();
+            let scale = (1.0 - to_black) / (1.0 - from_black);
+            steps.push(Step::BlackPointCompensation {
+                scale,
+                offset: white.map(|c| c * (to_black - scale * from_black)),
+            });
+        }
+
+        if from_luminance != to_luminance {
+            let scale = convert_luminance(1.0, from_luminance, to_luminance);
+            steps.push(Step::Matrix(RowMatrix::diagonal([scale; 3])));
+        }
+
+        let mut to_steps = to.steps_from_xyz();
+        if options.approximate_cbrt {
+            for step in &mut to_steps {
+                if *step == Step::Cbrt {
+                    *step = Step::ApproxCbrt;
+                }
+            }
+        }
+        if let ColorSpace::Rgb(space) = to {
+            let gamut_step = match intent {
+                RenderingIntent::Perceptual => {
+                    Some(Step::DesaturateToGamut(space.to_xyz_matrix().0[1]))
+                }
+                RenderingIntent::Saturation => Some(Step::ClipToGamut),
+                _ => None,
+            };
+            // RGB spaces are entered by a matrix into linear RGB and then an
+            // encoding transfer, tone and gamut mapping go between the two.
+            if let Some(step) = gamut_step {
+                to_steps.insert(1, step);
+            }
+            let (src_peak, dst_peak) = (from_luminance.to_nits(), to_luminance.to_nits());
+            if options.tone_map && src_peak > dst_peak {
+                to_steps.insert(1, Step::ToneMapBt2390 { src_peak, dst_peak });
+            }
+        }
+        steps.extend(to_steps);
+
+        ConversionPlan::from_steps(steps)
+    }
+
+    /// Creates a plan from `steps`, dropping linear transfers, multiplying
+    /// adjacent matrices together and dropping pairs of adjacent steps that
+    /// undo each other, such as an encoding transfer followed by decoding
+    /// with the same transfer or a matrix that has fused into the identity.
+    ///
+    /// Dropping a transfer pair also drops any clamping the transfer does, so
+    /// values outside its range pass through unchanged.
+    pub fn from_steps(steps: impl IntoIterator<Item = Step>) -> ConversionPlan {
+        let mut fused: Vec<Step> = Vec::new();
+        for step in steps {
+            match (fused.last_mut(), step) {
+                (
+                    _,
+                    Step::DecodeTransfer(Transfer::Linear) | Step::EncodeTransfer(Transfer::Linear),
+                ) => {}
+                (Some(Step::Matrix(previous)), Step::Matrix(matrix)) => {
+                    *previous = matrix * *previous;
+                    if previous.is_near_identity() {
+                        fused.pop();
+                    }
+                }
+                (Some(previous), step) if previous.is_undone_by(&step) => {
+                    fused.pop();
+                }
+                (_, Step::Matrix(matrix)) if matrix.is_near_identity() => {}
+                _ => fused.push(step),
+            }
+        }
+
+        ConversionPlan { steps: fused }
+    }
+
+    /// Returns the plan applying this plan and then `next`, fusing the steps
+    /// where they meet as [`Self::from_steps`] does.
+    #[must_use]
+    pub fn then(self, next: ConversionPlan) -> ConversionPlan {
+        ConversionPlan::from_steps(self.steps.into_iter().chain(next.steps))
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    #[must_use]
+    pub fn apply(&self, pixel: [f32; 4]) -> [f32; 4] {
+        self.steps
+            .iter()
+            .fold(pixel, |pixel, step| step.apply(pixel))
+    }
+
+    /// Returns a closure converting one pixel, for passing to iterator
+    /// adapters such as `map` and for hot loops. The closure borrows the plan.
+    pub fn as_fn(&self) -> impl Fn([f32; 4]) -> [f32; 4] + '_ {
+        let steps = self.steps.as_slice();
+        move |pixel| steps.iter().fold(pixel, |pixel, step| step.apply(pixel))
+    }
+
+    /// Converts every pixel of `pixels` in place.
+    pub fn apply_slice(&self, pixels: &mut [[f32; 4]]) {
+        for step in &self.steps {
+            for pixel in pixels.iter_mut() {
+                *pixel = step.apply(*pixel);
+            }
+        }
+    }
+
+    /// Converts `pixel` stored in the first of `alpha_modes` to a pixel
+    /// stored in the second. The color channels are made straight before
+    /// conversion, because the transfers and any non-linear steps are only
+    /// meaningful for straight colors.
+    #[must_use]
+    pub fn apply_with_alpha(
+        &self,
+        pixel: [f32; 4],
+        alpha_modes: (AlphaMode, AlphaMode),
+    ) -> [f32; 4] {
+        let (from, to) = alpha_modes;
+        to.from_straight(self.apply(from.to_straight(pixel)))
+    }
+
+    /// Converts every pixel of `pixels` in place with
+    /// [`Self::apply_with_alpha`].
+    pub fn apply_slice_with_alpha(
+        &self,
+        pixels: &mut [[f32; 4]],
+        alpha_modes: (AlphaMode, AlphaMode),
+    ) {
+        for pixel in pixels {
+            *pixel = self.apply_with_alpha(*pixel, alpha_modes);
+        }
+    }
+
+    /// Converts `pixel`, first checking that the values every transfer
+    /// decodes are within its [`Transfer::signal_range`] and the values it
+    /// encodes are within its [`Transfer::linear_range`].
+    pub fn apply_checked(&self, pixel: [f32; 4]) -> Result<[f32; 4], DomainError> {
+        self.steps.iter().try_fold(pixel, |pixel, step| {
+            let (transfer, direction, range) = match *step {
+                Step::DecodeTransfer(transfer) => {
+                    (transfer, Direction::Decode, transfer.signal_range())
+                }
+                Step::EncodeTransfer(transfer) => {
+                    (transfer, Direction::Encode, transfer.linear_range())
+                }
+                _ => return Ok(step.apply(pixel)),
+            };
+            match pixel[..3].iter().position(|c| !range.contains(c)) {
+                Some(channel) => Err(DomainError {
+                    transfer,
+                    direction,
+                    channel,
+                    value: pixel[channel],
+                }),
+                None => Ok(step.apply(pixel)),
+            }
+        })
+    }
+
+    /// Converts `pixel`, returning an error instead of a NaN or infinite
+    /// result or when a transfer is given values outside its domain, as
+    /// [`Self::apply_checked`] checks. This never panics whatever the input.
+    pub fn try_apply(&self, pixel: [f32; 4]) -> Result<[f32; 4], ConvertError> {
+        self.try_apply_at(pixel, 0)
+    }
+
+    /// Converts every pixel of `pixels` in place like [`Self::try_apply`],
+    /// stopping at the first pixel that fails. Pixels before it are converted
+    /// and it and those after it are left untouched. This never panics
+    /// whatever the input.
+    pub fn try_apply_slice(&self, pixels: &mut [[f32; 4]]) -> Result<(), ConvertError> {
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = self.try_apply_at(*pixel, index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Converts `pixel`, the one at `index` of a slice, for [`Self::try_apply`]
+    /// and [`Self::try_apply_slice`].
+    fn try_apply_at(&self, pixel: [f32; 4], index: usize) -> Result<[f32; 4], ConvertError> {
+        check_finite(pixel, index).map_err(ConvertError::NonFiniteInput)?;
+        let converted = self
+            .apply_checked(pixel)
+            .map_err(|error| ConvertError::OutOfDomain { index, error })?;
+        check_finite(converted, index).map_err(ConvertError::NonFiniteOutput)?;
+        Ok(converted)
+    }
+}
+
+/// Lazily converts each pixel of `image` with `plan` as the iterator is
+/// advanced, for streaming images through iterator chains without holding
+/// them in memory.
+///
+/// This applies every step to one pixel before moving on to the next, which
+/// is slower than [`ConversionPlan::apply_slice`] for images that are already
+/// in memory.
+pub fn convert_iter<'a, I>(
+    image: I,
+    plan: &'a ConversionPlan,
+) -> impl Iterator<Item = [f32; 4]> + 'a
+where
+    I: IntoIterator<Item = [f32; 4]>,
+    I::IntoIter: 'a,
+{
+    image.into_iter().map(|pixel| plan.apply(pixel))
+}
+
+/// Converts a copy of `image` from `from` to `to`, which can be any pair of
+/// spaces, planning the conversion once for the whole image.
+///
+/// This is the image conversion of [`PixelConvert`](crate::traits::PixelConvert)
+/// for untyped pixels in any [`ColorSpace`] rather than only RGB. Use
+/// [`ConversionPlan::apply_slice`] to convert in place instead.
+pub fn convert_image_generic(
+    image: &[[f32; 4]],
+    from: &ColorSpace,
+    to: &ColorSpace,
+) -> Vec<[f32; 4]> {
+    let mut converted = image.to_vec();
+    ConversionPlan::new(from, to).apply_slice(&mut converted);
+    converted
+}
+
+/// How many pixels [`convert_to_planar`] converts at a time, few enough to
+/// stay in the L1 cache.
+const PLANAR_CHUNK: usize = 256;
+
+/// Converts `image` from `from` to `to` and splits it into one plane per
+/// channel, alpha last, as machine learning tensors and some codecs expect.
+///
+/// The image is converted in small chunks that are split as soon as they
+/// are converted, so each pixel is only read from memory once. The result is
+/// exactly what [`ConversionPlan::apply_slice`] gives, split into planes.
+pub fn convert_to_planar(image: &[[f32; 4]], from: &ColorSpace, to: &ColorSpace) -> [Vec<f32>; 4] {
+    let plan = ConversionPlan::new(from, to);
+    let mut planes: [Vec<f32>; 4] = core::array::from_fn(|_| Vec::with_capacity(image.len()));
+    let mut buffer = [[0.0; 4]; PLANAR_CHUNK];
+
+    for chunk in image.chunks(PLANAR_CHUNK) {
+        let converted = &mut buffer[..chunk.len()];
+        converted.copy_from_slice(chunk);
+        plan.apply_slice(converted);
+        for pixel in converted.iter() {
+            for (plane, c) in planes.iter_mut().zip(pixel) {
+                plane.push(*c);
+            }
+        }
+    }
+
+    planes
+}
+
+/// Plans that have already been worked out, keyed by the spaces they convert
+/// between, for converting many small batches between the same spaces
+/// without planning each again.
+///
+/// Lookup is a linear search, which is fast for the handful of pairs most
+/// applications use. Color spaces aren't `Hash` as they can hold floats.
+#[derive(Debug, Clone, Default)]
+pub struct PlanCache {
+    plans: Vec<(ColorSpace, ColorSpace, ConversionPlan)>,
+}
+
+impl PlanCache {
+    pub fn new() -> PlanCache {
+        PlanCache::default()
+    }
+
+    /// Returns the plan from `from` to `to`, working it out with
+    /// [`ConversionPlan::new`] only if it isn't already cached.
+    pub fn plan(&mut self, from: &ColorSpace, to: &ColorSpace) -> &ConversionPlan {
+        let index = match self
+            .plans
+            .iter()
+            .position(|(cached_from, cached_to, _)| cached_from == from && cached_to == to)
+        {
+            Some(index) => index,
+            None => {
+                self.plans.push((*from, *to, ConversionPlan::new(from, to)));
+                self.plans.len() - 1
+            }
+        };
+        &self.plans[index].2
+    }
+
+    /// Returns how many plans have been worked out and cached.
+    pub fn len(&self) -> usize {
+        self.plans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plans.is_empty()
+    }
+
+    /// Forgets every cached plan.
+    pub fn clear(&mut self) {
+        self.plans.clear();
+    }
+}
+
+/// Converts `pixel` from `from` to `to` like
+/// [`convert`](crate::color_space::convert), reusing the plan in `cache` if
+/// there is one.
+pub fn convert_cached(
+    pixel: [f32; 4],
+    from: &ColorSpace,
+    to: &ColorSpace,
+    cache: &mut PlanCache,
+) -> [f32; 4] {
+    cache.plan(from, to).apply(pixel)
+}
+
+/// Moves `rgb` toward the gray of equal luminance under `weights` just far
+/// enough for every channel to be within `[0, 1]`. Colors brighter than white
+/// or darker than black become white or black.
+fn desaturate_to_gamut(rgb: [f32; 3], weights: [f32; 3]) -> [f32; 3] {
+    let luminance: f32 = rgb.iter().zip(weights).map(|(c, w)| c * w).sum();
+    if luminance <= 0.0 {
+        return [0.0; 3];
+    }
+    if luminance >= 1.0 {
+        return [1.0; 3];
+    }
+
+    let t = rgb.iter().fold(1.0_f32, |t, &c| {
+        if c > 1.0 {
+            t.min((1.0 - luminance) / (c - luminance))
+        } else if c < 0.0 {
+            t.min(luminance / (luminance - c))
+        } else {
+            t
+        }
+    });
+
+    rgb.map(|c| (luminance + t * (c - luminance)).clamp(0.0, 1.0))
+}
+
+/// A value outside the range a transfer is defined for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DomainError {
+    pub transfer: Transfer,
+    /// Whether the value was being decoded, and so is outside
+    /// [`Transfer::signal_range`], or encoded, and so is outside
+    /// [`Transfer::linear_range`].
+    pub direction: Direction,
+    pub channel: usize,
+    pub value: f32,
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (range, verb) = match self.direction {
+            Direction::Decode => (self.transfer.signal_range(), "decodes"),
+            Direction::Encode => (self.transfer.linear_range(), "encodes"),
+        };
+        write!(
+            f,
+            "value {} in channel {} is outside [{}, {}], the range the {:?} transfer {verb}",
+            self.value,
+            self.channel,
+            range.start(),
+            range.end(),
+            self.transfer
+        )
+    }
+}
+
+impl core::error::Error for DomainError {}
+
+/// Where a NaN or infinite value was found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonFinite {
+    /// The index of the pixel within the slice.
+    pub index: usize,
+    pub channel: usize,
+    pub value: f32,
+}
+
+/// An error from the fallible conversions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvertError {
+    /// An input channel was NaN or infinite.
+    NonFiniteInput(NonFinite),
+    /// A channel became NaN or infinite during conversion, such as from
+    /// overflow.
+    NonFiniteOutput(NonFinite),
+    /// A transfer was given a value outside its domain, such as PQ given a
+    /// signal above `1.0`, in the pixel at `index`.
+    OutOfDomain { index: usize, error: DomainError },
+    /// A space has a whitepoint without a finite XYZ, or RGB primaries and a
+    /// whitepoint that give a singular matrix, so no plan can use it.
+    DegenerateSpace(ColorSpace),
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (stage, non_finite) = match self {
+            ConvertError::NonFiniteInput(non_finite) => ("input", non_finite),
+            ConvertError::NonFiniteOutput(non_finite) => ("output", non_finite),
+            ConvertError::OutOfDomain { index, error } => {
+                return write!(f, "in pixel {index}, {error}");
+            }
+            ConvertError::DegenerateSpace(space) => {
+                return write!(f, "{space:?} is degenerate and can't be converted");
+            }
+        };
+        write!(
+            f,
+            "non-finite {stage} value {} in channel {} of pixel {}",
+            non_finite.value, non_finite.channel, non_finite.index
+        )
+    }
+}
+
+impl core::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ConvertError::OutOfDomain { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Checks that `space` can be planned with, for [`ConversionPlan::try_new`].
+fn check_space(space: &ColorSpace) -> Result<(), ConvertError> {
+    let rgb = match space {
+        ColorSpace::Rgb(rgb) | ColorSpace::Hwb(rgb) => Some(*rgb),
+        ColorSpace::Yuv(yuv) => Some(*yuv.rgb()),
+        _ => None,
+    };
+    let white_is_finite = space.whitepoint().to_xyz().iter().all(|c| c.is_finite());
+    let rgb_is_invertible = match rgb {
+        Some(rgb) => rgb
+            .primaries
+            .try_to_xyz(rgb.whitepoint)
+            .and_then(RowMatrix::try_inv)
+            .is_some(),
+        None => true,
+    };
+
+    if white_is_finite && rgb_is_invertible {
+        Ok(())
+    } else {
+        Err(ConvertError::DegenerateSpace(*space))
+    }
+}
+
+fn check_finite(pixel: [f32; 4], index: usize) -> Result<(), NonFinite> {
+    match pixel.iter().position(|c| !c.is_finite()) {
+        Some(channel) => Err(NonFinite {
+            index,
+            channel,
+            value: pixel[channel],
+        }),
+        None => Ok(()),
+    }
+}
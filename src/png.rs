@@ -0,0 +1,257 @@
+//! Interpreting the color chunks of PNG images as a [`ColorSpace`].
+//!
+//! PNG describes color with, from most to least preferred, the `cICP` code
+//! points of ITU-T H.273, an `iCCP` ICC profile, the `sRGB` chunk, and the
+//! `gAMA` and `cHRM` chunks. [`PngColorChunks`] follows that order, skipping
+//! chunks it can't interpret, and the chunks can also be read alone. An
+//! image with none of them is taken to be sRGB, as the PNG specification
+//! recommends.
+
+use core::fmt;
+
+use crate::{
+    channel::SignalRange,
+    color_space::ColorSpace,
+    icc::{IccError, IccProfile},
+    primaries::Primaries,
+    rgb_color_space::RgbColorSpace,
+    transfer::Transfer,
+    whitepoint::Whitepoint,
+    yuv::{Differencing, YuvColorSpace},
+};
+
+/// The scale of the integers in `gAMA` and `cHRM` chunks.
+const PNG_FIXED_POINT: f32 = 100_000.0;
+
+/// How close the chromaticities of a `cHRM` chunk must be to known
+/// primaries' to be taken as them rather than as custom ones.
+const CHROMATICITY_TOLERANCE: f32 = 1e-3;
+
+/// Why the color chunks of a PNG image could not be turned into a
+/// [`ColorSpace`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PngColorError {
+    /// A chunk's data has the wrong length.
+    InvalidLength { chunk: &'static str, len: usize },
+    /// An H.273 code point that is reserved or describes something this
+    /// crate has no equivalent of, such as a 2.8 gamma.
+    UnsupportedCodePoint { field: &'static str, value: u8 },
+    /// A `gAMA` value of zero, which is no [`Transfer`].
+    UnsupportedGamma(u32),
+    /// The `iCCP` profile could not be read or recognized.
+    Icc(IccError),
+}
+
+impl fmt::Display for PngColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngColorError::InvalidLength { chunk, len } => {
+                write!(f, "the {chunk} chunk has the invalid length {len}")
+            }
+            PngColorError::UnsupportedCodePoint { field, value } => {
+                write!(f, "unsupported {field} code point {value}")
+            }
+            PngColorError::UnsupportedGamma(gamma) => {
+                write!(f, "unsupported gamma {}", *gamma as f32 / PNG_FIXED_POINT)
+            }
+            PngColorError::Icc(error) => write!(f, "invalid ICC profile: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for PngColorError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            PngColorError::Icc(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<IccError> for PngColorError {
+    fn from(error: IccError) -> PngColorError {
+        PngColorError::Icc(error)
+    }
+}
+
+/// The coding-independent code points of a `cICP` chunk, as defined by
+/// ITU-T H.273 and shared with video formats and AVIF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cicp {
+    pub color_primaries: u8,
+    pub transfer_characteristics: u8,
+    /// `0` for RGB, which PNG requires, or the Y'CbCr differencing.
+    pub matrix_coefficients: u8,
+    pub full_range: bool,
+}
+
+impl Cicp {
+    /// Reads the four bytes of a `cICP` chunk.
+    pub fn parse(data: &[u8]) -> Result<Cicp, PngColorError> {
+        let &[color_primaries, transfer_characteristics, matrix_coefficients, full_range] = data
+        else {
+            return Err(PngColorError::InvalidLength {
+                chunk: "cICP",
+                len: data.len(),
+            });
+        };
+        Ok(Cicp {
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+            full_range: full_range != 0,
+        })
+    }
+
+    /// Returns the [`ColorSpace`] the code points describe, an RGB space for
+    /// matrix coefficients `0` and a Y'CbCr space otherwise.
+    ///
+    /// H.273 gives the BT.601 matrix coefficients `5` and `6` the same
+    /// weights, so with BT.601 primaries either is taken as the line
+    /// standard of the primaries.
+    pub fn color_space(&self) -> Result<ColorSpace, PngColorError> {
+        let unsupported = |field, value| PngColorError::UnsupportedCodePoint { field, value };
+        let primaries = Primaries::from_h273(self.color_primaries)
+            .ok_or(unsupported("color primaries", self.color_primaries))?;
+        let whitepoint = match self.color_primaries {
+            11 => Whitepoint::Dci,
+            _ => Whitepoint::D65,
+        };
+        let transfer = Transfer::from_h273(self.transfer_characteristics).ok_or(unsupported(
+            "transfer characteristics",
+            self.transfer_characteristics,
+        ))?;
+        let rgb = RgbColorSpace {
+            primaries,
+            whitepoint,
+            transfer,
+        };
+
+        let differencing = match self.matrix_coefficients {
+            0 => return Ok(ColorSpace::Rgb(rgb)),
+            5 | 6 if matches!(primaries, Primaries::Bt601_525 | Primaries::Bt601_625) => {
+                match primaries {
+                    Primaries::Bt601_625 => Differencing::Bt601_625,
+                    _ => Differencing::Bt601_525,
+                }
+            }
+            matrix => {
+                Differencing::from_h273(matrix).ok_or(unsupported("matrix coefficients", matrix))?
+            }
+        };
+        let yuv = YuvColorSpace::new(rgb, differencing)
+            .expect("BT.601 differencing follows BT.601 primaries");
+        Ok(ColorSpace::Yuv(yuv))
+    }
+
+    /// Returns the range the code values are stored in.
+    pub fn signal_range(&self) -> SignalRange {
+        if self.full_range {
+            SignalRange::Full
+        } else {
+            SignalRange::Narrow
+        }
+    }
+}
+
+/// Reads the big-endian integers of a `gAMA` or `cHRM` chunk.
+fn read_fixed_points<const N: usize>(
+    chunk: &'static str,
+    data: &[u8],
+) -> Result<[u32; N], PngColorError> {
+    if data.len() != 4 * N {
+        return Err(PngColorError::InvalidLength {
+            chunk,
+            len: data.len(),
+        });
+    }
+    let mut values = [0; N];
+    for (value, bytes) in values.iter_mut().zip(data.chunks_exact(4)) {
+        *value = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    Ok(values)
+}
+
+/// Returns the transfer of a `gAMA` chunk, which stores the exponent images
+/// were encoded with times 100000, as the [`Transfer::Gamma`] decoding with
+/// its inverse, or [`Transfer::Linear`] for `1.0`.
+///
+/// This is a pure power law even for `1 / 2.2`, the value written alongside
+/// the `sRGB` chunk, rather than the sRGB curve it approximates, as the
+/// chunk alone describes no more. Where the image is sRGB, its `sRGB` chunk
+/// takes precedence in [`PngColorChunks::color_space`].
+pub fn gama_transfer(data: &[u8]) -> Result<Transfer, PngColorError> {
+    match read_fixed_points("gAMA", data)? {
+        [0] => Err(PngColorError::UnsupportedGamma(0)),
+        [100_000] => Ok(Transfer::Linear),
+        [gamma] => Ok(Transfer::Gamma(PNG_FIXED_POINT / gamma as f32)),
+    }
+}
+
+/// Returns the primaries and white of a `cHRM` chunk, as
+/// [`Primaries::Custom`] if they match none of the others.
+pub fn chrm_primaries(data: &[u8]) -> Result<(Primaries, Whitepoint), PngColorError> {
+    let values = read_fixed_points::<8>("cHRM", data)?.map(|v| v as f32 / PNG_FIXED_POINT);
+    let [white_x, white_y, red_x, red_y, green_x, green_y, blue_x, blue_y] = values;
+    let chromaticities = [[red_x, red_y], [green_x, green_y], [blue_x, blue_y]];
+    let primaries = Primaries::find(chromaticities, CHROMATICITY_TOLERANCE)
+        .unwrap_or(Primaries::Custom(chromaticities));
+    Ok((primaries, Whitepoint::from_xy([white_x, white_y])))
+}
+
+/// The data of the color chunks of a PNG image, each `None` when absent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PngColorChunks<'a> {
+    pub cicp: Option<&'a [u8]>,
+    /// The ICC profile of the `iCCP` chunk, once decompressed as PNG
+    /// decoders do.
+    pub icc_profile: Option<&'a [u8]>,
+    pub srgb: Option<&'a [u8]>,
+    pub gama: Option<&'a [u8]>,
+    pub chrm: Option<&'a [u8]>,
+}
+
+impl PngColorChunks<'_> {
+    /// Returns the [`ColorSpace`] of the image, from the most preferred
+    /// chunk present that can be interpreted.
+    ///
+    /// A `cICP` or `iCCP` chunk that can't, such as a code point with no
+    /// equivalent or a profile whose curves match no [`Transfer`], is
+    /// skipped in favor of the next chunk, as PNG decoders skip what they
+    /// don't understand. Its error is returned only when no later chunk
+    /// describes the image, rather than guessing sRGB.
+    ///
+    /// The rendering intent of the `sRGB` chunk is ignored. A `gAMA` chunk
+    /// without `cHRM` is taken to have sRGB's primaries and white, and a
+    /// `cHRM` chunk without `gAMA` the sRGB curve.
+    pub fn color_space(&self) -> Result<ColorSpace, PngColorError> {
+        let mut skipped = None;
+        if let Some(cicp) = self.cicp {
+            match Cicp::parse(cicp).and_then(|cicp| cicp.color_space()) {
+                Ok(space) => return Ok(space),
+                Err(error) => skipped = Some(error),
+            }
+        }
+        if let Some(profile) = self.icc_profile {
+            match IccProfile::parse(profile).and_then(|profile| profile.to_rgb_color_space()) {
+                Ok(rgb) => return Ok(ColorSpace::Rgb(rgb)),
+                Err(error) => skipped = skipped.or(Some(error.into())),
+            }
+        }
+        if self.srgb.is_some() {
+            return Ok(ColorSpace::Rgb(RgbColorSpace::SRGB));
+        }
+        if let (Some(error), None, None) = (skipped, self.gama, self.chrm) {
+            return Err(error);
+        }
+
+        let mut rgb = RgbColorSpace::SRGB;
+        if let Some(gama) = self.gama {
+            rgb.transfer = gama_transfer(gama)?;
+        }
+        if let Some(chrm) = self.chrm {
+            (rgb.primaries, rgb.whitepoint) = chrm_primaries(chrm)?;
+        }
+        Ok(ColorSpace::Rgb(rgb))
+    }
+}
@@ -0,0 +1,144 @@
+//! The red, green and blue primaries of RGB color spaces.
+
+use crate::{matrix::RowMatrix, whitepoint::Whitepoint};
+
+/// A set of RGB primaries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primaries {
+    /// ITU-R BT.601 for 525 line systems, from SMPTE 170M.
+    #[allow(non_camel_case_types)]
+    Bt601_525,
+    /// ITU-R BT.601 for 625 line systems, from EBU Tech. 3213.
+    #[allow(non_camel_case_types)]
+    Bt601_625,
+    /// ITU-R BT.709, shared by sRGB.
+    Bt709,
+    /// ITU-R BT.2020, shared by BT.2100.
+    Bt2020,
+    /// SMPTE RP 431-2, shared by Display P3.
+    DciP3,
+    /// Adobe RGB (1998).
+    AdobeRgb,
+    /// ACES AP0, used by ACES2065-1.
+    AcesAp0,
+    /// ACES AP1, used by ACEScg, ACEScc and ACEScct.
+    AcesAp1,
+    /// The monochromatic 700, 546.1 and 435.8 nm primaries of the CIE 1931
+    /// color matching experiments.
+    CieRgb,
+    /// Arbitrary `[x, y]` chromaticities of the red, green and blue
+    /// primaries, such as those of ProPhoto RGB or a calibrated display.
+    Custom([[f32; 2]; 3]),
+}
+
+/// Every set of primaries, for [`Primaries::find`].
+const ALL: [Primaries; 9] = [
+    Primaries::Bt709,
+    Primaries::Bt601_525,
+    Primaries::Bt601_625,
+    Primaries::Bt2020,
+    Primaries::DciP3,
+    Primaries::AdobeRgb,
+    Primaries::AcesAp0,
+    Primaries::AcesAp1,
+    Primaries::CieRgb,
+];
+
+impl Primaries {
+    /// Returns the primaries whose chromaticities are each within
+    /// `tolerance` in x and y of `chromaticities`, if any, for recognizing
+    /// primaries stored with limited precision.
+    pub(crate) fn find(chromaticities: [[f32; 2]; 3], tolerance: f32) -> Option<Primaries> {
+        ALL.into_iter().find(|primaries| {
+            primaries
+                .chromaticities()
+                .iter()
+                .flatten()
+                .zip(chromaticities.iter().flatten())
+                .all(|(known, v)| (known - v).abs() <= tolerance)
+        })
+    }
+
+    /// Returns the primaries of the ITU-T H.273 color primaries code point
+    /// `value`, as used by video formats, AVIF and PNG, or `None` for
+    /// reserved values and primaries there is no variant for.
+    ///
+    /// Code points `11` and `12` are both [`Primaries::DciP3`], differing
+    /// only in their white, DCI for `11` and D65 for `12`.
+    pub fn from_h273(value: u8) -> Option<Primaries> {
+        let primaries = match value {
+            1 => Primaries::Bt709,
+            5 => Primaries::Bt601_625,
+            // SMPTE 240M shares the primaries of SMPTE 170M.
+            6 | 7 => Primaries::Bt601_525,
+            9 => Primaries::Bt2020,
+            11 | 12 => Primaries::DciP3,
+            _ => return None,
+        };
+        Some(primaries)
+    }
+
+    /// Returns the ITU-T H.273 color primaries code point of these
+    /// primaries, inverting [`Self::from_h273`], or `None` if they have
+    /// none. [`Primaries::DciP3`] gives `12`, the Display P3 of most
+    /// content.
+    pub fn to_h273(self) -> Option<u8> {
+        let value = match self {
+            Primaries::Bt709 => 1,
+            Primaries::Bt601_625 => 5,
+            Primaries::Bt601_525 => 6,
+            Primaries::Bt2020 => 9,
+            Primaries::DciP3 => 12,
+            Primaries::AdobeRgb
+            | Primaries::AcesAp0
+            | Primaries::AcesAp1
+            | Primaries::CieRgb
+            | Primaries::Custom(_) => return None,
+        };
+        Some(value)
+    }
+
+    /// Returns the `[x, y]` chromaticities of the red, green and blue
+    /// primaries, as used to build [`Self::to_xyz`].
+    pub fn chromaticities(self) -> [[f32; 2]; 3] {
+        match self {
+            Primaries::Bt601_525 => [[0.630, 0.340], [0.310, 0.595], [0.155, 0.070]],
+            Primaries::Bt601_625 => [[0.640, 0.330], [0.290, 0.600], [0.150, 0.060]],
+            Primaries::Bt709 => [[0.64, 0.33], [0.30, 0.60], [0.15, 0.06]],
+            Primaries::Bt2020 => [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046]],
+            Primaries::DciP3 => [[0.680, 0.320], [0.265, 0.690], [0.150, 0.060]],
+            Primaries::AdobeRgb => [[0.64, 0.33], [0.21, 0.71], [0.15, 0.06]],
+            Primaries::AcesAp0 => [[0.734_7, 0.265_3], [0.0, 1.0], [0.000_1, -0.077]],
+            Primaries::AcesAp1 => [[0.713, 0.293], [0.165, 0.830], [0.128, 0.044]],
+            Primaries::CieRgb => [
+                [0.734_74, 0.265_26],
+                [0.273_68, 0.717_43],
+                [0.166_54, 0.008_88],
+            ],
+            Primaries::Custom(chromaticities) => chromaticities,
+        }
+    }
+
+    /// Returns the matrix taking linear RGB to XYZ, scaled so that RGB white
+    /// maps to `whitepoint` with `Y = 1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the primaries are collinear, which only
+    /// [`Primaries::Custom`] can be.
+    pub fn to_xyz(self, whitepoint: Whitepoint) -> RowMatrix {
+        self.try_to_xyz(whitepoint)
+            .unwrap_or_else(|| panic!("{self:?} are collinear and span no gamut"))
+    }
+
+    /// Returns [`Self::to_xyz`], or `None` if the primaries are collinear.
+    pub(crate) fn try_to_xyz(self, whitepoint: Whitepoint) -> Option<RowMatrix> {
+        let primaries = RowMatrix::from_columns(
+            self.chromaticities()
+                .map(|[x, y]| [x / y, 1.0, (1.0 - x - y) / y]),
+        );
+        let scale = primaries.try_inv()?.mul_vec(whitepoint.to_xyz());
+
+        Some(primaries * RowMatrix::diagonal(scale))
+    }
+}
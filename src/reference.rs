@@ -0,0 +1,562 @@
+//! A slow conversion in `f64` for checking the optimized conversions
+//! against.
+//!
+//! Every matrix is derived from chromaticities on each call rather than
+//! precomputed, steps are never fused or cancelled, and every curve is
+//! evaluated in `f64`, apart from Okhsl and Okhsv which are defined by fits
+//! in `f32` and custom transfers which are only defined in `f32`. The
+//! constants defining the curves and spaces are widened from the same `f32`
+//! values the optimized paths use, so the difference between the two
+//! measures arithmetic error alone.
+
+use crate::{
+    adaptation::ChromaticAdaptation,
+    color_space::{ColorSpace, OKLAB_M1, OKLAB_M2},
+    matrix::RowMatrix,
+    okhsl::{okhsl_to_oklab, okhsv_to_oklab, oklab_to_okhsl, oklab_to_okhsv},
+    plan::ConversionPlan,
+    rgb_color_space::RgbColorSpace,
+    transfer::{self, Transfer, SRGB_ENCODED_KNEE, SRGB_LINEAR_KNEE},
+    whitepoint::Whitepoint,
+    yuv::Differencing,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+type Matrix = [[f64; 3]; 3];
+
+fn widen(m: RowMatrix) -> Matrix {
+    m.0.map(|row| row.map(f64::from))
+}
+
+fn widen_pixel(pixel: [f32; 4]) -> [f64; 4] {
+    pixel.map(f64::from)
+}
+
+fn narrow_pixel(pixel: [f64; 4]) -> [f32; 4] {
+    pixel.map(|c| c as f32)
+}
+
+fn mul(a: Matrix, b: Matrix) -> Matrix {
+    core::array::from_fn(|i| core::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn mul_vec(m: Matrix, v: [f64; 3]) -> [f64; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+fn diagonal([a, b, c]: [f64; 3]) -> Matrix {
+    [[a, 0.0, 0.0], [0.0, b, 0.0], [0.0, 0.0, c]]
+}
+
+fn inv(m: Matrix) -> Matrix {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let det = m[0][0] * cofactor(1, 2, 1, 2) - m[0][1] * cofactor(1, 2, 0, 2)
+        + m[0][2] * cofactor(1, 2, 0, 1);
+
+    [
+        [
+            cofactor(1, 2, 1, 2) / det,
+            -cofactor(0, 2, 1, 2) / det,
+            cofactor(0, 1, 1, 2) / det,
+        ],
+        [
+            -cofactor(1, 2, 0, 2) / det,
+            cofactor(0, 2, 0, 2) / det,
+            -cofactor(0, 1, 0, 2) / det,
+        ],
+        [
+            cofactor(1, 2, 0, 1) / det,
+            -cofactor(0, 2, 0, 1) / det,
+            cofactor(0, 1, 0, 1) / det,
+        ],
+    ]
+}
+
+/// Applies `m` to the color channels of `pixel`, leaving alpha untouched.
+fn apply_matrix(m: Matrix, [c0, c1, c2, alpha]: [f64; 4]) -> [f64; 4] {
+    let [c0, c1, c2] = mul_vec(m, [c0, c1, c2]);
+    [c0, c1, c2, alpha]
+}
+
+fn white_xyz(whitepoint: Whitepoint) -> [f64; 3] {
+    let [x, y] = whitepoint.chromaticity().map(f64::from);
+    [x / y, 1.0, (1.0 - x - y) / y]
+}
+
+fn rgb_to_xyz(space: &RgbColorSpace) -> Matrix {
+    let columns = space
+        .primaries
+        .chromaticities()
+        .map(|xy| xy.map(f64::from))
+        .map(|[x, y]| [x / y, 1.0, (1.0 - x - y) / y]);
+    let primaries: Matrix = core::array::from_fn(|i| core::array::from_fn(|j| columns[j][i]));
+    let scale = mul_vec(inv(primaries), white_xyz(space.whitepoint));
+
+    mul(primaries, diagonal(scale))
+}
+
+fn bradford(from: Whitepoint, to: Whitepoint) -> Matrix {
+    let cone = widen(ChromaticAdaptation::Bradford.cone_response());
+    let from = mul_vec(cone, white_xyz(from));
+    let to = mul_vec(cone, white_xyz(to));
+
+    mul(
+        inv(cone),
+        mul(diagonal(core::array::from_fn(|i| to[i] / from[i])), cone),
+    )
+}
+
+fn rgb_to_yuv(differencing: Differencing) -> Matrix {
+    let [kr, kg, kb] = differencing.luma_coefficients().map(f64::from);
+    let [cb, cr] = match differencing {
+        // The analog factors are published constants rather than derived.
+        Differencing::Bt470MPal | Differencing::Bt470MPalPrecise => {
+            differencing.chroma_scales().map(f64::from)
+        }
+        _ => [0.5 / (1.0 - kb), 0.5 / (1.0 - kr)],
+    };
+
+    [
+        [kr, kg, kb],
+        [-kr * cb, -kg * cb, (1.0 - kb) * cb],
+        [(1.0 - kr) * cr, -kg * cr, -kb * cr],
+    ]
+}
+
+fn srgb_eotf(v: f64) -> f64 {
+    if v <= f64::from(SRGB_ENCODED_KNEE) {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn srgb_oetf(v: f64) -> f64 {
+    if v <= f64::from(SRGB_LINEAR_KNEE) {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn acescc_log(v: f64) -> f64 {
+    (v.log2() + 9.72) / 17.52
+}
+
+fn acescc_exp(v: f64) -> f64 {
+    (v * 17.52 - 9.72).exp2()
+}
+
+fn eotf(transfer: Transfer, v: f64) -> f64 {
+    let half_max = f64::from(transfer::ACES_HALF_MAX);
+    match transfer {
+        Transfer::Linear => v,
+        Transfer::Srgb => srgb_eotf(v),
+        Transfer::SrgbExtended => srgb_eotf(v.abs()).copysign(v),
+        Transfer::Dci => v.max(0.0).powf(f64::from(transfer::DCI_GAMMA)),
+        Transfer::Gamma(gamma) => v.max(0.0).powf(f64::from(gamma)),
+        Transfer::Bt709 => {
+            let (alpha, beta) = (
+                f64::from(transfer::BT709_ALPHA),
+                f64::from(transfer::BT709_BETA),
+            );
+            if v < 4.5 * beta {
+                v / 4.5
+            } else {
+                ((v + (alpha - 1.0)) / alpha).powf(1.0 / 0.45)
+            }
+        }
+        Transfer::AcesCc => {
+            if v <= (9.72 - 15.0) / 17.52 {
+                (acescc_exp(v) - 2.0_f64.powi(-16)) * 2.0
+            } else if v < acescc_log(half_max) {
+                acescc_exp(v)
+            } else {
+                half_max
+            }
+        }
+        Transfer::AcesCct => {
+            if v <= f64::from(transfer::ACESCCT_Y_BRK) {
+                (v - f64::from(transfer::ACESCCT_B)) / f64::from(transfer::ACESCCT_A)
+            } else if v < acescc_log(half_max) {
+                acescc_exp(v)
+            } else {
+                half_max
+            }
+        }
+        Transfer::LogC3(ei) => {
+            let p = ei.params();
+            let [cut, a, b, c, d, e, f] = [p.cut, p.a, p.b, p.c, p.d, p.e, p.f].map(f64::from);
+            if v > e * cut + f {
+                (10.0_f64.powf((v - d) / c) - b) / a
+            } else {
+                (v - f) / e
+            }
+        }
+        Transfer::SLog3 => {
+            let cut_code = f64::from(transfer::SLOG3_CUT_CODE);
+            if v >= cut_code / 1023.0 {
+                10.0_f64.powf((v * 1023.0 - 420.0) / 261.5) * (0.18 + 0.01) - 0.01
+            } else {
+                (v * 1023.0 - 95.0) * 0.011_25 / (cut_code - 95.0)
+            }
+        }
+        Transfer::Pq => {
+            let p = v.max(0.0).powf(1.0 / transfer::PQ_M2);
+            ((p - transfer::PQ_C1).max(0.0) / (transfer::PQ_C2 - transfer::PQ_C3 * p))
+                .powf(1.0 / transfer::PQ_M1)
+        }
+        Transfer::Hlg => {
+            let [a, b, c] = [transfer::HLG_A, transfer::HLG_B, transfer::HLG_C].map(f64::from);
+            if v <= 0.5 {
+                v.max(0.0) * v.max(0.0) / 3.0
+            } else {
+                (((v - c) / a).exp() + b) / 12.0
+            }
+        }
+        Transfer::Custom(curve) => f64::from(curve.0.eotf(v as f32)),
+    }
+}
+
+fn oetf(transfer: Transfer, v: f64) -> f64 {
+    match transfer {
+        Transfer::Linear => v,
+        Transfer::Srgb => srgb_oetf(v),
+        Transfer::SrgbExtended => srgb_oetf(v.abs()).copysign(v),
+        Transfer::Dci => v.max(0.0).powf(1.0 / f64::from(transfer::DCI_GAMMA)),
+        Transfer::Gamma(gamma) => v.max(0.0).powf(1.0 / f64::from(gamma)),
+        Transfer::Bt709 => {
+            let (alpha, beta) = (
+                f64::from(transfer::BT709_ALPHA),
+                f64::from(transfer::BT709_BETA),
+            );
+            if v < beta {
+                4.5 * v
+            } else {
+                alpha * v.powf(0.45) - (alpha - 1.0)
+            }
+        }
+        Transfer::AcesCc => {
+            if v <= 0.0 {
+                acescc_log(2.0_f64.powi(-16))
+            } else if v < 2.0_f64.powi(-15) {
+                acescc_log(2.0_f64.powi(-16) + v * 0.5)
+            } else {
+                acescc_log(v)
+            }
+        }
+        Transfer::AcesCct => {
+            if v <= f64::from(transfer::ACESCCT_X_BRK) {
+                f64::from(transfer::ACESCCT_A) * v + f64::from(transfer::ACESCCT_B)
+            } else {
+                acescc_log(v)
+            }
+        }
+        Transfer::LogC3(ei) => {
+            let p = ei.params();
+            let [cut, a, b, c, d, e, f] = [p.cut, p.a, p.b, p.c, p.d, p.e, p.f].map(f64::from);
+            if v > cut {
+                c * (a * v + b).log10() + d
+            } else {
+                e * v + f
+            }
+        }
+        Transfer::SLog3 => {
+            let cut_code = f64::from(transfer::SLOG3_CUT_CODE);
+            if v >= 0.011_25 {
+                (420.0 + ((v + 0.01) / (0.18 + 0.01)).log10() * 261.5) / 1023.0
+            } else {
+                (v * (cut_code - 95.0) / 0.011_25 + 95.0) / 1023.0
+            }
+        }
+        Transfer::Pq => {
+            let y = v.max(0.0).powf(transfer::PQ_M1);
+            ((transfer::PQ_C1 + transfer::PQ_C2 * y) / (1.0 + transfer::PQ_C3 * y))
+                .powf(transfer::PQ_M2)
+        }
+        Transfer::Hlg => {
+            let [a, b, c] = [transfer::HLG_A, transfer::HLG_B, transfer::HLG_C].map(f64::from);
+            if v <= 1.0 / 12.0 {
+                (3.0 * v.max(0.0)).sqrt()
+            } else {
+                a * (12.0 * v - b).ln() + c
+            }
+        }
+        Transfer::Custom(curve) => f64::from(curve.0.oetf(v as f32)),
+    }
+}
+
+/// Returns the BT.2100 luminance of linear BT.2020 `rgb`, and the HLG
+/// system gamma at the nominal peak, which the HLG OOTF scales by.
+fn hlg_luminance_and_gamma(rgb: [f64; 3]) -> (f64, f64) {
+    let weights = transfer::BT2100_LUMINANCE.map(f64::from);
+    let y = weights.iter().zip(rgb).map(|(w, c)| w * c).sum();
+    let peak = f64::from(transfer::HLG_NOMINAL_PEAK_LUMINANCE);
+    (y, 1.2 + 0.42 * (peak / 1000.0).log10())
+}
+
+fn decode(transfer: Transfer, [r, g, b, alpha]: [f64; 4]) -> [f64; 4] {
+    let [r, g, b] = [r, g, b].map(|v| eotf(transfer, v));
+    if transfer != Transfer::Hlg {
+        return [r, g, b, alpha];
+    }
+
+    // The OOTF to display light, relative to the display's peak.
+    let (y, gamma) = hlg_luminance_and_gamma([r, g, b]);
+    if y <= 0.0 {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+    let gain = y.powf(gamma - 1.0);
+    [r * gain, g * gain, b * gain, alpha]
+}
+
+fn encode(transfer: Transfer, [r, g, b, alpha]: [f64; 4]) -> [f64; 4] {
+    let [r, g, b] = if transfer == Transfer::Hlg {
+        let (y, gamma) = hlg_luminance_and_gamma([r, g, b]);
+        if y <= 0.0 {
+            [0.0; 3]
+        } else {
+            let gain = y.powf(1.0 / gamma).powf(gamma - 1.0);
+            [r / gain, g / gain, b / gain]
+        }
+    } else {
+        [r, g, b]
+    };
+    let [r, g, b] = [r, g, b].map(|v| oetf(transfer, v));
+    [r, g, b, alpha]
+}
+
+fn rgb_to_hwb([r, g, b, alpha]: [f64; 4]) -> [f64; 4] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let sector = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    [sector * 60.0, min, 1.0 - max, alpha]
+}
+
+fn hwb_to_rgb([h, w, b, alpha]: [f64; 4]) -> [f64; 4] {
+    let sum = w + b;
+    if sum >= 1.0 {
+        let gray = w / sum;
+        return [gray, gray, gray, alpha];
+    }
+    let channel = |n: f64| {
+        let k = (n + h / 60.0).rem_euclid(6.0);
+        1.0 - k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    let [r, g, b] = [5.0, 3.0, 1.0].map(|n| channel(n) * (1.0 - w - b) + w);
+    [r, g, b, alpha]
+}
+
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+
+fn to_xyz(space: &ColorSpace, pixel: [f64; 4]) -> [f64; 4] {
+    match space {
+        ColorSpace::Rgb(space) => apply_matrix(rgb_to_xyz(space), decode(space.transfer, pixel)),
+        ColorSpace::Yuv(space) => {
+            let yuv_to_rgb = inv(rgb_to_yuv(space.differencing()));
+            to_xyz(
+                &ColorSpace::Rgb(*space.rgb()),
+                apply_matrix(yuv_to_rgb, pixel),
+            )
+        }
+        ColorSpace::Hwb(space) => to_xyz(&ColorSpace::Rgb(*space), hwb_to_rgb(pixel)),
+        ColorSpace::Xyz(_) => pixel,
+        ColorSpace::Xyy(_) => {
+            let [x, y, big_y, alpha] = pixel;
+            if y == 0.0 {
+                return [0.0, 0.0, 0.0, alpha];
+            }
+            let scale = big_y / y;
+            [x * scale, big_y, (1.0 - x - y) * scale, alpha]
+        }
+        ColorSpace::CieLab(whitepoint) => {
+            let [l, a, b, alpha] = pixel;
+            let white = white_xyz(*whitepoint);
+            let fy = (l + 16.0) / 116.0;
+            let f_inv = |t: f64| {
+                if t * t * t > LAB_EPSILON {
+                    t * t * t
+                } else {
+                    (116.0 * t - 16.0) / LAB_KAPPA
+                }
+            };
+            let y = if l > LAB_KAPPA * LAB_EPSILON {
+                fy * fy * fy
+            } else {
+                l / LAB_KAPPA
+            };
+            [
+                f_inv(fy + a / 500.0) * white[0],
+                y * white[1],
+                f_inv(fy - b / 200.0) * white[2],
+                alpha,
+            ]
+        }
+        ColorSpace::Oklab => {
+            let [l, m, s, alpha] = apply_matrix(inv(widen(OKLAB_M2)), pixel);
+            apply_matrix(
+                inv(widen(OKLAB_M1)),
+                [l * l * l, m * m * m, s * s * s, alpha],
+            )
+        }
+        // Okhsl and Okhsv are defined by polynomial fits in `f32`, so there is
+        // nothing more exact to compare them with.
+        ColorSpace::Okhsl => to_xyz(
+            &ColorSpace::Oklab,
+            widen_pixel(okhsl_to_oklab(narrow_pixel(pixel))),
+        ),
+        ColorSpace::Okhsv => to_xyz(
+            &ColorSpace::Oklab,
+            widen_pixel(okhsv_to_oklab(narrow_pixel(pixel))),
+        ),
+        ColorSpace::Oklch => {
+            let [l, c, h, alpha] = pixel;
+            let (sin, cos) = h.to_radians().sin_cos();
+            to_xyz(&ColorSpace::Oklab, [l, c * cos, c * sin, alpha])
+        }
+    }
+}
+
+fn from_xyz(space: &ColorSpace, xyz: [f64; 4]) -> [f64; 4] {
+    match space {
+        ColorSpace::Rgb(space) => encode(space.transfer, apply_matrix(inv(rgb_to_xyz(space)), xyz)),
+        ColorSpace::Yuv(space) => {
+            let rgb = from_xyz(&ColorSpace::Rgb(*space.rgb()), xyz);
+            apply_matrix(rgb_to_yuv(space.differencing()), rgb)
+        }
+        ColorSpace::Hwb(space) => rgb_to_hwb(from_xyz(&ColorSpace::Rgb(*space), xyz)),
+        ColorSpace::Xyz(_) => xyz,
+        ColorSpace::Xyy(whitepoint) => {
+            let [x, y, z, alpha] = xyz;
+            let sum = x + y + z;
+            if sum == 0.0 {
+                let [white_x, white_y] = whitepoint.chromaticity().map(f64::from);
+                return [white_x, white_y, y, alpha];
+            }
+            [x / sum, y / sum, y, alpha]
+        }
+        ColorSpace::CieLab(whitepoint) => {
+            let [x, y, z, alpha] = xyz;
+            let white = white_xyz(*whitepoint);
+            let f = |t: f64| {
+                if t > LAB_EPSILON {
+                    t.cbrt()
+                } else {
+                    (LAB_KAPPA * t + 16.0) / 116.0
+                }
+            };
+            let [fx, fy, fz] = [x / white[0], y / white[1], z / white[2]].map(f);
+            [
+                116.0 * fy - 16.0,
+                500.0 * (fx - fy),
+                200.0 * (fy - fz),
+                alpha,
+            ]
+        }
+        ColorSpace::Oklab => {
+            let [l, m, s, alpha] = apply_matrix(widen(OKLAB_M1), xyz);
+            apply_matrix(widen(OKLAB_M2), [l.cbrt(), m.cbrt(), s.cbrt(), alpha])
+        }
+        ColorSpace::Okhsl => widen_pixel(oklab_to_okhsl(narrow_pixel(from_xyz(
+            &ColorSpace::Oklab,
+            xyz,
+        )))),
+        ColorSpace::Okhsv => widen_pixel(oklab_to_okhsv(narrow_pixel(from_xyz(
+            &ColorSpace::Oklab,
+            xyz,
+        )))),
+        ColorSpace::Oklch => {
+            let [l, a, b, alpha] = from_xyz(&ColorSpace::Oklab, xyz);
+            [
+                l,
+                a.hypot(b),
+                b.atan2(a).to_degrees().rem_euclid(360.0),
+                alpha,
+            ]
+        }
+    }
+}
+
+/// Converts `pixel` from `from` to `to` like
+/// [`convert`](crate::color_space::convert), but in `f64` with nothing
+/// precomputed. This is far slower and only meant as a test oracle.
+pub fn convert_reference(pixel: [f64; 4], from: &ColorSpace, to: &ColorSpace) -> [f64; 4] {
+    let mut xyz = to_xyz(from, pixel);
+    let (from_white, to_white) = (from.whitepoint(), to.whitepoint());
+    if from_white != to_white {
+        xyz = apply_matrix(bradford(from_white, to_white), xyz);
+    }
+    from_xyz(to, xyz)
+}
+
+/// The error of the `f32` conversion between two spaces against
+/// [`convert_reference`], in the units of the destination's channels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PrecisionReport {
+    /// The largest error of any channel.
+    pub max: f64,
+    /// The mean error over every color channel.
+    pub mean: f64,
+    /// The root mean square error over every color channel.
+    pub rms: f64,
+}
+
+/// The steps of Martin Roberts' R3 sequence, whose multiples spread points
+/// evenly through the unit cube for any number of them.
+const R3_STEPS: [f64; 3] = [
+    0.819_172_513_396_164_4,
+    0.671_043_606_703_789_2,
+    0.549_700_477_901_970_2,
+];
+
+/// Measures the error of converting from `from` to `to` with a
+/// [`ConversionPlan`] against [`convert_reference`].
+///
+/// The inputs are `samples` colors spread evenly through the sRGB gamut,
+/// expressed in `from` and rounded to `f32`, so that both conversions start
+/// from the same values. Alpha is never altered and isn't counted. Hues
+/// are ill-conditioned for near grays, so spaces with a hue channel can
+/// report large errors there that don't matter in practice. No samples give
+/// an error of zero.
+pub fn precision_report(from: &ColorSpace, to: &ColorSpace, samples: usize) -> PrecisionReport {
+    if samples == 0 {
+        return PrecisionReport::default();
+    }
+
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let plan = ConversionPlan::new(from, to);
+    let (mut max, mut sum, mut sum_squares) = (0.0_f64, 0.0, 0.0);
+    for i in 0..samples {
+        let [r, g, b] = R3_STEPS.map(|step| (0.5 + step * i as f64).rem_euclid(1.0));
+        let pixel = narrow_pixel(convert_reference([r, g, b, 1.0], &srgb, from));
+
+        let actual = plan.apply(pixel);
+        let expected = convert_reference(widen_pixel(pixel), from, to);
+        for (actual, expected) in actual[..3].iter().zip(&expected[..3]) {
+            let error = (f64::from(*actual) - expected).abs();
+            max = max.max(error);
+            sum += error;
+            sum_squares += error * error;
+        }
+    }
+
+    let count = (3 * samples) as f64;
+    PrecisionReport {
+        max,
+        mean: sum / count,
+        rms: (sum_squares / count).sqrt(),
+    }
+}
@@ -0,0 +1,215 @@
+//! RGB color spaces, made of primaries, a whitepoint and a transfer.
+
+use core::fmt;
+
+use crate::{
+    adaptation::ChromaticAdaptation, matrix::RowMatrix, primaries::Primaries, transfer::Transfer,
+    whitepoint::Whitepoint,
+};
+
+/// The `[x, y]` chromaticity of the ACES white.
+const ACES_WHITE: [f32; 2] = [0.321_68, 0.337_67];
+
+/// An RGB color space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RgbColorSpace {
+    pub primaries: Primaries,
+    pub whitepoint: Whitepoint,
+    pub transfer: Transfer,
+}
+
+impl RgbColorSpace {
+    pub const SRGB: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::Bt709,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Srgb,
+    };
+    pub const LINEAR_SRGB: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::Bt709,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Linear,
+    };
+    pub const BT601_525: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::Bt601_525,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Bt709,
+    };
+    pub const BT601_625: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::Bt601_625,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Bt709,
+    };
+    pub const BT709: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::Bt709,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Bt709,
+    };
+    pub const BT2020: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::Bt2020,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Bt709,
+    };
+    /// Theatrical DCI-P3 with the DCI white and 2.6 gamma of SMPTE RP 431-2.
+    pub const DCI_P3: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::DciP3,
+        whitepoint: Whitepoint::Dci,
+        transfer: Transfer::Dci,
+    };
+    pub const DISPLAY_P3: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::DciP3,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Srgb,
+    };
+    /// The linear CIE 1931 RGB space, whose equal-energy white makes it a
+    /// neutral reference for theoretical work.
+    pub const CIE_RGB: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::CieRgb,
+        whitepoint: Whitepoint::E,
+        transfer: Transfer::Linear,
+    };
+    /// The linear ACEScg working space of Academy S-2014-004, with the ACES
+    /// white near D60.
+    pub const ACES_CG: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::AcesAp1,
+        whitepoint: Whitepoint::Custom(ACES_WHITE),
+        transfer: Transfer::Linear,
+    };
+    /// The linear ACES2065-1 interchange space of SMPTE ST 2065-1, whose
+    /// primaries enclose every visible color.
+    pub const ACES2065_1: RgbColorSpace = RgbColorSpace {
+        primaries: Primaries::AcesAp0,
+        whitepoint: Whitepoint::Custom(ACES_WHITE),
+        transfer: Transfer::Linear,
+    };
+
+    /// The matrix taking linear BT.709 and sRGB to XYZ relative to D65.
+    pub const BT709_TO_XYZ: RowMatrix = RowMatrix([
+        [0.412_390_8, 0.357_584_34, 0.180_480_8],
+        [0.212_639, 0.715_168_68, 0.072_192_32],
+        [0.019_330_819, 0.119_194_78, 0.950_532_15],
+    ]);
+
+    /// The inverse of [`Self::BT709_TO_XYZ`].
+    pub const XYZ_TO_BT709: RowMatrix = RowMatrix([
+        [3.240_97, -1.537_383_2, -0.498_610_76],
+        [-0.969_243_64, 1.875_967_5, 0.041_555_06],
+        [0.055_630_08, -0.203_976_96, 1.056_971_5],
+    ]);
+
+    /// The matrix taking linear BT.2020 to XYZ relative to D65.
+    pub const BT2020_TO_XYZ: RowMatrix = RowMatrix([
+        [0.636_958_05, 0.144_616_9, 0.168_880_97],
+        [0.262_700_2, 0.677_998_07, 0.059_301_716],
+        [0.0, 0.028_072_693, 1.060_985_1],
+    ]);
+
+    /// The inverse of [`Self::BT2020_TO_XYZ`].
+    pub const XYZ_TO_BT2020: RowMatrix = RowMatrix([
+        [1.716_651_2, -0.355_670_78, -0.253_366_3],
+        [-0.666_684_3, 1.616_481_2, 0.015_768_546],
+        [0.017_639_857, -0.042_770_613, 0.942_103_12],
+    ]);
+
+    /// The matrix taking linear Display P3 to XYZ relative to D65.
+    pub const DISPLAY_P3_TO_XYZ: RowMatrix = RowMatrix([
+        [0.486_570_95, 0.265_667_7, 0.198_217_29],
+        [0.228_974_56, 0.691_738_52, 0.079_286_91],
+        [0.0, 0.045_113_38, 1.043_944_4],
+    ]);
+
+    /// The inverse of [`Self::DISPLAY_P3_TO_XYZ`].
+    pub const XYZ_TO_DISPLAY_P3: RowMatrix = RowMatrix([
+        [2.493_497, -0.931_383_6, -0.402_710_8],
+        [-0.829_489, 1.762_664_1, 0.023_624_686],
+        [0.035_845_83, -0.076_172_39, 0.956_884_5],
+    ]);
+
+    /// Returns the precomputed matrices to and from XYZ for the primaries of
+    /// the built-in spaces with a D65 white.
+    fn precomputed_matrices(&self) -> Option<(RowMatrix, RowMatrix)> {
+        if self.whitepoint != Whitepoint::D65 {
+            return None;
+        }
+
+        match self.primaries {
+            Primaries::Bt709 => Some((Self::BT709_TO_XYZ, Self::XYZ_TO_BT709)),
+            Primaries::Bt2020 => Some((Self::BT2020_TO_XYZ, Self::XYZ_TO_BT2020)),
+            Primaries::DciP3 => Some((Self::DISPLAY_P3_TO_XYZ, Self::XYZ_TO_DISPLAY_P3)),
+            _ => None,
+        }
+    }
+
+    /// Returns the matrix taking this space's linear RGB to XYZ relative to
+    /// its whitepoint.
+    pub fn to_xyz_matrix(&self) -> RowMatrix {
+        match self.precomputed_matrices() {
+            Some((to_xyz, _)) => to_xyz,
+            None => self.primaries.to_xyz(self.whitepoint),
+        }
+    }
+
+    /// Returns the matrix taking XYZ relative to this space's whitepoint to
+    /// its linear RGB.
+    pub fn from_xyz_matrix(&self) -> RowMatrix {
+        match self.precomputed_matrices() {
+            Some((_, from_xyz)) => from_xyz,
+            None => self.to_xyz_matrix().inv(),
+        }
+    }
+
+    /// Decodes `pixel` and converts it to XYZ relative to this space's
+    /// whitepoint, leaving alpha untouched.
+    #[must_use]
+    pub fn to_xyz(&self, pixel: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, a] = self.transfer.to_optical_display(pixel);
+        let [x, y, z] = self.to_xyz_matrix().mul_vec([r, g, b]);
+        [x, y, z, a]
+    }
+
+    /// Converts XYZ relative to this space's whitepoint into this space and
+    /// encodes it, leaving alpha untouched.
+    #[must_use]
+    pub fn from_xyz(&self, xyz: [f32; 4]) -> [f32; 4] {
+        let [x, y, z, a] = xyz;
+        let [r, g, b] = self.from_xyz_matrix().mul_vec([x, y, z]);
+        self.transfer.from_optical_display([r, g, b, a])
+    }
+}
+
+/// Shows the primaries, whitepoint and transfer along with the resolved
+/// whitepoint XYZ and RGB to XYZ matrix, for pasting into bug reports.
+impl fmt::Display for RgbColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [x, y, z] = self.whitepoint.to_xyz();
+        writeln!(f, "primaries: {:?}", self.primaries)?;
+        writeln!(
+            f,
+            "whitepoint: {:?}, XYZ [{x:.6}, {y:.6}, {z:.6}]",
+            self.whitepoint
+        )?;
+        writeln!(f, "transfer: {:?}", self.transfer)?;
+        write!(f, "RGB to XYZ:")?;
+        for [a, b, c] in self.to_xyz_matrix().0 {
+            write!(f, "\n  [{a:.6}, {b:.6}, {c:.6}]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts linear light RGB from `from` to `to`, adapting between their
+/// whitepoints with the Bradford transform if they differ.
+///
+/// The transfers of both spaces are ignored, which for data that is already
+/// linear, such as from OpenEXR, saves decoding and encoding and the
+/// rounding they introduce.
+pub fn convert_linear_rgb(pixel: [f32; 3], from: &RgbColorSpace, to: &RgbColorSpace) -> [f32; 3] {
+    if from.primaries == to.primaries && from.whitepoint == to.whitepoint {
+        return pixel;
+    }
+
+    let mut matrix = from.to_xyz_matrix();
+    if from.whitepoint != to.whitepoint {
+        matrix = ChromaticAdaptation::Bradford.matrix(from.whitepoint, to.whitepoint) * matrix;
+    }
+    (to.from_xyz_matrix() * matrix).mul_vec(pixel)
+}
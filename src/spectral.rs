@@ -0,0 +1,257 @@
+//! Spectral colorimetry against the CIE standard observers.
+
+use crate::whitepoint::Whitepoint;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// A CIE standard colorimetric observer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Observer {
+    /// The CIE 1931 2° standard observer.
+    #[default]
+    Cie1931,
+}
+
+/// The wavelength in nanometers of the first color matching function sample.
+const CMF_START: f32 = 380.0;
+/// The spacing in nanometers between color matching function samples.
+const CMF_STEP: f32 = 5.0;
+
+/// The CIE 1931 2° color matching functions from 380nm to 780nm in 5nm steps.
+#[rustfmt::skip]
+#[allow(clippy::excessive_precision)]
+const CIE_1931_CMF: [[f32; 3]; 81] = [
+    [0.001368, 0.000039, 0.006450],
+    [0.002236, 0.000064, 0.010550],
+    [0.004243, 0.000120, 0.020050],
+    [0.007650, 0.000217, 0.036210],
+    [0.014310, 0.000396, 0.067850],
+    [0.023190, 0.000640, 0.110200],
+    [0.043510, 0.001210, 0.207400],
+    [0.077630, 0.002180, 0.371300],
+    [0.134380, 0.004000, 0.645600],
+    [0.214770, 0.007300, 1.039050],
+    [0.283900, 0.011600, 1.385600],
+    [0.328500, 0.016840, 1.622960],
+    [0.348280, 0.023000, 1.747060],
+    [0.348060, 0.029800, 1.782600],
+    [0.336200, 0.038000, 1.772110],
+    [0.318700, 0.048000, 1.744100],
+    [0.290800, 0.060000, 1.669200],
+    [0.251100, 0.073900, 1.528100],
+    [0.195360, 0.090980, 1.287640],
+    [0.142100, 0.112600, 1.041900],
+    [0.095640, 0.139020, 0.812950],
+    [0.057950, 0.169300, 0.616200],
+    [0.032010, 0.208020, 0.465180],
+    [0.014700, 0.258600, 0.353300],
+    [0.004900, 0.323000, 0.272000],
+    [0.002400, 0.407300, 0.212300],
+    [0.009300, 0.503000, 0.158200],
+    [0.029100, 0.608200, 0.111700],
+    [0.063270, 0.710000, 0.078250],
+    [0.109600, 0.793200, 0.057250],
+    [0.165500, 0.862000, 0.042160],
+    [0.225750, 0.914850, 0.029840],
+    [0.290400, 0.954000, 0.020300],
+    [0.359700, 0.980300, 0.013400],
+    [0.433450, 0.994950, 0.008750],
+    [0.512050, 1.000000, 0.005750],
+    [0.594500, 0.995000, 0.003900],
+    [0.678400, 0.978600, 0.002750],
+    [0.762100, 0.952000, 0.002100],
+    [0.842500, 0.915400, 0.001800],
+    [0.916300, 0.870000, 0.001650],
+    [0.978600, 0.816300, 0.001400],
+    [1.026300, 0.757000, 0.001100],
+    [1.056700, 0.694900, 0.001000],
+    [1.062200, 0.631000, 0.000800],
+    [1.045600, 0.566800, 0.000600],
+    [1.002600, 0.503000, 0.000340],
+    [0.938400, 0.441200, 0.000240],
+    [0.854450, 0.381000, 0.000190],
+    [0.751400, 0.321000, 0.000100],
+    [0.642400, 0.265000, 0.000050],
+    [0.541900, 0.217000, 0.000030],
+    [0.447900, 0.175000, 0.000020],
+    [0.360800, 0.138200, 0.000010],
+    [0.283500, 0.107000, 0.000000],
+    [0.218700, 0.081600, 0.000000],
+    [0.164900, 0.061000, 0.000000],
+    [0.121200, 0.044580, 0.000000],
+    [0.087400, 0.032000, 0.000000],
+    [0.063600, 0.023200, 0.000000],
+    [0.046770, 0.017000, 0.000000],
+    [0.032900, 0.011920, 0.000000],
+    [0.022700, 0.008210, 0.000000],
+    [0.015840, 0.005723, 0.000000],
+    [0.011359, 0.004102, 0.000000],
+    [0.008111, 0.002929, 0.000000],
+    [0.005790, 0.002091, 0.000000],
+    [0.004109, 0.001484, 0.000000],
+    [0.002899, 0.001047, 0.000000],
+    [0.002049, 0.000740, 0.000000],
+    [0.001440, 0.000520, 0.000000],
+    [0.001000, 0.000361, 0.000000],
+    [0.000690, 0.000249, 0.000000],
+    [0.000476, 0.000172, 0.000000],
+    [0.000332, 0.000120, 0.000000],
+    [0.000235, 0.000085, 0.000000],
+    [0.000166, 0.000060, 0.000000],
+    [0.000117, 0.000042, 0.000000],
+    [0.000083, 0.000030, 0.000000],
+    [0.000059, 0.000021, 0.000000],
+    [0.000042, 0.000015, 0.000000],
+];
+
+impl Observer {
+    fn table(self) -> &'static [[f32; 3]] {
+        match self {
+            Observer::Cie1931 => &CIE_1931_CMF,
+        }
+    }
+
+    /// Returns the `[x̄, ȳ, z̄]` color matching functions at `wavelength` in
+    /// nanometers, linearly interpolated and zero outside the tabulated range.
+    pub fn color_matching(self, wavelength: f32) -> [f32; 3] {
+        let table = self.table();
+        let position = (wavelength - CMF_START) / CMF_STEP;
+        if !(0.0..=(table.len() - 1) as f32).contains(&position) {
+            return [0.0; 3];
+        }
+
+        let i = (position as usize).min(table.len() - 2);
+        let t = position - i as f32;
+        core::array::from_fn(|c| table[i][c] + (table[i + 1][c] - table[i][c]) * t)
+    }
+}
+
+/// Returns the power of `samples` at `wavelength`, linearly interpolated and
+/// zero outside the sampled range.
+fn sample_power(samples: &[(f32, f32)], wavelength: f32) -> f32 {
+    let upper = samples.partition_point(|&(nm, _)| nm < wavelength);
+    match (upper.checked_sub(1).map(|i| samples[i]), samples.get(upper)) {
+        (_, Some(&(nm, power))) if nm == wavelength => power,
+        (Some((nm0, p0)), Some(&(nm1, p1))) => p0 + (p1 - p0) * (wavelength - nm0) / (nm1 - nm0),
+        _ => 0.0,
+    }
+}
+
+/// Integrates a spectral power distribution against the color matching
+/// functions of `observer`.
+///
+/// `samples` are `(wavelength in nanometers, power)` pairs sorted by
+/// wavelength on any grid, they are resampled onto the observer's 5nm grid.
+/// The result is normalized so that a flat spectrum has `Y = 1`, making it
+/// relative XYZ as used by the rest of the crate.
+pub fn spectrum_to_xyz(samples: &[(f32, f32)], observer: Observer) -> [f32; 3] {
+    let mut xyz = [0.0; 3];
+    let mut y_norm = 0.0;
+
+    for (i, cmf) in observer.table().iter().enumerate() {
+        let power = sample_power(samples, CMF_START + i as f32 * CMF_STEP);
+        for c in 0..3 {
+            xyz[c] += cmf[c] * power;
+        }
+        y_norm += cmf[1];
+    }
+
+    xyz.map(|c| c / y_norm)
+}
+
+/// The wavelength of a color on the spectral locus relative to a white.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DominantWavelength {
+    /// The wavelength in nanometers on the spectral locus beyond the color.
+    Dominant(f32),
+    /// For purples, which lie toward the line of purples and so have no
+    /// dominant wavelength, the wavelength in nanometers on the spectral locus
+    /// opposite the color.
+    Complementary(f32),
+}
+
+/// The chromaticity distance below which a color is considered achromatic.
+const ACHROMATIC_EPSILON: f32 = 1e-6;
+
+/// The last wavelength in nanometers used for the spectral locus, past which
+/// the tabulated chromaticities are dominated by rounding noise.
+const LOCUS_END: f32 = 700.0;
+
+fn xy([x, y, z]: [f32; 3]) -> [f32; 2] {
+    let sum = x + y + z;
+    [x / sum, y / sum]
+}
+
+/// Returns the chromaticities of the spectral locus of the CIE 1931 observer
+/// at each tabulated wavelength up to [`LOCUS_END`].
+pub(crate) fn spectral_locus() -> impl Iterator<Item = [f32; 2]> {
+    (0..)
+        .map(|i| CMF_START + i as f32 * CMF_STEP)
+        .take_while(|&nm| nm <= LOCUS_END)
+        .map(|nm| xy(Observer::Cie1931.color_matching(nm)))
+}
+
+/// Returns where the ray from `origin` along `direction` leaves the spectral
+/// locus, as the distance along `direction` and the wavelength hit, with `None`
+/// as the wavelength if it leaves through the line of purples.
+fn locus_intersection(origin: [f32; 2], direction: [f32; 2]) -> Option<(f32, Option<f32>)> {
+    let observer = Observer::Cie1931;
+    let point = |nm: f32| xy(observer.color_matching(nm));
+    let cross = |a: [f32; 2], b: [f32; 2]| a[0] * b[1] - a[1] * b[0];
+
+    let mut edges = (0..)
+        .map(|i| CMF_START + i as f32 * CMF_STEP)
+        .take_while(|&nm| nm < LOCUS_END)
+        .map(|nm| (nm, Some(nm + CMF_STEP)))
+        .chain(core::iter::once((LOCUS_END, None)));
+
+    edges.find_map(|(start_nm, end_nm)| {
+        let a = point(start_nm);
+        let b = point(end_nm.unwrap_or(CMF_START));
+        let edge = [b[0] - a[0], b[1] - a[1]];
+        let offset = [a[0] - origin[0], a[1] - origin[1]];
+
+        let denominator = cross(direction, edge);
+        if denominator == 0.0 {
+            return None;
+        }
+        let t = cross(offset, edge) / denominator;
+        let s = cross(offset, direction) / denominator;
+
+        (t > 0.0 && (0.0..=1.0).contains(&s)).then(|| (t, end_nm.map(|_| start_nm + s * CMF_STEP)))
+    })
+}
+
+/// Returns the dominant or complementary wavelength of `xyz` seen under
+/// `white`, or `None` if the color has the white's chromaticity.
+pub fn dominant_wavelength(xyz: [f32; 3], white: Whitepoint) -> Option<DominantWavelength> {
+    let white = xy(white.to_xyz());
+    let color = xy(xyz);
+    let direction = [color[0] - white[0], color[1] - white[1]];
+    if direction[0].hypot(direction[1]) < ACHROMATIC_EPSILON {
+        return None;
+    }
+
+    match locus_intersection(white, direction)? {
+        (_, Some(nm)) => Some(DominantWavelength::Dominant(nm)),
+        (_, None) => {
+            let (_, nm) = locus_intersection(white, direction.map(|d| -d))?;
+            nm.map(DominantWavelength::Complementary)
+        }
+    }
+}
+
+/// Returns the excitation purity of `xyz` seen under `white`, its distance
+/// from the white as a fraction of the distance to the spectral locus or line
+/// of purples in the same direction.
+pub fn excitation_purity(xyz: [f32; 3], white: Whitepoint) -> f32 {
+    let white = xy(white.to_xyz());
+    let color = xy(xyz);
+    let direction = [color[0] - white[0], color[1] - white[1]];
+    if direction[0].hypot(direction[1]) < ACHROMATIC_EPSILON {
+        return 0.0;
+    }
+
+    locus_intersection(white, direction).map_or(0.0, |(t, _)| 1.0 / t)
+}
@@ -0,0 +1,63 @@
+//! Reordering the channels of pixels, such as from BGRA or ARGB to RGBA,
+//! without converting them.
+
+/// Where a channel of a swizzled pixel comes from. For spaces other than RGB
+/// the red, green and blue channels are the first, second and third.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelIndex {
+    R,
+    G,
+    B,
+    A,
+    /// A constant zero, whatever the source pixel.
+    Zero,
+    /// A constant one, whatever the source pixel, as for the alpha of
+    /// pixels without one.
+    One,
+}
+
+impl ChannelIndex {
+    /// Returns the value of this channel for `pixel`.
+    pub fn select(self, pixel: [f32; 4]) -> f32 {
+        match self {
+            ChannelIndex::R => pixel[0],
+            ChannelIndex::G => pixel[1],
+            ChannelIndex::B => pixel[2],
+            ChannelIndex::A => pixel[3],
+            ChannelIndex::Zero => 0.0,
+            ChannelIndex::One => 1.0,
+        }
+    }
+}
+
+/// The pattern that leaves pixels unchanged.
+pub const IDENTITY: [ChannelIndex; 4] = [
+    ChannelIndex::R,
+    ChannelIndex::G,
+    ChannelIndex::B,
+    ChannelIndex::A,
+];
+
+/// Returns `pixel` with its channels taken from where `pattern` says, so
+/// `[B, G, R, A]` swaps red and blue and `[R, G, B, One]` makes it opaque.
+pub fn swizzle_pixel(pixel: [f32; 4], pattern: [ChannelIndex; 4]) -> [f32; 4] {
+    pattern.map(|channel| channel.select(pixel))
+}
+
+/// Writes each pixel of `image` swizzled with `pattern` to the same position
+/// in `out`, as with [`swizzle_pixel`].
+///
+/// # Panics
+///
+/// Panics if `image` and `out` have different lengths.
+pub fn swizzle(image: &[[f32; 4]], pattern: [ChannelIndex; 4], out: &mut [[f32; 4]]) {
+    assert_eq!(
+        image.len(),
+        out.len(),
+        "the swizzled image must be the same length as the input"
+    );
+
+    for (pixel, out) in image.iter().zip(out) {
+        *out = swizzle_pixel(*pixel, pattern);
+    }
+}
@@ -0,0 +1,105 @@
+//! Color temperature and white balance.
+
+use crate::{
+    adaptation::ChromaticAdaptation, rgb_color_space::RgbColorSpace, whitepoint::Whitepoint,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Returns the CIE 1960 `[u, v]` chromaticity of the Planckian locus at
+/// `temp_k`, using Krystek's rational approximation which is accurate from
+/// 1000K to 15000K.
+fn planckian_uv(temp_k: f32) -> [f32; 2] {
+    let t = f64::from(temp_k);
+    let u = (0.860_117_757 + 1.541_182_54e-4 * t + 1.286_412_12e-7 * t * t)
+        / (1.0 + 8.424_202_35e-4 * t + 7.081_451_63e-7 * t * t);
+    let v = (0.317_398_726 + 4.228_062_45e-5 * t + 4.204_816_91e-8 * t * t)
+        / (1.0 - 2.897_418_16e-5 * t + 1.614_560_53e-7 * t * t);
+    [u as f32, v as f32]
+}
+
+fn uv_to_xy([u, v]: [f32; 2]) -> [f32; 2] {
+    let d = 2.0 * u - 8.0 * v + 4.0;
+    [3.0 * u / d, 2.0 * v / d]
+}
+
+/// Returns the correlated color temperature in kelvin of the CIE 1931 `x, y`
+/// chromaticity, using McCamy's cubic approximation which is accurate to a
+/// few kelvin from 2856K to 6504K.
+pub fn cct_from_xy(x: f32, y: f32) -> f32 {
+    let n = (x - 0.3320) / (0.1858 - y);
+    449.0 * n.powi(3) + 3525.0 * n.powi(2) + 6823.3 * n + 5520.33
+}
+
+/// Returns the CIE 1931 `[x, y]` chromaticity of the Planckian locus at
+/// `temp_k`.
+pub fn xy_from_cct(temp_k: f32) -> [f32; 2] {
+    uv_to_xy(planckian_uv(temp_k))
+}
+
+/// Returns the whitepoint at `temp_k` offset from the Planckian locus by
+/// `tint` in CIE 1960 `Duv` units, positive values being greener.
+fn whitepoint_from_temperature(temp_k: f32, tint: f32) -> Whitepoint {
+    let [u, v] = planckian_uv(temp_k);
+    let [u2, v2] = planckian_uv(temp_k + 1.0);
+    let (du, dv) = (u2 - u, v2 - v);
+    let len = (du * du + dv * dv).sqrt();
+
+    // The locus runs toward lower u and v as temperature rises, so this normal
+    // points toward green.
+    let normal = [dv / len, -du / len];
+
+    Whitepoint::Custom(uv_to_xy([u + normal[0] * tint, v + normal[1] * tint]))
+}
+
+/// Re-balances linear-light `image` in `space` from a scene lit at
+/// `from_temp_k` to one lit at `to_temp_k`, shifted by `tint` in `Duv` units,
+/// by Bradford chromatic adaptation between the corresponding whitepoints.
+///
+/// Lowering the temperature warms the image and raising it cools it.
+pub fn white_balance(
+    image: &mut [[f32; 4]],
+    from_temp_k: f32,
+    to_temp_k: f32,
+    tint: f32,
+    space: &RgbColorSpace,
+) {
+    if from_temp_k == to_temp_k && tint == 0.0 {
+        return;
+    }
+
+    let adaptation = ChromaticAdaptation::Bradford.matrix(
+        whitepoint_from_temperature(from_temp_k, 0.0),
+        whitepoint_from_temperature(to_temp_k, tint),
+    );
+    let matrix = space.from_xyz_matrix() * adaptation * space.to_xyz_matrix();
+
+    for pixel in image {
+        let [r, g, b, a] = *pixel;
+        let [r, g, b] = matrix.mul_vec([r, g, b]);
+        *pixel = [r, g, b, a];
+    }
+}
+
+/// Returns the opaque sRGB color of a blackbody radiator at `temp_k`,
+/// normalized so its brightest channel is `1.0`.
+///
+/// Temperatures are clamped to the 1000K to 15000K range of the locus
+/// approximation. Below roughly 1700K the locus leaves the sRGB gamut, such
+/// colors are desaturated toward white until they fit.
+pub fn blackbody_srgb(temp_k: f32) -> [f32; 4] {
+    let [x, y] = xy_from_cct(temp_k.clamp(1000.0, 15000.0));
+    let space = RgbColorSpace::SRGB;
+    let rgb = space
+        .from_xyz_matrix()
+        .mul_vec([x / y, 1.0, (1.0 - x - y) / y]);
+
+    let min = rgb.into_iter().fold(0.0_f32, f32::min);
+    let rgb = rgb.map(|c| c - min);
+    let max = rgb.into_iter().fold(0.0_f32, f32::max);
+
+    space
+        .transfer
+        .from_optical_display([rgb[0] / max, rgb[1] / max, rgb[2] / max, 1.0])
+}
@@ -0,0 +1,1031 @@
+//! Transfer functions relating encoded signal values to linear light.
+
+use core::{fmt, ops::RangeInclusive};
+
+use crate::channel::{BitDepth, Rounding, SignalRange};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// A transfer function relating encoded signal values to linear light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transfer {
+    /// The values are already linear light.
+    Linear,
+    /// The piecewise sRGB curve from IEC 61966-2-1.
+    Srgb,
+    /// The sRGB curve mirrored for negative values, as used by scRGB and
+    /// extended sRGB to describe colors outside the sRGB gamut. It is the
+    /// same as [`Transfer::Srgb`] within `[0, 1]` and continues the curve
+    /// beyond it rather than clamping.
+    SrgbExtended,
+    /// The pure 2.6 power law of theatrical DCI projection from SMPTE RP
+    /// 431-2.
+    Dci,
+    /// A pure power law with the given exponent, decoding `v` to `v^γ`, as
+    /// described by Adobe RGB (1998), the PNG gAMA chunk and the gamma curves
+    /// of ICC profiles.
+    Gamma(f32),
+    /// The ITU-R BT.709 camera curve, shared by BT.601 and BT.2020.
+    Bt709,
+    /// The ACEScc logarithmic encoding from Academy S-2014-003.
+    AcesCc,
+    /// The ACEScct logarithmic encoding from Academy S-2016-001, which is
+    /// ACEScc with a linear toe below [`ACESCCT_X_BRK`].
+    AcesCct,
+    /// ARRI LogC3 at the given exposure index, encoding the normalized sensor
+    /// signal. It is scene-referred and pairs with ARRI Wide Gamut 3.
+    LogC3(ArriExposureIndex),
+    /// Sony S-Log3. It is scene-referred and pairs with S-Gamut3 and
+    /// S-Gamut3.Cine.
+    SLog3,
+    /// The ITU-R BT.2100 perceptual quantizer from SMPTE ST 2084. It is
+    /// display-referred and decodes to absolute luminance with `1.0` being
+    /// [`PQ_PEAK_LUMINANCE`].
+    Pq,
+    /// The ITU-R BT.2100 hybrid log-gamma curve. It is scene-referred,
+    /// decoding to scene light in `[0, 1]` in [`LightDomain::Scene`] and to
+    /// the light of the nominal 1000 cd/m² display, relative to its peak, in
+    /// [`LightDomain::Display`].
+    Hlg,
+    /// A curve defined outside this crate.
+    Custom(CustomCurve),
+}
+
+/// A transfer function defined outside this crate, such as a proprietary
+/// camera log curve, for use as [`Transfer::Custom`].
+pub trait CustomTransfer: fmt::Debug + Sync {
+    /// Decodes an encoded value into linear light.
+    fn eotf(&self, v: f32) -> f32;
+
+    /// Encodes a linear light value, inverting [`Self::eotf`].
+    fn oetf(&self, v: f32) -> f32;
+
+    /// Returns the domain of the linear light the curve is defined on,
+    /// which is the display unless overridden.
+    fn light_domain(&self) -> LightDomain {
+        LightDomain::Display
+    }
+
+    /// Returns the range of encoded values the curve is defined for, which
+    /// is `[0, 1]` unless overridden.
+    fn signal_range(&self) -> RangeInclusive<f32> {
+        0.0..=1.0
+    }
+}
+
+/// A [`CustomTransfer`] used as [`Transfer::Custom`].
+///
+/// The curve is borrowed for `'static` so that [`Transfer`], and the color
+/// spaces holding it, stay `Copy`. Use a `static` or leak a `Box` for curves
+/// built at runtime. Curves are equal only if they are the same object, so
+/// plans can still cancel a custom encode followed by its decode.
+#[derive(Clone, Copy)]
+pub struct CustomCurve(pub &'static dyn CustomTransfer);
+
+impl PartialEq for CustomCurve {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for CustomCurve {}
+
+impl fmt::Debug for CustomCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Whether linear light describes the scene in front of a camera or the
+/// light a display emits.
+///
+/// Scene light has to be rendered by an opto-optical transfer function
+/// (OOTF) before it is shown, which for HLG depends on the peak luminance of
+/// the display. Mixing the two without one is what makes naively converted
+/// HLG look flat and dark next to PQ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightDomain {
+    Scene,
+    Display,
+}
+
+/// The exposure indices ARRI publishes LogC3 parameters for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArriExposureIndex {
+    Ei160,
+    Ei200,
+    Ei250,
+    Ei320,
+    Ei400,
+    Ei500,
+    Ei640,
+    #[default]
+    Ei800,
+    Ei1000,
+    Ei1280,
+    Ei1600,
+}
+
+/// The parameters of the LogC3 curve at a particular exposure index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogC3Params {
+    pub cut: f32,
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl ArriExposureIndex {
+    /// The LogC3 parameters for the normalized sensor signal, from ARRI's
+    /// "ALEXA Log C Curve - Usage in VFX".
+    #[rustfmt::skip]
+    pub fn params(self) -> LogC3Params {
+        let (cut, b, c, d, e, f) = match self {
+            ArriExposureIndex::Ei160 => (0.005_561, 0.080_216, 0.269_036, 0.381_991, 5.842_037, 0.092_778),
+            ArriExposureIndex::Ei200 => (0.006_208, 0.076_621, 0.266_007, 0.382_478, 5.776_265, 0.092_782),
+            ArriExposureIndex::Ei250 => (0.006_871, 0.072_941, 0.262_978, 0.382_966, 5.710_495, 0.092_787),
+            ArriExposureIndex::Ei320 => (0.007_622, 0.068_768, 0.259_627, 0.383_508, 5.637_729, 0.092_791),
+            ArriExposureIndex::Ei400 => (0.008_318, 0.064_901, 0.256_598, 0.383_999, 5.571_955, 0.092_796),
+            ArriExposureIndex::Ei500 => (0.009_031, 0.060_939, 0.253_569, 0.384_493, 5.506_181, 0.092_800),
+            ArriExposureIndex::Ei640 => (0.009_840, 0.056_443, 0.250_219, 0.385_040, 5.433_437, 0.092_804),
+            ArriExposureIndex::Ei800 => (0.010_591, 0.052_272, 0.247_190, 0.385_537, 5.367_655, 0.092_809),
+            ArriExposureIndex::Ei1000 => (0.011_361, 0.047_996, 0.244_161, 0.386_036, 5.301_889, 0.092_814),
+            ArriExposureIndex::Ei1280 => (0.012_235, 0.043_137, 0.240_810, 0.386_590, 5.229_123, 0.092_819),
+            ArriExposureIndex::Ei1600 => (0.013_047, 0.038_625, 0.237_781, 0.387_093, 5.163_349, 0.092_823),
+        };
+
+        LogC3Params { cut, a: 5.555_556, b, c, d, e, f }
+    }
+}
+
+/// The analytic form of a transfer, from [`Transfer::parameters`], for
+/// regenerating the curve elsewhere such as in shader code or ICC curves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferParams {
+    /// The identity.
+    Linear,
+    /// A power curve, possibly with a linear toe, as the sRGB, BT.709 and
+    /// DCI curves are.
+    Power(PowerCurveParams),
+    /// ARRI LogC3 at one exposure index.
+    LogC3(LogC3Params),
+    /// The perceptual quantizer.
+    Pq(PqParams),
+    /// Hybrid log-gamma.
+    Hlg(HlgParams),
+    /// A curve with no parameterization here: ACEScc, ACEScct, S-Log3 and
+    /// custom curves, which are only available through [`Transfer`].
+    Other,
+}
+
+/// The parameters of a power curve with a linear toe, which encodes linear
+/// light `v` as
+///
+/// ```text
+/// slope * v                        if v <= threshold
+/// scale * v^(1 / power) - offset   otherwise
+/// ```
+///
+/// and decodes with its inverse, where a pure power law has a `threshold`
+/// of `0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerCurveParams {
+    /// The linear light value at which the linear toe ends.
+    pub threshold: f32,
+    /// The slope of the linear toe.
+    pub slope: f32,
+    /// The decoding exponent, such as `2.4` for sRGB.
+    pub power: f32,
+    pub scale: f32,
+    pub offset: f32,
+    /// Whether the curve is mirrored for negative values rather than
+    /// defined only for positive ones.
+    pub mirrored: bool,
+}
+
+/// The constants of the perceptual quantizer from SMPTE ST 2084, which
+/// encodes linear light `y`, relative to `peak_luminance`, as
+/// `((c1 + c2 * y^m1) / (1 + c3 * y^m1))^m2`.
+///
+/// They are in `f64`, which is how this crate evaluates the curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PqParams {
+    pub m1: f64,
+    pub m2: f64,
+    pub c1: f64,
+    pub c2: f64,
+    pub c3: f64,
+    /// The luminance in cd/m² that is encoded as `1.0`.
+    pub peak_luminance: f32,
+}
+
+/// The constants of the hybrid log-gamma OETF from ITU-R BT.2100, which
+/// encodes scene light `e` as `sqrt(3 * e)` up to `1 / 12` and
+/// `a * ln(12 * e - b) + c` beyond.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HlgParams {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+}
+
+impl Transfer {
+    /// Returns the transfer of the ITU-T H.273 transfer characteristics code
+    /// point `value`, as used by video formats, AVIF and PNG, or `None` for
+    /// reserved values and curves there is no variant for.
+    ///
+    /// BT.709, BT.601 and the 10 and 12 bit BT.2020 code points `1`, `6`,
+    /// `14` and `15` all define the same curve.
+    pub fn from_h273(value: u8) -> Option<Transfer> {
+        let transfer = match value {
+            1 | 6 | 14 | 15 => Transfer::Bt709,
+            8 => Transfer::Linear,
+            13 => Transfer::Srgb,
+            16 => Transfer::Pq,
+            18 => Transfer::Hlg,
+            _ => return None,
+        };
+        Some(transfer)
+    }
+
+    /// Returns the ITU-T H.273 transfer characteristics code point of this
+    /// transfer, inverting [`Self::from_h273`], or `None` if it has none.
+    /// [`Transfer::Bt709`] gives `1`.
+    pub fn to_h273(self) -> Option<u8> {
+        let value = match self {
+            Transfer::Bt709 => 1,
+            Transfer::Linear => 8,
+            Transfer::Srgb => 13,
+            Transfer::Pq => 16,
+            Transfer::Hlg => 18,
+            Transfer::SrgbExtended
+            | Transfer::Dci
+            | Transfer::Gamma(_)
+            | Transfer::AcesCc
+            | Transfer::AcesCct
+            | Transfer::LogC3(_)
+            | Transfer::SLog3
+            | Transfer::Custom(_) => return None,
+        };
+        Some(value)
+    }
+
+    /// Returns the domain of the linear light this curve is defined on, the
+    /// scene for camera curves and the display for the rest.
+    pub fn light_domain(self) -> LightDomain {
+        match self {
+            Transfer::Linear
+            | Transfer::Srgb
+            | Transfer::SrgbExtended
+            | Transfer::Dci
+            | Transfer::Gamma(_)
+            | Transfer::Pq => LightDomain::Display,
+            Transfer::Bt709
+            | Transfer::AcesCc
+            | Transfer::AcesCct
+            | Transfer::LogC3(_)
+            | Transfer::SLog3
+            | Transfer::Hlg => LightDomain::Scene,
+            Transfer::Custom(curve) => curve.0.light_domain(),
+        }
+    }
+
+    /// Returns the range of encoded values this transfer is defined for.
+    ///
+    /// The display curves and the HDR curves encode to `[0, 1]`, whereas
+    /// the log curves, [`Transfer::SrgbExtended`] and [`Transfer::Linear`]
+    /// give meaning to any finite value.
+    pub fn signal_range(self) -> RangeInclusive<f32> {
+        match self {
+            Transfer::Srgb
+            | Transfer::Dci
+            | Transfer::Gamma(_)
+            | Transfer::Bt709
+            | Transfer::Pq
+            | Transfer::Hlg => 0.0..=1.0,
+            Transfer::Linear
+            | Transfer::SrgbExtended
+            | Transfer::AcesCc
+            | Transfer::AcesCct
+            | Transfer::LogC3(_)
+            | Transfer::SLog3 => f32::MIN..=f32::MAX,
+            Transfer::Custom(curve) => curve.0.signal_range(),
+        }
+    }
+
+    /// Returns the range of linear light this transfer encodes.
+    ///
+    /// The log curves describe scene light, which is never negative. The
+    /// other curves clip negative values or extend to them.
+    pub fn linear_range(self) -> RangeInclusive<f32> {
+        match self {
+            Transfer::AcesCc | Transfer::AcesCct | Transfer::LogC3(_) | Transfer::SLog3 => {
+                0.0..=f32::MAX
+            }
+            Transfer::Linear
+            | Transfer::Srgb
+            | Transfer::SrgbExtended
+            | Transfer::Dci
+            | Transfer::Gamma(_)
+            | Transfer::Bt709
+            | Transfer::Pq
+            | Transfer::Hlg
+            | Transfer::Custom(_) => f32::MIN..=f32::MAX,
+        }
+    }
+
+    /// Returns the analytic form of this curve.
+    ///
+    /// The sRGB threshold is the exact intersection of its two segments,
+    /// [`SRGB_LINEAR_KNEE`], rather than the `0.0031308` IEC 61966-2-1
+    /// rounds it to, so the parameters regenerate this crate's curve.
+    pub fn parameters(self) -> TransferParams {
+        let srgb = PowerCurveParams {
+            threshold: SRGB_LINEAR_KNEE,
+            slope: 12.92,
+            power: 2.4,
+            scale: 1.055,
+            offset: 0.055,
+            mirrored: false,
+        };
+        match self {
+            Transfer::Linear => TransferParams::Linear,
+            Transfer::Srgb => TransferParams::Power(srgb),
+            Transfer::SrgbExtended => TransferParams::Power(PowerCurveParams {
+                mirrored: true,
+                ..srgb
+            }),
+            Transfer::Dci => Transfer::Gamma(DCI_GAMMA).parameters(),
+            Transfer::Gamma(gamma) => TransferParams::Power(PowerCurveParams {
+                threshold: 0.0,
+                slope: 0.0,
+                power: gamma,
+                scale: 1.0,
+                offset: 0.0,
+                mirrored: false,
+            }),
+            Transfer::Bt709 => TransferParams::Power(PowerCurveParams {
+                threshold: BT709_BETA,
+                slope: 4.5,
+                power: 1.0 / 0.45,
+                scale: BT709_ALPHA,
+                offset: BT709_ALPHA - 1.0,
+                mirrored: false,
+            }),
+            Transfer::LogC3(ei) => TransferParams::LogC3(ei.params()),
+            Transfer::Pq => TransferParams::Pq(PqParams {
+                m1: PQ_M1,
+                m2: PQ_M2,
+                c1: PQ_C1,
+                c2: PQ_C2,
+                c3: PQ_C3,
+                peak_luminance: PQ_PEAK_LUMINANCE,
+            }),
+            Transfer::Hlg => TransferParams::Hlg(HlgParams {
+                a: HLG_A,
+                b: HLG_B,
+                c: HLG_C,
+            }),
+            Transfer::AcesCc | Transfer::AcesCct | Transfer::SLog3 | Transfer::Custom(_) => {
+                TransferParams::Other
+            }
+        }
+    }
+
+    /// Decodes the color channels of `value` into linear light in `domain`,
+    /// leaving alpha untouched.
+    ///
+    /// Only [`Transfer::Hlg`] defines both domains, applying its OOTF for the
+    /// display. Every other curve decodes the same way whatever the domain.
+    #[must_use]
+    pub fn to_optical(self, domain: LightDomain, value: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, a] = value;
+        let decoded = [self.eotf(r), self.eotf(g), self.eotf(b), a];
+        match (self, domain) {
+            (Transfer::Hlg, LightDomain::Display) => {
+                let [r, g, b, a] = hlg_ootf(decoded, HLG_NOMINAL_PEAK_LUMINANCE);
+                let peak = HLG_NOMINAL_PEAK_LUMINANCE;
+                [r / peak, g / peak, b / peak, a]
+            }
+            _ => decoded,
+        }
+    }
+
+    /// Encodes the color channels of `value`, linear light in `domain`,
+    /// leaving alpha untouched. This inverts [`Self::to_optical`].
+    #[must_use]
+    pub fn from_optical(self, domain: LightDomain, value: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, a] = match (self, domain) {
+            (Transfer::Hlg, LightDomain::Display) => {
+                let [r, g, b, a] = value;
+                let peak = HLG_NOMINAL_PEAK_LUMINANCE;
+                hlg_inverse_ootf([r * peak, g * peak, b * peak, a], peak)
+            }
+            _ => value,
+        };
+        [self.oetf(r), self.oetf(g), self.oetf(b), a]
+    }
+
+    /// Decodes the color channels of `value` into display light, leaving
+    /// alpha untouched. This is [`Self::to_optical`] in
+    /// [`LightDomain::Display`].
+    #[must_use]
+    pub fn to_optical_display(self, value: [f32; 4]) -> [f32; 4] {
+        self.to_optical(LightDomain::Display, value)
+    }
+
+    /// Encodes the display light color channels of `value`, leaving alpha
+    /// untouched. This is [`Self::from_optical`] in [`LightDomain::Display`].
+    #[must_use]
+    pub fn from_optical_display(self, value: [f32; 4]) -> [f32; 4] {
+        self.from_optical(LightDomain::Display, value)
+    }
+
+    /// Decodes a single value into display light, treating it as a gray as
+    /// is right for luminance planes, depth maps and masks.
+    #[must_use]
+    pub fn to_optical_scalar(self, v: f32) -> f32 {
+        match self {
+            // The OOTF depends on luminance, which for a gray is the value.
+            Transfer::Hlg => self.to_optical_display([v, v, v, 1.0])[0],
+            _ => self.eotf(v),
+        }
+    }
+
+    /// Encodes a single display light value, treating it as a gray. This
+    /// inverts [`Self::to_optical_scalar`].
+    #[must_use]
+    pub fn from_optical_scalar(self, v: f32) -> f32 {
+        match self {
+            Transfer::Hlg => self.from_optical_display([v, v, v, 1.0])[0],
+            _ => self.oetf(v),
+        }
+    }
+
+    /// Decodes every value of `values` in place with
+    /// [`Self::to_optical_scalar`].
+    ///
+    /// Every value is decoded, so interleaved RGBA channels should be
+    /// decoded with [`Self::to_optical_pixels`] instead to keep alpha linear.
+    pub fn to_optical_slice(self, values: &mut [f32]) {
+        for v in values {
+            *v = self.to_optical_scalar(*v);
+        }
+    }
+
+    /// Encodes every value of `values` in place with
+    /// [`Self::from_optical_scalar`].
+    ///
+    /// Every value is encoded, so interleaved RGBA channels should be
+    /// encoded with [`Self::from_optical_pixels`] instead to keep alpha
+    /// linear.
+    pub fn from_optical_slice(self, values: &mut [f32]) {
+        for v in values {
+            *v = self.from_optical_scalar(*v);
+        }
+    }
+
+    /// Decodes the color channels of every pixel of `pixels` in place with
+    /// [`Self::to_optical_display`]. Alpha is straight coverage rather than
+    /// an encoded signal, so it is left exactly as it is.
+    pub fn to_optical_pixels(self, pixels: &mut [[f32; 4]]) {
+        for pixel in pixels {
+            *pixel = self.to_optical_display(*pixel);
+        }
+    }
+
+    /// Encodes the color channels of every pixel of `pixels` in place with
+    /// [`Self::from_optical_display`], leaving alpha exactly as it is.
+    pub fn from_optical_pixels(self, pixels: &mut [[f32; 4]]) {
+        for pixel in pixels {
+            *pixel = self.from_optical_display(*pixel);
+        }
+    }
+
+    /// Decodes integer R'G'B' codes stored at `depth` in `range` into display
+    /// light, expanding the range to `[0, 1]` before the EOTF as with
+    /// [`SignalRange::to_normalized`].
+    ///
+    /// PQ and HLG video is usually stored in the narrow range, and decoding
+    /// it as full range lifts blacks and dims whites, while the reverse
+    /// crushes blacks and clips whites.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` isn't valid for `range`, as for
+    /// [`SignalRange::black_and_white`].
+    pub fn to_optical_codes(
+        self,
+        codes: [u16; 3],
+        depth: BitDepth,
+        range: SignalRange,
+    ) -> [f32; 3] {
+        let [r, g, b] = codes.map(|code| range.to_normalized(code, depth));
+        let [r, g, b, _] = self.to_optical_display([r, g, b, 1.0]);
+        [r, g, b]
+    }
+
+    /// Encodes display light into integer R'G'B' codes stored at `depth` in
+    /// `range`, inverting [`Self::to_optical_codes`] up to rounding with
+    /// `rounding`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` isn't valid for `range`, as for
+    /// [`SignalRange::black_and_white`].
+    pub fn from_optical_codes(
+        self,
+        linear: [f32; 3],
+        depth: BitDepth,
+        range: SignalRange,
+        rounding: Rounding,
+    ) -> [u16; 3] {
+        let [r, g, b] = linear;
+        let [r, g, b, _] = self.from_optical_display([r, g, b, 1.0]);
+        [r, g, b].map(|v| range.from_normalized(v, depth, rounding))
+    }
+
+    fn eotf(self, v: f32) -> f32 {
+        match self {
+            Transfer::Linear => v,
+            Transfer::Srgb => transfer_eo_srgb(v),
+            Transfer::SrgbExtended => transfer_eo_srgb_extended(v),
+            Transfer::Dci => transfer_eo_dci(v),
+            Transfer::Gamma(gamma) => v.max(0.0).powf(gamma),
+            Transfer::Bt709 => transfer_eo_bt709(v),
+            Transfer::AcesCc => transfer_eo_acescc(v),
+            Transfer::AcesCct => transfer_eo_acescct(v),
+            Transfer::LogC3(ei) => transfer_eo_logc3(v, ei.params()),
+            Transfer::SLog3 => transfer_eo_slog3(v),
+            Transfer::Pq => transfer_eo_pq(v),
+            Transfer::Hlg => transfer_eo_hlg(v),
+            Transfer::Custom(curve) => curve.0.eotf(v),
+        }
+    }
+
+    fn oetf(self, v: f32) -> f32 {
+        match self {
+            Transfer::Linear => v,
+            Transfer::Srgb => transfer_oe_srgb(v),
+            Transfer::SrgbExtended => transfer_oe_srgb_extended(v),
+            Transfer::Dci => transfer_oe_dci(v),
+            Transfer::Gamma(gamma) => v.max(0.0).powf(1.0 / gamma),
+            Transfer::Bt709 => transfer_oe_bt709(v),
+            Transfer::AcesCc => transfer_oe_acescc(v),
+            Transfer::AcesCct => transfer_oe_acescct(v),
+            Transfer::LogC3(ei) => transfer_oe_logc3(v, ei.params()),
+            Transfer::SLog3 => transfer_oe_slog3(v),
+            Transfer::Pq => transfer_oe_pq(v),
+            Transfer::Hlg => transfer_oe_hlg(v),
+            Transfer::Custom(curve) => curve.0.oetf(v),
+        }
+    }
+}
+
+/// Which way a transfer is applied, as by [`apply_transfer_channel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Decodes encoded values into linear light with the EOTF.
+    Decode,
+    /// Encodes linear light with the OETF.
+    Encode,
+}
+
+/// Applies `transfer` in `direction` to every value of `channel` in place,
+/// for images stored with each channel in its own plane. Linear values are
+/// in display light, as [`Transfer::to_optical_pixels`] gives them.
+///
+/// This is [`apply_transfer_channel_in_domain`] in [`LightDomain::Display`].
+pub fn apply_transfer_channel(transfer: Transfer, channel: &mut [f32], direction: Direction) {
+    apply_transfer_channel_in_domain(transfer, channel, direction, LightDomain::Display);
+}
+
+/// Applies `transfer` in `direction` to every value of `channel` in place,
+/// with linear light in `domain`.
+///
+/// Every transfer but [`Transfer::Hlg`] handles each value exactly as
+/// [`Transfer::to_optical`] and [`Transfer::from_optical`] handle that
+/// channel of an RGBA pixel, in either domain. The HLG OOTF of display light
+/// depends on the luminance of the whole pixel, which a lone channel doesn't
+/// have, so there each value is treated as a gray as by
+/// [`Transfer::to_optical_scalar`]. That matches the RGBA path for gray
+/// pixels only. HLG in scene light has no OOTF and matches exactly.
+pub fn apply_transfer_channel_in_domain(
+    transfer: Transfer,
+    channel: &mut [f32],
+    direction: Direction,
+    domain: LightDomain,
+) {
+    for v in channel {
+        *v = match (direction, domain) {
+            (Direction::Decode, LightDomain::Scene) => transfer.eotf(*v),
+            (Direction::Encode, LightDomain::Scene) => transfer.oetf(*v),
+            (Direction::Decode, LightDomain::Display) => transfer.to_optical_scalar(*v),
+            (Direction::Encode, LightDomain::Display) => transfer.from_optical_scalar(*v),
+        };
+    }
+}
+
+/// Finds the encoded value `transfer` decodes to the display light `y` by
+/// solving its EOTF numerically, as [`Transfer::from_optical_scalar`] does in
+/// closed form.
+///
+/// This is a reference for checking closed form inverses and a fallback for
+/// curves that only define an EOTF. Values of `y` beyond what the transfer
+/// can decode give the nearest end of its [`Transfer::signal_range`].
+pub fn invert_eotf(transfer: Transfer, y: f32) -> f32 {
+    invert_increasing(
+        |v| transfer.to_optical_scalar(v),
+        y,
+        transfer.signal_range(),
+    )
+}
+
+/// Finds the `x` in `domain` where the increasing function `f` is `y` by
+/// bisection, giving the nearest end of `domain` if `f` never reaches `y`.
+pub fn invert_increasing(f: impl Fn(f32) -> f32, y: f32, domain: RangeInclusive<f32>) -> f32 {
+    if y.is_nan() {
+        return f32::NAN;
+    }
+
+    // Start from [0, 1], where every transfer's values of interest are, and
+    // widen it as far as the domain allows until it brackets `y`.
+    let (start, end) = (*domain.start(), *domain.end());
+    let mut lo = 0.0_f32.clamp(start, end);
+    let mut hi = 1.0_f32.clamp(start, end);
+    while f(lo) > y && lo > start {
+        lo = (lo * 2.0 - 1.0).max(start);
+    }
+    while f(hi) < y && hi < end {
+        hi = (hi * 2.0 + 1.0).min(end);
+    }
+    if f(lo) > y {
+        return lo;
+    }
+    if f(hi) < y {
+        return hi;
+    }
+
+    // Halving stops once there is no `f32` between the ends, so this takes
+    // at most a few hundred steps even across the whole `f32` range.
+    loop {
+        let mid = lo / 2.0 + hi / 2.0;
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if f(mid) < y {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    if (f(lo) - y).abs() <= (f(hi) - y).abs() {
+        lo
+    } else {
+        hi
+    }
+}
+
+/// Where the linear segment of the sRGB curve meets the power segment, in
+/// linear light and encoded. IEC 61966-2-1 rounds these to `0.0031308` and
+/// `0.04045`, where the segments are slightly apart, so the exact
+/// intersection is used to keep the curve continuous.
+pub const SRGB_LINEAR_KNEE: f32 = 0.003_130_668_4;
+pub const SRGB_ENCODED_KNEE: f32 = 0.040_448_237;
+
+pub fn transfer_eo_srgb(v: f32) -> f32 {
+    if v <= SRGB_ENCODED_KNEE {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn transfer_oe_srgb(v: f32) -> f32 {
+    if v <= SRGB_LINEAR_KNEE {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn transfer_eo_srgb_extended(v: f32) -> f32 {
+    transfer_eo_srgb(v.abs()).copysign(v)
+}
+
+pub fn transfer_oe_srgb_extended(v: f32) -> f32 {
+    transfer_oe_srgb(v.abs()).copysign(v)
+}
+
+/// The gamma of theatrical DCI projection.
+pub(crate) const DCI_GAMMA: f32 = 2.6;
+
+pub fn transfer_eo_dci(v: f32) -> f32 {
+    v.max(0.0).powf(DCI_GAMMA)
+}
+
+pub fn transfer_oe_dci(v: f32) -> f32 {
+    v.max(0.0).powf(1.0 / DCI_GAMMA)
+}
+
+/// BT.709's `α` and `β` to the precision given in BT.2020.
+pub(crate) const BT709_ALPHA: f32 = 1.099_296_8;
+pub(crate) const BT709_BETA: f32 = 0.018_053_97;
+
+pub fn transfer_eo_bt709(v: f32) -> f32 {
+    if v < 4.5 * BT709_BETA {
+        v / 4.5
+    } else {
+        ((v + (BT709_ALPHA - 1.0)) / BT709_ALPHA).powf(1.0 / 0.45)
+    }
+}
+
+pub fn transfer_oe_bt709(v: f32) -> f32 {
+    if v < BT709_BETA {
+        4.5 * v
+    } else {
+        BT709_ALPHA * v.powf(0.45) - (BT709_ALPHA - 1.0)
+    }
+}
+
+/// The largest value representable in half-float, which ACES clamps to.
+pub(crate) const ACES_HALF_MAX: f32 = 65504.0;
+
+/// The linear value below which ACEScct switches to its linear toe.
+pub const ACESCCT_X_BRK: f32 = 0.007_812_5;
+/// The ACEScct encoded value of [`ACESCCT_X_BRK`].
+pub const ACESCCT_Y_BRK: f32 = 0.155_251_14;
+pub(crate) const ACESCCT_A: f32 = 10.540_237;
+pub(crate) const ACESCCT_B: f32 = 0.072_905_53;
+
+fn acescc_log(v: f32) -> f32 {
+    (v.log2() + 9.72) / 17.52
+}
+
+fn acescc_exp(v: f32) -> f32 {
+    (v * 17.52 - 9.72).exp2()
+}
+
+pub fn transfer_oe_acescc(v: f32) -> f32 {
+    if v <= 0.0 {
+        acescc_log(2.0_f32.powi(-16))
+    } else if v < 2.0_f32.powi(-15) {
+        acescc_log(2.0_f32.powi(-16) + v * 0.5)
+    } else {
+        acescc_log(v)
+    }
+}
+
+pub fn transfer_eo_acescc(v: f32) -> f32 {
+    if v <= (9.72 - 15.0) / 17.52 {
+        (acescc_exp(v) - 2.0_f32.powi(-16)) * 2.0
+    } else if v < acescc_log(ACES_HALF_MAX) {
+        acescc_exp(v)
+    } else {
+        ACES_HALF_MAX
+    }
+}
+
+pub fn transfer_oe_acescct(v: f32) -> f32 {
+    if v <= ACESCCT_X_BRK {
+        ACESCCT_A * v + ACESCCT_B
+    } else {
+        acescc_log(v)
+    }
+}
+
+pub fn transfer_eo_acescct(v: f32) -> f32 {
+    if v <= ACESCCT_Y_BRK {
+        (v - ACESCCT_B) / ACESCCT_A
+    } else if v < acescc_log(ACES_HALF_MAX) {
+        acescc_exp(v)
+    } else {
+        ACES_HALF_MAX
+    }
+}
+
+pub fn transfer_oe_logc3(v: f32, p: LogC3Params) -> f32 {
+    if v > p.cut {
+        p.c * (p.a * v + p.b).log10() + p.d
+    } else {
+        p.e * v + p.f
+    }
+}
+
+pub fn transfer_eo_logc3(v: f32, p: LogC3Params) -> f32 {
+    if v > p.e * p.cut + p.f {
+        (10.0_f32.powf((v - p.d) / p.c) - p.b) / p.a
+    } else {
+        (v - p.f) / p.e
+    }
+}
+
+/// The S-Log3 code value, out of 1023, at which the linear toe ends.
+pub(crate) const SLOG3_CUT_CODE: f32 = 171.210_3;
+
+pub fn transfer_oe_slog3(v: f32) -> f32 {
+    if v >= 0.011_25 {
+        (420.0 + ((v + 0.01) / (0.18 + 0.01)).log10() * 261.5) / 1023.0
+    } else {
+        (v * (SLOG3_CUT_CODE - 95.0) / 0.011_25 + 95.0) / 1023.0
+    }
+}
+
+pub fn transfer_eo_slog3(v: f32) -> f32 {
+    if v >= SLOG3_CUT_CODE / 1023.0 {
+        10.0_f32.powf((v * 1023.0 - 420.0) / 261.5) * (0.18 + 0.01) - 0.01
+    } else {
+        (v * 1023.0 - 95.0) * 0.011_25 / (SLOG3_CUT_CODE - 95.0)
+    }
+}
+
+/// The luminance in cd/m² that PQ encodes as `1.0`.
+pub const PQ_PEAK_LUMINANCE: f32 = 10000.0;
+
+// PQ's exponents are steep enough that evaluating it in `f32` loses several
+// ulps, so it is evaluated in `f64`.
+pub(crate) const PQ_M1: f64 = 2610.0 / 16384.0;
+pub(crate) const PQ_M2: f64 = 2523.0 / 4096.0 * 128.0;
+pub(crate) const PQ_C1: f64 = 3424.0 / 4096.0;
+pub(crate) const PQ_C2: f64 = 2413.0 / 4096.0 * 32.0;
+pub(crate) const PQ_C3: f64 = 2392.0 / 4096.0 * 32.0;
+
+pub fn transfer_eo_pq(v: f32) -> f32 {
+    let p = f64::from(v.max(0.0)).powf(1.0 / PQ_M2);
+    ((p - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * p)).powf(1.0 / PQ_M1) as f32
+}
+
+pub fn transfer_oe_pq(v: f32) -> f32 {
+    let y = f64::from(v.max(0.0)).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y)).powf(PQ_M2) as f32
+}
+
+pub(crate) const HLG_A: f32 = 0.178_832_77;
+pub(crate) const HLG_B: f32 = 1.0 - 4.0 * HLG_A;
+pub(crate) const HLG_C: f32 = 0.559_910_7;
+
+pub fn transfer_oe_hlg(v: f32) -> f32 {
+    if v <= 1.0 / 12.0 {
+        (3.0 * v.max(0.0)).sqrt()
+    } else {
+        HLG_A * (12.0 * v - HLG_B).ln() + HLG_C
+    }
+}
+
+pub fn transfer_eo_hlg(v: f32) -> f32 {
+    if v <= 0.5 {
+        v.max(0.0) * v.max(0.0) / 3.0
+    } else {
+        (((v - HLG_C) / HLG_A).exp() + HLG_B) / 12.0
+    }
+}
+
+/// The peak luminance in cd/m² of the reference display HLG is decoded for
+/// when no other is given, at which its system gamma is `1.2`.
+pub const HLG_NOMINAL_PEAK_LUMINANCE: f32 = 1000.0;
+
+/// The BT.2100 luminance weights of the BT.2020 primaries, which the HLG
+/// OOTF applies its system gamma to.
+pub(crate) const BT2100_LUMINANCE: [f32; 3] = [0.2627, 0.6780, 0.0593];
+
+/// Returns the HLG system gamma for a display with a nominal peak of
+/// `peak_luminance` cd/m², which is `1.2` at 1000 cd/m².
+pub fn hlg_system_gamma(peak_luminance: f32) -> f32 {
+    1.2 + 0.42 * (peak_luminance / 1000.0).log10()
+}
+
+/// Renders HLG scene light in `[0, 1]` as the display light, in cd/m², of a
+/// display with a nominal peak of `peak_luminance` cd/m² and zero black
+/// level, leaving alpha untouched.
+#[must_use]
+pub fn hlg_ootf(scene: [f32; 4], peak_luminance: f32) -> [f32; 4] {
+    let [r, g, b, alpha] = scene;
+    let y = BT2100_LUMINANCE[0] * r + BT2100_LUMINANCE[1] * g + BT2100_LUMINANCE[2] * b;
+    if y <= 0.0 {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+
+    let gain = peak_luminance * y.powf(hlg_system_gamma(peak_luminance) - 1.0);
+    [r * gain, g * gain, b * gain, alpha]
+}
+
+/// Inverts [`hlg_ootf`], taking display light in cd/m² back to scene light.
+#[must_use]
+pub fn hlg_inverse_ootf(display: [f32; 4], peak_luminance: f32) -> [f32; 4] {
+    let [r, g, b, alpha] = display;
+    let y = BT2100_LUMINANCE[0] * r + BT2100_LUMINANCE[1] * g + BT2100_LUMINANCE[2] * b;
+    if y <= 0.0 {
+        return [0.0, 0.0, 0.0, alpha];
+    }
+
+    let gamma = hlg_system_gamma(peak_luminance);
+    let scene_y = (y / peak_luminance).powf(1.0 / gamma);
+    let gain = peak_luminance * scene_y.powf(gamma - 1.0);
+    [r / gain, g / gain, b / gain, alpha]
+}
+
+/// Converts linear light between domains with the HLG OOTF for a display
+/// with a nominal peak of `peak_luminance` cd/m². Scene light is in `[0, 1]`
+/// and display light is in cd/m².
+#[must_use]
+pub fn convert_light_domain(
+    pixel: [f32; 4],
+    from: LightDomain,
+    to: LightDomain,
+    peak_luminance: f32,
+) -> [f32; 4] {
+    match (from, to) {
+        (LightDomain::Scene, LightDomain::Display) => hlg_ootf(pixel, peak_luminance),
+        (LightDomain::Display, LightDomain::Scene) => hlg_inverse_ootf(pixel, peak_luminance),
+        _ => pixel,
+    }
+}
+
+/// Converts an HLG signal to the PQ signal that shows the same picture on a
+/// display with a nominal peak of `peak_luminance` cd/m².
+#[must_use]
+pub fn hlg_to_pq(signal: [f32; 4], peak_luminance: f32) -> [f32; 4] {
+    let scene = Transfer::Hlg.to_optical(LightDomain::Scene, signal);
+    let display = hlg_ootf(scene, peak_luminance);
+    let [r, g, b, alpha] = display;
+    Transfer::Pq.from_optical(
+        LightDomain::Display,
+        [
+            r / PQ_PEAK_LUMINANCE,
+            g / PQ_PEAK_LUMINANCE,
+            b / PQ_PEAK_LUMINANCE,
+            alpha,
+        ],
+    )
+}
+
+/// Converts a PQ signal to the HLG signal that shows the same picture on a
+/// display with a nominal peak of `peak_luminance` cd/m². PQ light above the
+/// peak is outside what HLG can describe and is not clipped.
+#[must_use]
+pub fn pq_to_hlg(signal: [f32; 4], peak_luminance: f32) -> [f32; 4] {
+    let [r, g, b, alpha] = Transfer::Pq.to_optical(LightDomain::Display, signal);
+    let display = [
+        r * PQ_PEAK_LUMINANCE,
+        g * PQ_PEAK_LUMINANCE,
+        b * PQ_PEAK_LUMINANCE,
+        alpha,
+    ];
+    Transfer::Hlg.from_optical(
+        LightDomain::Scene,
+        hlg_inverse_ootf(display, peak_luminance),
+    )
+}
+
+/// Maps display light of `linear` cd/m², mastered for a peak of `src_peak`
+/// cd/m², onto a display with a lower peak of `dst_peak` cd/m² with the
+/// BT.2390 EETF.
+///
+/// The curve works on the PQ signal relative to the source peak, leaving
+/// everything below a knee unchanged and rolling off above it with a
+/// Hermite spline that reaches `dst_peak` exactly at `src_peak`. Light
+/// above the source peak is treated as the peak, and black is kept at zero.
+/// A destination as bright as the source leaves `linear` unchanged.
+pub fn bt2390_eetf(linear: f32, src_peak: f32, dst_peak: f32) -> f32 {
+    if dst_peak >= src_peak {
+        return linear;
+    }
+
+    let pq = |luminance: f32| transfer_oe_pq(luminance / PQ_PEAK_LUMINANCE);
+    let src_signal = pq(src_peak);
+    let e1 = (pq(linear) / src_signal).min(1.0);
+    let max_lum = pq(dst_peak) / src_signal;
+    let knee = 1.5 * max_lum - 0.5;
+
+    let e2 = if e1 < knee {
+        e1
+    } else {
+        let t = (e1 - knee) / (1.0 - knee);
+        let (t2, t3) = (t * t, t * t * t);
+        (2.0 * t3 - 3.0 * t2 + 1.0) * knee
+            + (t3 - 2.0 * t2 + t) * (1.0 - knee)
+            + (-2.0 * t3 + 3.0 * t2) * max_lum
+    };
+
+    transfer_eo_pq(e2 * src_signal) * PQ_PEAK_LUMINANCE
+}
+
+/// Tone maps display light in cd/m² as [`bt2390_eetf`] does, applying the
+/// curve to the largest color channel and scaling the others with it so
+/// that hue and saturation are kept. Alpha is left untouched.
+#[must_use]
+pub fn tone_map_bt2390(pixel: [f32; 4], src_peak: f32, dst_peak: f32) -> [f32; 4] {
+    let [r, g, b, alpha] = pixel;
+    let max = r.max(g).max(b);
+    if max <= 0.0 {
+        return pixel;
+    }
+
+    let gain = bt2390_eetf(max, src_peak, dst_peak) / max;
+    [r * gain, g * gain, b * gain, alpha]
+}
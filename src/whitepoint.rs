@@ -0,0 +1,103 @@
+//! Reference whites used by color spaces and chromatic adaptation.
+
+use crate::temperature::cct_from_xy;
+
+/// A reference white, as a chromaticity on the CIE 1931 2° observer.
+///
+/// Every white, including the equal-energy and fluorescent illuminants, is
+/// handled the same way by chromatic adaptation and the spaces relative to a
+/// white such as [`ColorSpace::CieLab`](crate::color_space::ColorSpace::CieLab).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Whitepoint {
+    /// Incandescent tungsten light.
+    A,
+    /// Horizon daylight, used by ICC profile connection spaces.
+    D50,
+    /// Mid-morning daylight.
+    D55,
+    /// Noon daylight, used by sRGB and most video standards.
+    D65,
+    /// North sky daylight.
+    D75,
+    /// The equal-energy illuminant.
+    E,
+    /// Cool white fluorescent.
+    F2,
+    /// Broadband daylight fluorescent.
+    F7,
+    /// Narrowband white fluorescent.
+    F11,
+    /// The greenish white of theatrical DCI projection from SMPTE RP 431-2,
+    /// which is not a daylight or standard illuminant.
+    Dci,
+    /// An arbitrary `[x, y]` chromaticity.
+    Custom([f32; 2]),
+}
+
+/// The named whites [`Whitepoint::from_xy`] recognizes.
+pub(crate) const NAMED_WHITES: [Whitepoint; 10] = [
+    Whitepoint::A,
+    Whitepoint::D50,
+    Whitepoint::D55,
+    Whitepoint::D65,
+    Whitepoint::D75,
+    Whitepoint::E,
+    Whitepoint::F2,
+    Whitepoint::F7,
+    Whitepoint::F11,
+    Whitepoint::Dci,
+];
+
+/// How close a chromaticity must be to a named white's to be taken
+/// as it, half a unit in the fourth decimal place that specs and ICC
+/// profiles commonly round to.
+const ILLUMINANT_TOLERANCE: f32 = 5e-5;
+
+impl Whitepoint {
+    /// Returns the named white with the `[x, y]` chromaticity `xy`, or a
+    /// [`Whitepoint::Custom`] if it is none of them.
+    pub fn from_xy(xy: [f32; 2]) -> Whitepoint {
+        NAMED_WHITES
+            .into_iter()
+            .find(|illuminant| {
+                let [x, y] = illuminant.chromaticity();
+                (x - xy[0]).abs() <= ILLUMINANT_TOLERANCE
+                    && (y - xy[1]).abs() <= ILLUMINANT_TOLERANCE
+            })
+            .unwrap_or(Whitepoint::Custom(xy))
+    }
+
+    /// Returns the `[x, y]` chromaticity of the white, `X / (X + Y + Z)` and
+    /// `Y / (X + Y + Z)` of its tristimulus values.
+    pub fn to_xy(self) -> [f32; 2] {
+        self.chromaticity()
+    }
+
+    pub(crate) fn chromaticity(self) -> [f32; 2] {
+        match self {
+            Whitepoint::A => [0.447_57, 0.407_45],
+            Whitepoint::D50 => [0.345_7, 0.358_5],
+            Whitepoint::D55 => [0.332_42, 0.347_43],
+            Whitepoint::D65 => [0.312_7, 0.329_0],
+            Whitepoint::D75 => [0.299_02, 0.314_85],
+            Whitepoint::E => [1.0 / 3.0, 1.0 / 3.0],
+            Whitepoint::F2 => [0.372_08, 0.375_29],
+            Whitepoint::F7 => [0.312_92, 0.329_33],
+            Whitepoint::F11 => [0.380_54, 0.377_13],
+            Whitepoint::Dci => [0.314, 0.351],
+            Whitepoint::Custom(xy) => xy,
+        }
+    }
+
+    /// Returns the XYZ tristimulus values of the white, normalized to `Y = 1`.
+    pub fn to_xyz(self) -> [f32; 3] {
+        let [x, y] = self.chromaticity();
+        [x / y, 1.0, (1.0 - x - y) / y]
+    }
+
+    /// Returns the correlated color temperature of the white in kelvin.
+    pub fn cct(&self) -> f32 {
+        let [x, y] = self.chromaticity();
+        cct_from_xy(x, y)
+    }
+}
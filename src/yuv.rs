@@ -0,0 +1,581 @@
+//! Luma and color difference (Y'CbCr) encodings of RGB color spaces.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    channel::{ChannelEncoding, Rounding},
+    color_space::ColorSpace,
+    matrix::RowMatrix,
+    plan::{convert_to_planar, ConversionPlan},
+    primaries::Primaries,
+    rgb_color_space::RgbColorSpace,
+};
+
+/// The luma coefficients used to form color differences from non-linear RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Differencing {
+    /// ITU-R BT.601 for 525 line systems.
+    #[allow(non_camel_case_types)]
+    Bt601_525,
+    /// ITU-R BT.601 for 625 line systems.
+    #[allow(non_camel_case_types)]
+    Bt601_625,
+    /// ITU-R BT.709.
+    Bt709,
+    /// ITU-R BT.2020 non-constant luminance.
+    Bt2020,
+    /// The analog U and V of ITU-R BT.470 System M/PAL, with the color
+    /// difference scale factors of `0.493` and `0.877` as published.
+    ///
+    /// The published `0.493` doesn't follow from the derivation the factors
+    /// come from, see [`Differencing::Bt470MPalPrecise`], but is kept for
+    /// matching equipment and software built to the text.
+    Bt470MPal,
+    /// The analog U and V of ITU-R BT.470 System M/PAL, with the scale
+    /// factors derived exactly rather than as published.
+    Bt470MPalPrecise,
+}
+
+impl Differencing {
+    /// Returns the differencing of the ITU-T H.273 matrix coefficients code
+    /// point `value`, as used by video formats and AVIF, or `None` for RGB's
+    /// `0`, reserved values and matrices there is no variant for.
+    pub fn from_h273(value: u8) -> Option<Differencing> {
+        let differencing = match value {
+            1 => Differencing::Bt709,
+            5 => Differencing::Bt601_625,
+            6 => Differencing::Bt601_525,
+            9 => Differencing::Bt2020,
+            _ => return None,
+        };
+        Some(differencing)
+    }
+
+    /// Returns the ITU-T H.273 matrix coefficients code point of this
+    /// differencing, inverting [`Self::from_h273`], or `None` if it has
+    /// none.
+    pub fn to_h273(self) -> Option<u8> {
+        let value = match self {
+            Differencing::Bt709 => 1,
+            Differencing::Bt601_625 => 5,
+            Differencing::Bt601_525 => 6,
+            Differencing::Bt2020 => 9,
+            Differencing::Bt470MPal | Differencing::Bt470MPalPrecise => return None,
+        };
+        Some(value)
+    }
+
+    /// Returns the `[Kr, Kg, Kb]` weights luma is formed from.
+    ///
+    /// Both BT.601 line standards publish the same weights, inherited from
+    /// the 1953 NTSC primaries rather than derived from their own.
+    pub fn luma_coefficients(self) -> [f32; 3] {
+        let (kr, kb) = match self {
+            Differencing::Bt601_525
+            | Differencing::Bt601_625
+            | Differencing::Bt470MPal
+            | Differencing::Bt470MPalPrecise => (0.299, 0.114),
+            Differencing::Bt709 => (0.2126, 0.0722),
+            Differencing::Bt2020 => (0.2627, 0.0593),
+        };
+        [kr, 1.0 - kr - kb, kb]
+    }
+
+    /// Returns the primaries of the standard these coefficients come from.
+    ///
+    /// M/PAL is a 525 line system and shares the primaries of BT.601 for 525
+    /// lines.
+    pub fn primaries(self) -> Primaries {
+        match self {
+            Differencing::Bt601_525 | Differencing::Bt470MPal | Differencing::Bt470MPalPrecise => {
+                Primaries::Bt601_525
+            }
+            Differencing::Bt601_625 => Primaries::Bt601_625,
+            Differencing::Bt709 => Primaries::Bt709,
+            Differencing::Bt2020 => Primaries::Bt2020,
+        }
+    }
+
+    /// Returns the factors `B' - Y'` and `R' - Y'` are scaled by to give the
+    /// two color differences.
+    ///
+    /// The digital standards scale the differences into `[-0.5, 0.5]`. The
+    /// analog PAL factors instead keep the composite signal of saturated
+    /// colors within `[-1/3, 4/3]` of the luma range, which exactly solved
+    /// gives `0.492111` and `0.877283`. BT.470 publishes `0.493` and
+    /// `0.877`, though the first doesn't round from the exact value.
+    pub fn chroma_scales(self) -> [f32; 2] {
+        let [kr, _, kb] = self.luma_coefficients();
+        match self {
+            Differencing::Bt470MPal => [0.493, 0.877],
+            Differencing::Bt470MPalPrecise => [0.492_111, 0.877_283],
+            _ => [0.5 / (1.0 - kb), 0.5 / (1.0 - kr)],
+        }
+    }
+
+    /// Returns the matrix taking non-linear `[R', G', B']` to `[Y', Cb, Cr]`
+    /// with `Y'` in `[0, 1]` and the color differences scaled by
+    /// [`Self::chroma_scales`], into `[-0.5, 0.5]` for the digital standards.
+    pub fn rgb_to_yuv_matrix(self) -> RowMatrix {
+        let [kr, kg, kb] = self.luma_coefficients();
+        let [cb, cr] = self.chroma_scales();
+
+        RowMatrix([
+            [kr, kg, kb],
+            [-kr * cb, -kg * cb, (1.0 - kb) * cb],
+            [(1.0 - kr) * cr, -kg * cr, -kb * cr],
+        ])
+    }
+
+    /// Returns the matrix taking `[Y', Cb, Cr]` back to non-linear
+    /// `[R', G', B']`.
+    pub fn yuv_to_rgb_matrix(self) -> RowMatrix {
+        self.rgb_to_yuv_matrix().inv()
+    }
+}
+
+/// Returns the largest error in any non-linear RGB channel, in `[0, 1]`,
+/// when Y'CbCr encoded with `actual` is decoded as if it were `assumed`.
+///
+/// Decoding BT.709 video with BT.601 coefficients, or the reverse, is a
+/// common mistake that leaves grays untouched but shifts saturated colors,
+/// most visibly turning greens yellower or bluer and skin tones toward
+/// orange or purple. The error is linear in the color, so this is the error
+/// at the worst corner of the RGB cube, and is zero when the two match.
+pub fn yuv_coefficient_mismatch_delta(actual: Differencing, assumed: Differencing) -> f32 {
+    if actual.rgb_to_yuv_matrix() == assumed.rgb_to_yuv_matrix() {
+        return 0.0;
+    }
+
+    let error = assumed.yuv_to_rgb_matrix() * actual.rgb_to_yuv_matrix();
+
+    error
+        .0
+        .iter()
+        .zip(RowMatrix::IDENTITY.0)
+        .map(|(row, identity)| {
+            // Each channel is most wrong at the corner that includes every
+            // primary pushing it the same way.
+            let (over, under) = row
+                .iter()
+                .zip(identity)
+                .map(|(e, i)| e - i)
+                .fold((0.0_f32, 0.0_f32), |(over, under), d| {
+                    (over + d.max(0.0), under + d.min(0.0))
+                });
+            over.max(-under)
+        })
+        .fold(0.0, f32::max)
+}
+
+/// A Y'CbCr color space, an RGB color space whose encoded values are
+/// differenced into luma and chroma.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YuvColorSpace {
+    rgb: RgbColorSpace,
+    differencing: Differencing,
+}
+
+/// An error constructing a [`YuvColorSpace`] from the primaries of one BT.601
+/// line standard and the differencing of the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MismatchedDifferencing {
+    pub primaries: Primaries,
+    pub differencing: Differencing,
+}
+
+impl fmt::Display for MismatchedDifferencing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} differencing does not belong with {:?} primaries",
+            self.differencing, self.primaries
+        )
+    }
+}
+
+impl core::error::Error for MismatchedDifferencing {}
+
+impl YuvColorSpace {
+    pub const BT601_525: YuvColorSpace = YuvColorSpace {
+        rgb: RgbColorSpace::BT601_525,
+        differencing: Differencing::Bt601_525,
+    };
+    pub const BT601_625: YuvColorSpace = YuvColorSpace {
+        rgb: RgbColorSpace::BT601_625,
+        differencing: Differencing::Bt601_625,
+    };
+    pub const BT709: YuvColorSpace = YuvColorSpace {
+        rgb: RgbColorSpace::BT709,
+        differencing: Differencing::Bt709,
+    };
+    pub const BT2020: YuvColorSpace = YuvColorSpace {
+        rgb: RgbColorSpace::BT2020,
+        differencing: Differencing::Bt2020,
+    };
+
+    /// Creates a Y'CbCr space differencing `rgb` with `differencing`.
+    ///
+    /// Mixing standards is common, JPEG pairs BT.601 differencing with sRGB
+    /// for example, but pairing the primaries of one BT.601 line standard
+    /// with the differencing of the other is always a mistake and is
+    /// rejected.
+    pub fn new(
+        rgb: RgbColorSpace,
+        differencing: Differencing,
+    ) -> Result<YuvColorSpace, MismatchedDifferencing> {
+        let line_standards = [Primaries::Bt601_525, Primaries::Bt601_625];
+        if line_standards.contains(&rgb.primaries)
+            && line_standards.contains(&differencing.primaries())
+            && rgb.primaries != differencing.primaries()
+        {
+            return Err(MismatchedDifferencing {
+                primaries: rgb.primaries,
+                differencing,
+            });
+        }
+
+        Ok(YuvColorSpace { rgb, differencing })
+    }
+
+    pub fn rgb(&self) -> &RgbColorSpace {
+        &self.rgb
+    }
+
+    pub fn differencing(&self) -> Differencing {
+        self.differencing
+    }
+}
+
+/// A stored Y'CbCr pixel, with luma and alpha as `L` and the color
+/// differences as `C`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YuvSample<L, C> {
+    pub y: L,
+    pub cb: C,
+    pub cr: C,
+    pub alpha: L,
+}
+
+/// Stores the `[Y', Cb, Cr, alpha]` pixel `yuv`.
+///
+/// Color differences are offset by [`ChannelEncoding::CHROMA_OFFSET`], so
+/// neutral gray is the middle code of unsigned types, the offset binary of
+/// most formats, and exactly zero in signed types such as `i8`.
+pub fn quantize_yuv<L: ChannelEncoding, C: ChannelEncoding>(
+    yuv: [f32; 4],
+    rounding: Rounding,
+) -> YuvSample<L, C> {
+    let [y, cb, cr, alpha] = yuv;
+    let offset = C::CHROMA_OFFSET;
+
+    YuvSample {
+        y: L::from_normalized(y, rounding),
+        cb: C::from_normalized(cb + offset, rounding),
+        cr: C::from_normalized(cr + offset, rounding),
+        alpha: L::from_normalized(alpha, rounding),
+    }
+}
+
+/// Returns the `[Y', Cb, Cr, alpha]` pixel stored in `sample`, inverting
+/// [`quantize_yuv`].
+pub fn dequantize_yuv<L: ChannelEncoding, C: ChannelEncoding>(sample: YuvSample<L, C>) -> [f32; 4] {
+    let offset = C::CHROMA_OFFSET;
+
+    [
+        sample.y.to_normalized(),
+        sample.cb.to_normalized() - offset,
+        sample.cr.to_normalized() - offset,
+        sample.alpha.to_normalized(),
+    ]
+}
+
+/// Where the chroma samples of 4:2:0 video sit relative to its luma samples.
+///
+/// Each chroma sample covers a 2x2 block of luma samples, and upsampling
+/// with the wrong siting shifts every color edge by half a luma sample
+/// against the luma edge it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaSiting {
+    /// On the left luma column of each block and halfway between its rows,
+    /// as in MPEG-2, H.264, HEVC and most other 4:2:0 video.
+    #[default]
+    Left,
+    /// In the middle of each block, as in MPEG-1 and JPEG.
+    Center,
+    /// On the top left luma sample of each block, as in BT.2020 and BT.2100
+    /// video.
+    CoSited,
+}
+
+impl ChromaSiting {
+    /// Returns where the first chroma sample sits, in luma samples across
+    /// and down.
+    fn offset(self) -> [f32; 2] {
+        match self {
+            ChromaSiting::Left => [0.0, 0.5],
+            ChromaSiting::Center => [0.5, 0.5],
+            ChromaSiting::CoSited => [0.0, 0.0],
+        }
+    }
+}
+
+/// Returns the width and height of the 4:2:0 chroma plane of a `width` by
+/// `height` image, rounding up for odd sizes.
+pub fn chroma_420_size(width: usize, height: usize) -> (usize, usize) {
+    (width.div_ceil(2), height.div_ceil(2))
+}
+
+/// Returns the filter taps, as offsets from the first luma sample under a
+/// chroma sample and weights, averaging the luma resolution samples around a
+/// chroma sample `offset` luma samples further on.
+fn downsample_taps(offset: f32) -> &'static [(isize, f32)] {
+    if offset == 0.0 {
+        &[(-1, 0.25), (0, 0.5), (1, 0.25)]
+    } else {
+        &[(0, 0.5), (1, 0.5)]
+    }
+}
+
+/// Downsamples a `width` by `height` plane of one color difference channel,
+/// stored row by row, to 4:2:0 by averaging the samples around each chroma
+/// sample's position under `siting`. Samples beyond the edges repeat the
+/// outermost ones.
+///
+/// # Panics
+///
+/// Panics if `plane` doesn't hold `width * height` samples.
+pub fn downsample_chroma_420(
+    plane: &[f32],
+    width: usize,
+    height: usize,
+    siting: ChromaSiting,
+) -> Vec<f32> {
+    assert_eq!(
+        plane.len(),
+        width * height,
+        "a {width}x{height} plane must have {} samples",
+        width * height
+    );
+
+    let (chroma_width, chroma_height) = chroma_420_size(width, height);
+    let [taps_x, taps_y] = siting.offset().map(downsample_taps);
+    let clamp = |i: isize, len: usize| i.clamp(0, len as isize - 1) as usize;
+
+    let mut chroma = Vec::with_capacity(chroma_width * chroma_height);
+    for j in 0..chroma_height {
+        for i in 0..chroma_width {
+            let mut sum = 0.0;
+            for &(dy, wy) in taps_y {
+                let y = clamp(2 * j as isize + dy, height);
+                for &(dx, wx) in taps_x {
+                    let x = clamp(2 * i as isize + dx, width);
+                    sum += plane[y * width + x] * wx * wy;
+                }
+            }
+            chroma.push(sum);
+        }
+    }
+    chroma
+}
+
+/// Upsamples a 4:2:0 plane of one color difference channel, of the size
+/// [`chroma_420_size`] gives and stored row by row, to `width` by `height` by
+/// interpolating bilinearly between the chroma samples at their positions
+/// under `siting`. Beyond the outermost samples the nearest is repeated.
+///
+/// # Panics
+///
+/// Panics if `plane` isn't the size of the chroma plane of a `width` by
+/// `height` image.
+pub fn upsample_chroma_420(
+    plane: &[f32],
+    width: usize,
+    height: usize,
+    siting: ChromaSiting,
+) -> Vec<f32> {
+    let (chroma_width, chroma_height) = chroma_420_size(width, height);
+    assert_eq!(
+        plane.len(),
+        chroma_width * chroma_height,
+        "the chroma of a {width}x{height} image must have {chroma_width}x{chroma_height} samples"
+    );
+
+    // Returns the chroma samples either side of luma sample `x` and the
+    // weight of the second.
+    let neighbors = |x: usize, offset: f32, len: usize| {
+        let position = ((x as f32 - offset) / 2.0).clamp(0.0, (len - 1) as f32);
+        let i = (position as usize).min(len.saturating_sub(2));
+        (i, (i + 1).min(len - 1), position - i as f32)
+    };
+
+    let [offset_x, offset_y] = siting.offset();
+    let at = |x: usize, y: usize| plane[y * chroma_width + x];
+    let mut upsampled = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (y0, y1, ty) = neighbors(y, offset_y, chroma_height);
+        for x in 0..width {
+            let (x0, x1, tx) = neighbors(x, offset_x, chroma_width);
+            let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+            let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+            upsampled.push(top + (bottom - top) * ty);
+        }
+    }
+    upsampled
+}
+
+/// A Y'CbCr image with alpha (YUVA), stored as one plane per channel row by
+/// row.
+///
+/// Alpha is kept at full resolution beside luma, even when the color
+/// differences are subsampled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YuvaPlanes {
+    pub width: usize,
+    pub height: usize,
+    pub y: Vec<f32>,
+    /// The color difference planes, of the size [`chroma_420_size`] gives
+    /// when `siting` is set and of the full image size otherwise.
+    pub cb: Vec<f32>,
+    pub cr: Vec<f32>,
+    pub alpha: Vec<f32>,
+    /// Where the chroma samples sit when they are subsampled to 4:2:0, or
+    /// `None` for 4:4:4.
+    pub siting: Option<ChromaSiting>,
+}
+
+impl YuvaPlanes {
+    /// Converts the `width` by `height` RGBA `image` in `from` into `to`,
+    /// subsampling the color differences to 4:2:0 when `siting` is set.
+    ///
+    /// Alpha passes through as it is, never differenced or transfer
+    /// applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `image` doesn't hold `width * height` pixels.
+    pub fn from_rgba(
+        image: &[[f32; 4]],
+        width: usize,
+        height: usize,
+        from: &ColorSpace,
+        to: &YuvColorSpace,
+        siting: Option<ChromaSiting>,
+    ) -> YuvaPlanes {
+        assert_eq!(
+            image.len(),
+            width * height,
+            "a {width}x{height} image must have {} pixels",
+            width * height
+        );
+
+        let [y, mut cb, mut cr, alpha] = convert_to_planar(image, from, &ColorSpace::Yuv(*to));
+        if let Some(siting) = siting {
+            cb = downsample_chroma_420(&cb, width, height, siting);
+            cr = downsample_chroma_420(&cr, width, height, siting);
+        }
+        YuvaPlanes {
+            width,
+            height,
+            y,
+            cb,
+            cr,
+            alpha,
+            siting,
+        }
+    }
+
+    /// Converts the planes from `from` to RGBA pixels in `to`, upsampling
+    /// subsampled color differences first. Alpha is returned as it is
+    /// stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the planes aren't the sizes [`YuvaPlanes`] describes.
+    pub fn to_rgba(&self, from: &YuvColorSpace, to: &ColorSpace) -> Vec<[f32; 4]> {
+        let len = self.width * self.height;
+        assert!(
+            self.y.len() == len && self.alpha.len() == len,
+            "the luma and alpha of a {}x{} image must have {len} samples",
+            self.width,
+            self.height
+        );
+
+        let upsample = |plane: &[f32]| match self.siting {
+            Some(siting) => upsample_chroma_420(plane, self.width, self.height, siting),
+            None => {
+                assert_eq!(plane.len(), len, "4:4:4 chroma must have {len} samples");
+                plane.to_vec()
+            }
+        };
+        let (cb, cr) = (upsample(&self.cb), upsample(&self.cr));
+
+        let mut image: Vec<[f32; 4]> = (0..len)
+            .map(|i| [self.y[i], cb[i], cr[i], self.alpha[i]])
+            .collect();
+        ConversionPlan::new(&ColorSpace::Yuv(*from), to).apply_slice(&mut image);
+        image
+    }
+}
+
+/// Converts 8-bit RGB to YCgCo-R as `[Y, Cg, Co]` with the lifting scheme of
+/// H.264 FRExt, which [`ycgco_r_to_rgb_pixel`] inverts exactly.
+///
+/// Unlike Y'CbCr this is lossless in integers, which makes it the usual
+/// choice of lossless codecs. Luma stays in `[0, 255]` but the color
+/// differences need a ninth bit for `[-255, 255]`.
+pub fn rgb_to_ycgco_r_pixel([r, g, b]: [u8; 3]) -> [i16; 3] {
+    let [r, g, b] = [r, g, b].map(i16::from);
+    let co = r - b;
+    let t = b + (co >> 1);
+    let cg = g - t;
+    [t + (cg >> 1), cg, co]
+}
+
+/// Converts YCgCo-R as `[Y, Cg, Co]` back to 8-bit RGB, inverting
+/// [`rgb_to_ycgco_r_pixel`]. Values no RGB converts to may leave `[0, 255]`
+/// and are clamped.
+pub fn ycgco_r_to_rgb_pixel([y, cg, co]: [i16; 3]) -> [u8; 3] {
+    let t = y - (cg >> 1);
+    let g = cg + t;
+    let b = t - (co >> 1);
+    let r = b + co;
+    [r, g, b].map(|c| c.clamp(0, 255) as u8)
+}
+
+/// Converts each pixel of `image` to YCgCo-R with [`rgb_to_ycgco_r_pixel`],
+/// writing it to the same position in `out`.
+///
+/// # Panics
+///
+/// Panics if `image` and `out` have different lengths.
+pub fn rgb_to_ycgco_r(image: &[[u8; 3]], out: &mut [[i16; 3]]) {
+    assert_eq!(
+        image.len(),
+        out.len(),
+        "the converted image must be the same length as the input"
+    );
+
+    for (pixel, out) in image.iter().zip(out) {
+        *out = rgb_to_ycgco_r_pixel(*pixel);
+    }
+}
+
+/// Converts each YCgCo-R pixel of `image` back to RGB with
+/// [`ycgco_r_to_rgb_pixel`], writing it to the same position in `out`.
+///
+/// # Panics
+///
+/// Panics if `image` and `out` have different lengths.
+pub fn ycgco_r_to_rgb(image: &[[i16; 3]], out: &mut [[u8; 3]]) {
+    assert_eq!(
+        image.len(),
+        out.len(),
+        "the converted image must be the same length as the input"
+    );
+
+    for (pixel, out) in image.iter().zip(out) {
+        *out = ycgco_r_to_rgb_pixel(*pixel);
+    }
+}
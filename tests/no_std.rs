@@ -0,0 +1,32 @@
+//! Checks that the scalar conversions work with `libm` in place of `std`.
+#![cfg(not(feature = "std"))]
+
+use pixel_convert::{
+    color_space::{convert, ColorSpace},
+    diagnostics::self_test,
+    rgb_color_space::RgbColorSpace,
+    transfer::Transfer,
+};
+
+#[test]
+fn srgb_round_trips_through_oklch() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let pixel = [0.2, 0.5, 0.8, 0.5];
+    let oklch = convert(pixel, &srgb, &ColorSpace::Oklch);
+    let back = convert(oklch, &ColorSpace::Oklch, &srgb);
+
+    for (a, e) in back.iter().zip(pixel) {
+        assert!((a - e).abs() <= 1e-4, "{back:?} differs from {pixel:?}");
+    }
+}
+
+#[test]
+fn transfers_match_known_values() {
+    let [linear, ..] = Transfer::Srgb.to_optical_display([0.5, 0.5, 0.5, 1.0]);
+    assert!((linear - 0.214_041_14).abs() <= 1e-6, "{linear}");
+}
+
+#[test]
+fn self_test_passes_with_libm() {
+    assert_eq!(self_test(), Ok(()));
+}
@@ -0,0 +1,3074 @@
+//! Tests of the crate's public API, from property tests that conversions and
+//! transfers invert each other to checks against published values.
+
+use pixel_convert::{
+    accessibility::{apca_contrast, simulate_cvd, wcag_contrast, CvdKind, CvdSimulation},
+    adaptation::{adapt_slice, adapt_xyz, ChromaticAdaptation, ConeResponse},
+    adjust::{rotate_hue, saturate},
+    blend::{blend, matte, source_over, unmatte, BlendMode, UNMATTE_MIN_ALPHA},
+    bytes::{convert_bytes, ChannelFormat, ChannelOrder, Endian, Interleaving, PixelLayout},
+    channel::{quantize_pixel, u16_to_u8, BitDepth, Rounding, SignalRange},
+    color_space::{
+        cielab_to_oklab, cielab_to_xyz, convert, convert_checked, convert_luminance, convert_via,
+        convert_with_intent, convert_with_options, encode_linear, oklab_to_cielab, rescale_xyz,
+        to_linear, try_convert, xyz_to_cielab, xyz_to_oklab_with_cones, ColorSpace, Luminance,
+        YScale,
+    },
+    descriptor::DescriptorError,
+    diagnostics::{
+        detect_clipping, estimate_encoded_state, jnd_step, plot_gamuts_svg, self_test,
+        EncodedState, EncodingGuess,
+    },
+    display::{DisplayTransform, ToneMap},
+    gamut::{
+        compress_to_gamut, desaturate_highlights, gamut_area, gamut_coverage, gamut_encloses,
+        max_chroma, recommend_working_space,
+    },
+    hue::{hue_add, hue_distance, hue_lerp},
+    icc::{IccError, IccProfile},
+    interpolate::{gradient, mix, HueInterpolation},
+    lut::{bt2087_lut, BT2087_MATRIX},
+    matrix::RowMatrix,
+    palette::{nearest_in_palette, quantize, PaletteIndex},
+    plan::{
+        approx_cbrt, convert_cached, convert_image_generic, convert_iter, convert_to_planar,
+        AlphaMode, ConversionOptions, ConversionPlan, ConvertError, DomainError, PlanCache,
+        RenderingIntent, Step, APPROX_CBRT_ERROR, OKLAB_APPROX_ERROR,
+    },
+    png::{Cicp, PngColorChunks, PngColorError},
+    primaries::Primaries,
+    reference::{convert_reference, precision_report, PrecisionReport},
+    rgb_color_space::{convert_linear_rgb, RgbColorSpace},
+    spectral::{
+        dominant_wavelength, excitation_purity, spectrum_to_xyz, DominantWavelength, Observer,
+    },
+    swizzle::{swizzle, ChannelIndex, IDENTITY},
+    temperature::{blackbody_srgb, cct_from_xy, white_balance},
+    transfer::{
+        apply_transfer_channel, apply_transfer_channel_in_domain, bt2390_eetf, invert_eotf,
+        transfer_eo_srgb, transfer_oe_srgb, ArriExposureIndex, CustomCurve, CustomTransfer,
+        Direction, LightDomain, Transfer, TransferParams, ACESCCT_X_BRK, ACESCCT_Y_BRK,
+        SRGB_ENCODED_KNEE, SRGB_LINEAR_KNEE,
+    },
+    whitepoint::Whitepoint,
+    yuv::{
+        dequantize_yuv, quantize_yuv, rgb_to_ycgco_r, ycgco_r_to_rgb,
+        yuv_coefficient_mismatch_delta, ChromaSiting, Differencing, YuvColorSpace, YuvSample,
+        YuvaPlanes,
+    },
+};
+use proptest::{
+    array::{uniform2, uniform3, uniform4},
+    prelude::*,
+    sample::select,
+};
+
+/// The largest difference allowed after a transfer round trip. The log curves
+/// lose a few ulps through `log2`/`exp2` so this is a little above `f32`
+/// epsilon.
+const TRANSFER_TOLERANCE: f32 = 1e-5;
+
+/// The largest difference allowed after a conversion round trip, which
+/// accumulates error from two transfers, several matrices and any
+/// non-linear steps in between.
+const CONVERT_TOLERANCE: f32 = 1e-4;
+
+/// The largest difference allowed between a conversion and the `f64`
+/// reference, which is only the rounding error of `f32` arithmetic.
+const REFERENCE_TOLERANCE: f32 = 1e-5;
+
+/// A plain power law standing in for a user's own curve.
+#[derive(Debug)]
+struct Gamma3;
+
+impl CustomTransfer for Gamma3 {
+    fn eotf(&self, v: f32) -> f32 {
+        v.max(0.0).powf(3.0)
+    }
+
+    fn oetf(&self, v: f32) -> f32 {
+        v.max(0.0).cbrt()
+    }
+}
+
+/// A display curve whose black is raised to 1% of white, as with a display
+/// that leaks light.
+#[derive(Debug)]
+struct LiftedBlack;
+
+impl CustomTransfer for LiftedBlack {
+    fn eotf(&self, v: f32) -> f32 {
+        0.01 + 0.99 * v.max(0.0).powf(2.2)
+    }
+
+    fn oetf(&self, v: f32) -> f32 {
+        ((v - 0.01) / 0.99).max(0.0).powf(1.0 / 2.2)
+    }
+}
+
+const LIFTED_SRGB: ColorSpace = ColorSpace::Rgb(RgbColorSpace {
+    transfer: Transfer::Custom(CustomCurve(&LiftedBlack)),
+    ..RgbColorSpace::SRGB
+});
+
+/// Spaces relative to D65 with a black of zero.
+const D65_SPACES: [ColorSpace; 3] = [
+    ColorSpace::Rgb(RgbColorSpace::SRGB),
+    ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3),
+    ColorSpace::Rgb(RgbColorSpace::BT2020),
+];
+
+/// Each reference white with the luminance it is documented to have.
+const LUMINANCES: [(Luminance, f32); 3] = [
+    (Luminance::Sdr, 100.0),
+    (Luminance::AdobeRgb, 160.0),
+    (Luminance::DciP3, 1000.0),
+];
+
+const TRANSFERS: [Transfer; 13] = [
+    Transfer::Linear,
+    Transfer::Srgb,
+    Transfer::SrgbExtended,
+    Transfer::Dci,
+    Transfer::Bt709,
+    Transfer::AcesCc,
+    Transfer::AcesCct,
+    Transfer::LogC3(ArriExposureIndex::Ei160),
+    Transfer::LogC3(ArriExposureIndex::Ei800),
+    Transfer::SLog3,
+    Transfer::Pq,
+    Transfer::Hlg,
+    Transfer::Custom(CustomCurve(&Gamma3)),
+];
+
+const SPACES: [ColorSpace; 14] = [
+    ColorSpace::Rgb(RgbColorSpace::SRGB),
+    ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB),
+    ColorSpace::Rgb(RgbColorSpace::BT709),
+    ColorSpace::Rgb(RgbColorSpace::BT2020),
+    ColorSpace::Rgb(RgbColorSpace::DCI_P3),
+    ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3),
+    ColorSpace::Hwb(RgbColorSpace::SRGB),
+    ColorSpace::Xyz(Whitepoint::D50),
+    ColorSpace::Xyy(Whitepoint::D65),
+    ColorSpace::CieLab(Whitepoint::D50),
+    ColorSpace::Oklab,
+    ColorSpace::Oklch,
+    ColorSpace::Okhsl,
+    ColorSpace::Okhsv,
+];
+
+const PRIMARIES: [Primaries; 9] = [
+    Primaries::Bt601_525,
+    Primaries::Bt601_625,
+    Primaries::Bt709,
+    Primaries::Bt2020,
+    Primaries::DciP3,
+    Primaries::AdobeRgb,
+    Primaries::AcesAp0,
+    Primaries::AcesAp1,
+    Primaries::CieRgb,
+];
+
+/// Pixels whose channels, including alpha, are anywhere in `[0, 1]`.
+fn unit_pixel() -> impl Strategy<Value = [f32; 4]> {
+    uniform4(0.0_f32..=1.0)
+}
+
+fn assert_close(actual: [f32; 4], expected: [f32; 4], tolerance: f32) -> Result<(), TestCaseError> {
+    for (a, e) in actual.iter().zip(expected) {
+        prop_assert!(
+            (a - e).abs() <= tolerance,
+            "{actual:?} differs from {expected:?} by more than {tolerance}"
+        );
+    }
+    Ok(())
+}
+
+/// Asserts that `actual` is within `tolerance` of `expected`, for checking
+/// published values outside of `proptest!`.
+fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{actual} differs from {expected} by more than {tolerance}"
+    );
+}
+
+/// A small image of varied colors and alphas, for checking conversions of
+/// whole images outside of `proptest!`.
+fn sample_image() -> Vec<[f32; 4]> {
+    (0..64)
+        .map(|i| {
+            let t = i as f32 / 63.0;
+            [t, 1.0 - t, (t * 7.0).fract(), (t * 3.0).fract()]
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn transfer_round_trips(pixel in unit_pixel(), transfer in select(TRANSFERS.to_vec())) {
+        let decoded = transfer.to_optical_display(pixel);
+        assert_close(transfer.from_optical_display(decoded), pixel, TRANSFER_TOLERANCE)?;
+    }
+
+    #[test]
+    fn transfer_preserves_alpha(
+        pixel in unit_pixel(),
+        transfer in select(TRANSFERS.to_vec()),
+    ) {
+        let alpha = pixel[3].to_bits();
+        prop_assert_eq!(transfer.to_optical_display(pixel)[3].to_bits(), alpha);
+        prop_assert_eq!(transfer.from_optical_display(pixel)[3].to_bits(), alpha);
+
+        let mut pixels = [pixel];
+        transfer.to_optical_pixels(&mut pixels);
+        prop_assert_eq!(pixels[0][3].to_bits(), alpha);
+        transfer.from_optical_pixels(&mut pixels);
+        prop_assert_eq!(pixels[0][3].to_bits(), alpha);
+    }
+
+    #[test]
+    fn convert_round_trips(
+        pixel in unit_pixel(),
+        to in select(SPACES.to_vec()),
+    ) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let there = convert(pixel, &srgb, &to);
+        assert_close(convert(there, &to, &srgb), pixel, CONVERT_TOLERANCE)?;
+    }
+
+    #[test]
+    fn encode_linear_round_trips(
+        pixel in unit_pixel(),
+        space in select(SPACES.to_vec()),
+    ) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let encoded = convert(pixel, &srgb, &space);
+        let linear = to_linear(encoded, &space);
+        let back = convert(encode_linear(linear, &space), &space, &srgb);
+        assert_close(back, pixel, CONVERT_TOLERANCE)?;
+    }
+
+    #[test]
+    fn convert_matches_reference(pixel in unit_pixel()) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let bt2020 = ColorSpace::Rgb(RgbColorSpace::BT2020);
+
+        let there = convert(pixel, &srgb, &bt2020);
+        let reference = convert_reference(pixel.map(f64::from), &srgb, &bt2020);
+        assert_close(there, reference.map(|c| c as f32), REFERENCE_TOLERANCE)?;
+
+        let back = convert(there, &bt2020, &srgb);
+        let reference = convert_reference(reference, &bt2020, &srgb);
+        assert_close(back, reference.map(|c| c as f32), REFERENCE_TOLERANCE)?;
+    }
+
+    #[test]
+    fn convert_preserves_alpha(
+        pixel in unit_pixel(),
+        from in select(SPACES.to_vec()),
+        to in select(SPACES.to_vec()),
+    ) {
+        prop_assert_eq!(convert(pixel, &from, &to)[3].to_bits(), pixel[3].to_bits());
+    }
+
+    #[test]
+    fn swizzle_round_trips(pixel in unit_pixel()) {
+        use ChannelIndex::*;
+
+        let mut out = [[0.0; 4]];
+        swizzle(&[pixel], IDENTITY, &mut out);
+        prop_assert_eq!(out[0], pixel);
+
+        let [r, g, b, a] = pixel;
+        swizzle(&[pixel], [B, G, R, A], &mut out);
+        prop_assert_eq!(out[0], [b, g, r, a]);
+        swizzle(&out.clone(), [B, G, R, A], &mut out);
+        prop_assert_eq!(out[0], pixel);
+
+        swizzle(&[pixel], [R, G, B, One], &mut out);
+        prop_assert_eq!(out[0], [r, g, b, 1.0]);
+    }
+
+    #[test]
+    fn u16_to_u8_rounds_to_nearest(v in any::<u16>()) {
+        prop_assert_eq!(u16_to_u8(v), (f32::from(v) / 257.0).round() as u8);
+    }
+
+    #[test]
+    fn unmatte_inverts_matte(
+        [r, g, b, _] in unit_pixel(),
+        alpha in UNMATTE_MIN_ALPHA..=1.0,
+        background in uniform3(0.0_f32..=1.0),
+    ) {
+        // Removing the background divides by alpha, magnifying rounding
+        // error without bound as alpha approaches zero.
+        let srgb = RgbColorSpace::SRGB;
+        let pixel = [r, g, b, alpha];
+        let composited = matte(pixel, background, &srgb);
+        let tolerance = TRANSFER_TOLERANCE / alpha;
+        assert_close(unmatte(composited, background, &srgb), pixel, tolerance)?;
+    }
+
+    #[test]
+    fn source_over_opaque_top_covers_bottom(
+        [r, g, b, _] in unit_pixel(),
+        bottom in unit_pixel(),
+    ) {
+        let top = [r, g, b, 1.0];
+        let composited = source_over(top, bottom, &RgbColorSpace::SRGB);
+        assert_close(composited, top, TRANSFER_TOLERANCE)?;
+    }
+
+    #[test]
+    fn palette_index_matches_linear_search(
+        pixel in unit_pixel(),
+        palette in prop::collection::vec(unit_pixel(), 1..32),
+        space in select(vec![ColorSpace::Rgb(RgbColorSpace::SRGB), ColorSpace::Oklab]),
+    ) {
+        let index = PaletteIndex::new(&palette, &space);
+        prop_assert_eq!(index.nearest(pixel), nearest_in_palette(pixel, &palette, &space));
+    }
+
+    #[test]
+    fn ycgco_r_round_trips_exactly(
+        image in prop::collection::vec(uniform3(any::<u8>()), 0..64),
+    ) {
+        let mut ycgco = vec![[0; 3]; image.len()];
+        rgb_to_ycgco_r(&image, &mut ycgco);
+        let mut rgb = vec![[0; 3]; image.len()];
+        ycgco_r_to_rgb(&ycgco, &mut rgb);
+        prop_assert_eq!(rgb, image);
+    }
+
+    #[test]
+    fn black_point_compensation_off_keeps_luminance(
+        pixel in unit_pixel(),
+        to in select(D65_SPACES.to_vec()),
+    ) {
+        let options = ConversionOptions::default();
+        let converted = convert_with_options(pixel, &LIFTED_SRGB, &to, options);
+        let xyz = ColorSpace::Xyz(Whitepoint::D65);
+        let expected = convert(pixel, &LIFTED_SRGB, &xyz)[1];
+        let actual = convert(converted, &to, &xyz)[1];
+        prop_assert!((actual - expected).abs() <= CONVERT_TOLERANCE, "{actual} is not {expected}");
+    }
+
+    #[test]
+    fn luminance_scales_linear_values(
+        pixel in unit_pixel(),
+        (from, from_nits) in select(LUMINANCES.to_vec()),
+        (to, to_nits) in select(LUMINANCES.to_vec()),
+    ) {
+        prop_assert_eq!(from.to_nits(), from_nits);
+        prop_assert_eq!(to.to_nits(), to_nits);
+
+        let options = ConversionOptions {
+            source_luminance: from,
+            destination_luminance: to,
+            ..ConversionOptions::default()
+        };
+        let linear = ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB);
+        let converted = convert_with_options(pixel, &linear, &linear, options);
+        let [r, g, b, alpha] = pixel;
+        let scale = from_nits / to_nits;
+        assert_close(converted, [r * scale, g * scale, b * scale, alpha], CONVERT_TOLERANCE)?;
+    }
+
+    #[test]
+    fn display_transform_applies_stages_in_order(
+        pixel in uniform4(0.0_f32..=16.0),
+        exposure in -4.0_f32..=4.0,
+    ) {
+        let transform = DisplayTransform {
+            exposure,
+            ..DisplayTransform::default()
+        };
+        let [r, g, b, alpha] = pixel;
+        let gain = exposure.exp2();
+        let exposed = [r * gain, g * gain, b * gain, alpha];
+        let expected = convert_with_intent(
+            ToneMap::Reinhard.apply(exposed),
+            &ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB),
+            &ColorSpace::Rgb(RgbColorSpace::SRGB),
+            RenderingIntent::Saturation,
+        );
+        assert_close(transform.apply(pixel), expected, TRANSFER_TOLERANCE)?;
+
+        let mut slice = [pixel];
+        transform.apply_slice(&mut slice);
+        prop_assert_eq!(slice[0], transform.apply(pixel));
+    }
+
+    #[test]
+    fn absolute_intent_keeps_the_source_white_tint(gray in 0.01_f32..=1.0) {
+        let d50 = ColorSpace::Rgb(RgbColorSpace {
+            whitepoint: Whitepoint::D50,
+            ..RgbColorSpace::LINEAR_SRGB
+        });
+        let d65 = ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB);
+        let pixel = [gray, gray, gray, 1.0];
+
+        let intent = RenderingIntent::RelativeColorimetric;
+        let relative = convert_with_intent(pixel, &d50, &d65, intent);
+        assert_close(relative, pixel, CONVERT_TOLERANCE)?;
+
+        let [r, g, b, _] = convert_with_intent(pixel, &d50, &d65, RenderingIntent::Absolute);
+        prop_assert!(r > g && g > b, "{:?} is not warm", [r, g, b]);
+    }
+
+    #[test]
+    fn bit_depth_normalizes_full_scale_to_one(
+        bits in select(vec![12_u8, 14, 16]),
+        code in any::<u16>(),
+    ) {
+        let depth = BitDepth(bits);
+        let max = depth.max_code();
+        prop_assert_eq!(u32::from(max), (1 << bits) - 1);
+        prop_assert_eq!(depth.to_normalized(max), 1.0);
+        prop_assert_eq!(depth.to_normalized(0), 0.0);
+
+        let code = code & max;
+        let normalized = depth.to_normalized(code);
+        prop_assert!((0.0..=1.0).contains(&normalized));
+        prop_assert_eq!(depth.from_normalized(normalized, Rounding::Nearest), code);
+    }
+
+    #[test]
+    fn planar_conversion_matches_interleaved(
+        image in prop::collection::vec(unit_pixel(), 0..600),
+        to in select(SPACES.to_vec()),
+    ) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let planes = convert_to_planar(&image, &srgb, &to);
+
+        let mut expected = image.clone();
+        ConversionPlan::new(&srgb, &to).apply_slice(&mut expected);
+        let interleaved: Vec<[f32; 4]> = (0..image.len())
+            .map(|i| [planes[0][i], planes[1][i], planes[2][i], planes[3][i]])
+            .collect();
+        prop_assert_eq!(interleaved, expected);
+    }
+
+    #[test]
+    fn jnd_step_grows_with_srgb_brightness(
+        dark in 0.1_f32..=0.2,
+        bright in 0.8_f32..=1.0,
+    ) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let dark_step = jnd_step([dark, dark, dark, 1.0], &srgb);
+        let bright_step = jnd_step([bright, bright, bright, 1.0], &srgb);
+        for c in 0..3 {
+            prop_assert!(dark_step[c] < bright_step[c], "{dark_step:?} vs {bright_step:?}");
+        }
+
+        // One ΔE is a code or two of an 8-bit image.
+        prop_assert!((1.0 / 255.0..=4.0 / 255.0).contains(&dark_step[1]));
+    }
+
+    #[test]
+    fn detect_clipping_finds_known_regions(
+        image in prop::collection::vec(uniform4(0.1_f32..=0.9), 0..64),
+        clipped_from in 0_usize..64,
+        crushed_from in 0_usize..64,
+        channel in 0_usize..3,
+    ) {
+        // The pixels from `clipped_from` on are blown out, and from
+        // `crushed_from` on crushed, so some may be both.
+        let mut image = image;
+        for (i, pixel) in image.iter_mut().enumerate() {
+            if i >= clipped_from {
+                pixel[channel] = 1.0;
+            }
+            if i >= crushed_from {
+                pixel[(channel + 1) % 3] = 0.0;
+            }
+        }
+
+        let clipping = detect_clipping(&image, 0.05);
+        let len = image.len();
+        prop_assert_eq!(clipping.clipped, len.saturating_sub(clipped_from));
+        prop_assert_eq!(clipping.crushed, len.saturating_sub(crushed_from));
+        let expected: Vec<bool> = (0..len).map(|i| i >= clipped_from.min(crushed_from)).collect();
+        prop_assert_eq!(clipping.mask, expected);
+    }
+
+    #[test]
+    fn convert_via_intermediate_round_trips(pixel in unit_pixel()) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let bt2020 = ColorSpace::Rgb(RgbColorSpace::BT2020);
+        let xyz = ColorSpace::Xyz(Whitepoint::D50);
+        assert_close(convert_via(pixel, &srgb, &bt2020, &srgb), pixel, CONVERT_TOLERANCE)?;
+        assert_close(
+            convert_via(pixel, &srgb, &bt2020, &xyz),
+            convert(pixel, &srgb, &xyz),
+            CONVERT_TOLERANCE,
+        )?;
+    }
+
+    #[test]
+    fn equal_energy_white_adapts_to_d65(gray in 0.01_f32..=1.0) {
+        let cie_rgb = ColorSpace::Rgb(RgbColorSpace::CIE_RGB);
+        let linear_srgb = ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB);
+        let matrix = RgbColorSpace::CIE_RGB.to_xyz_matrix();
+        prop_assert!(matrix.0.iter().flatten().all(|c| c.is_finite()));
+        prop_assert!(matrix.condition_number() < 100.0);
+
+        // Adapted, the equal-energy gray is a D65 gray, while unadapted it
+        // keeps the E white's pink tint.
+        let pixel = [gray, gray, gray, 1.0];
+        assert_close(convert(pixel, &cie_rgb, &linear_srgb), pixel, CONVERT_TOLERANCE)?;
+        let intent = RenderingIntent::Absolute;
+        let [r, g, _, _] = convert_with_intent(pixel, &cie_rgb, &linear_srgb, intent);
+        prop_assert!(r > g, "{r} {g}");
+    }
+
+    #[test]
+    fn two_stop_gradient_matches_mix(
+        a in unit_pixel(),
+        b in unit_pixel(),
+        n in 2_usize..32,
+        space in select(SPACES.to_vec()),
+    ) {
+        let hue = HueInterpolation::Shorter;
+        let samples = gradient(&[(0.0, a), (1.0, b)], n, &space, hue);
+        prop_assert_eq!(samples.len(), n);
+        for (i, sample) in samples.into_iter().enumerate() {
+            let t = i as f32 / (n - 1) as f32;
+            assert_close(sample, mix(a, b, t, &space, hue), TRANSFER_TOLERANCE)?;
+        }
+    }
+
+    #[test]
+    fn oklch_gradient_takes_the_chosen_hue_arc(
+        from in 0.0_f32..360.0,
+        delta in 1.0_f32..179.0,
+    ) {
+        let to = (from + delta) % 360.0;
+        let stops = [(0.0, [0.7, 0.1, from, 1.0]), (1.0, [0.7, 0.1, to, 1.0])];
+        let middle = |hue| gradient(&stops, 3, &ColorSpace::Oklch, hue)[1][2];
+
+        // The arc from `from` increasing to `to` is the shorter one, so the
+        // middle hues of the two arcs are half a turn apart.
+        let shorter = (from + delta / 2.0) % 360.0;
+        let longer = (shorter + 180.0) % 360.0;
+        let hue_distance = |a: f32, b: f32| {
+            let d = (a - b).rem_euclid(360.0);
+            d.min(360.0 - d)
+        };
+        prop_assert!(hue_distance(middle(HueInterpolation::Shorter), shorter) < 1e-2);
+        prop_assert!(hue_distance(middle(HueInterpolation::Increasing), shorter) < 1e-2);
+        prop_assert!(hue_distance(middle(HueInterpolation::Longer), longer) < 1e-2);
+        prop_assert!(hue_distance(middle(HueInterpolation::Decreasing), longer) < 1e-2);
+    }
+
+    #[test]
+    fn mpal_precise_differs_from_published(pixel in unit_pixel()) {
+        // Keeping the composite signal of saturated yellow and cyan at most
+        // 4/3 and of red and blue at least -1/3 gives U = 0.492111 (B' - Y')
+        // and V = 0.877283 (R' - Y'), which BT.470 publishes as 0.493 and
+        // 0.877.
+        let published = Differencing::Bt470MPal;
+        let precise = Differencing::Bt470MPalPrecise;
+        prop_assert_eq!(published.luma_coefficients(), precise.luma_coefficients());
+        prop_assert_ne!(published.rgb_to_yuv_matrix().0[1], precise.rgb_to_yuv_matrix().0[1]);
+        prop_assert_ne!(published.rgb_to_yuv_matrix().0[2], precise.rgb_to_yuv_matrix().0[2]);
+
+        let rgb = ColorSpace::Rgb(RgbColorSpace::BT601_525);
+        let yuv = |differencing| {
+            let space = YuvColorSpace::new(RgbColorSpace::BT601_525, differencing).unwrap();
+            convert(pixel, &rgb, &ColorSpace::Yuv(space))
+        };
+        let [y, u, v, _] = yuv(published);
+        let [precise_y, precise_u, precise_v, _] = yuv(precise);
+        prop_assert!((y - precise_y).abs() <= TRANSFER_TOLERANCE);
+        prop_assert!((u * 0.492_111 - precise_u * 0.493).abs() <= TRANSFER_TOLERANCE);
+        prop_assert!((v * 0.877_283 - precise_v * 0.877).abs() <= TRANSFER_TOLERANCE);
+    }
+
+    #[test]
+    fn convert_image_generic_matches_convert(
+        image in prop::collection::vec(unit_pixel(), 0..64),
+        yuv in select(vec![YuvColorSpace::BT601_525, YuvColorSpace::BT709, YuvColorSpace::BT2020]),
+    ) {
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        let conversions = [
+            (ColorSpace::Oklab, srgb),
+            (ColorSpace::Yuv(yuv), ColorSpace::Xyz(Whitepoint::D65)),
+        ];
+        for (from, to) in conversions {
+            // Start from colors that are valid in the source space.
+            let source: Vec<[f32; 4]> =
+                image.iter().map(|pixel| convert(*pixel, &srgb, &from)).collect();
+            let converted = convert_image_generic(&source, &from, &to);
+            prop_assert_eq!(converted.len(), source.len());
+            for (pixel, converted) in source.iter().zip(converted) {
+                assert_close(converted, convert(*pixel, &from, &to), TRANSFER_TOLERANCE)?;
+            }
+        }
+    }
+
+    #[test]
+    fn desaturate_highlights_keeps_hue(
+        [r, g, b] in uniform3(0.0_f32..=1.0),
+        alpha in 0.0_f32..=1.0,
+        gain in 1.0_f32..=4.0,
+    ) {
+        let threshold = 0.8;
+        let max = r.max(g).max(b);
+        prop_assume!(max > 0.1);
+        let linear = ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB);
+
+        // Scaled to within the threshold the color is untouched.
+        let scale = 0.99 * threshold / max;
+        let below = [r * scale, g * scale, b * scale, alpha];
+        prop_assert_eq!(desaturate_highlights(below, threshold, &RgbColorSpace::SRGB), below);
+
+        // Brighter, it is in gamut with less chroma and the same hue.
+        let scale = scale * (1.0 + gain);
+        let bright = [r * scale, g * scale, b * scale, alpha];
+        let desaturated = desaturate_highlights(bright, threshold, &RgbColorSpace::SRGB);
+        prop_assert!(desaturated[..3].iter().all(|c| (0.0..=1.0 + TRANSFER_TOLERANCE).contains(c)));
+        prop_assert_eq!(desaturated[3], alpha);
+
+        let [l, c, h, _] = convert(bright, &linear, &ColorSpace::Oklch);
+        let [_, new_c, new_h, _] = convert(desaturated, &linear, &ColorSpace::Oklch);
+        prop_assert!(new_c <= c + TRANSFER_TOLERANCE);
+        if l < 1.0 && new_c > 1e-2 {
+            let d = (new_h - h).rem_euclid(360.0);
+            prop_assert!(d.min(360.0 - d) < 0.1, "hue {h} became {new_h}");
+        }
+    }
+
+    #[test]
+    fn convert_luminance_matches_nits_ratio(
+        y in 0.0_f32..=1.0,
+        (from, from_nits) in select(LUMINANCES.to_vec()),
+        (to, to_nits) in select(LUMINANCES.to_vec()),
+    ) {
+        let converted = convert_luminance(y, from, to);
+        prop_assert!((converted - y * from_nits / to_nits).abs() <= TRANSFER_TOLERANCE);
+
+        // The luminance of a whole color scales the same way.
+        let options = ConversionOptions {
+            source_luminance: from,
+            destination_luminance: to,
+            ..ConversionOptions::default()
+        };
+        let xyz = ColorSpace::Xyz(Whitepoint::D65);
+        let gray = Whitepoint::D65.to_xyz().map(|c| c * y);
+        let plan = ConversionPlan::with_options(&xyz, &xyz, options);
+        let scaled = plan.apply([gray[0], gray[1], gray[2], 1.0])[1];
+        prop_assert!((scaled - converted).abs() <= TRANSFER_TOLERANCE);
+    }
+
+    #[test]
+    fn yuva_round_trip_preserves_alpha(
+        image in prop::collection::vec(unit_pixel(), 12),
+        yuv in select(vec![YuvColorSpace::BT601_525, YuvColorSpace::BT709, YuvColorSpace::BT2020]),
+        siting in select(vec![
+            None,
+            Some(ChromaSiting::Left),
+            Some(ChromaSiting::Center),
+            Some(ChromaSiting::CoSited),
+        ]),
+    ) {
+        let (width, height) = (4, 3);
+        let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+        // Subsampled chroma only survives where it is smooth, so those
+        // images share one color under their varying alpha.
+        let image: Vec<[f32; 4]> = match siting {
+            None => image,
+            Some(_) => image.iter().map(|&[.., alpha]| {
+                let [r, g, b, _] = image[0];
+                [r, g, b, alpha]
+            }).collect(),
+        };
+
+        let planes = YuvaPlanes::from_rgba(&image, width, height, &srgb, &yuv, siting);
+        prop_assert_eq!(planes.alpha.len(), width * height);
+        let round_trip = planes.to_rgba(&yuv, &srgb);
+        prop_assert_eq!(round_trip.len(), image.len());
+        for (pixel, round_trip) in image.iter().zip(round_trip) {
+            prop_assert_eq!(round_trip[3], pixel[3]);
+            assert_close(round_trip, *pixel, CONVERT_TOLERANCE)?;
+        }
+    }
+
+    #[test]
+    fn hue_arithmetic_wraps(
+        a in -720.0_f32..720.0,
+        b in -720.0_f32..720.0,
+        t in 0.0_f32..=1.0,
+        direction in select(vec![
+            HueInterpolation::Shorter,
+            HueInterpolation::Longer,
+            HueInterpolation::Increasing,
+            HueInterpolation::Decreasing,
+        ]),
+    ) {
+        let in_range = |hue: f32| (0.0..360.0).contains(&hue);
+        let sum = hue_add(a, b);
+        prop_assert!(in_range(sum), "{a} + {b} gave {sum}");
+        prop_assert!(hue_distance(sum, a + b) <= 0.01);
+        let lerp = hue_lerp(a, b, t, direction);
+        prop_assert!(in_range(lerp), "lerp of {a} and {b} gave {lerp}");
+
+        let distance = hue_distance(a, b);
+        prop_assert!((0.0..=180.0).contains(&distance));
+        prop_assert!((distance - hue_distance(b, a)).abs() <= 0.01);
+        // The shorter arc covers the distance at an even pace.
+        let travelled = hue_distance(a, hue_lerp(a, b, t, HueInterpolation::Shorter));
+        prop_assert!(
+            (travelled - t * distance).abs() <= 0.01,
+            "{travelled} is not {t} of {distance}"
+        );
+    }
+
+    #[test]
+    fn transfer_channel_matches_rgba_red(
+        image in prop::collection::vec(unit_pixel(), 0..64),
+        transfer in select(TRANSFERS.to_vec()),
+    ) {
+        let mut decoded = image.clone();
+        Transfer::Srgb.to_optical_pixels(&mut decoded);
+        let mut red: Vec<f32> = image.iter().map(|pixel| pixel[0]).collect();
+        apply_transfer_channel(Transfer::Srgb, &mut red, Direction::Decode);
+        prop_assert_eq!(&red, &decoded.iter().map(|pixel| pixel[0]).collect::<Vec<_>>());
+
+        // HLG's OOTF mixes channels, so in general a lone channel matches
+        // the RGBA path in scene light.
+        let scene = LightDomain::Scene;
+        let mut channel: Vec<f32> = image.iter().map(|pixel| pixel[0]).collect();
+        apply_transfer_channel_in_domain(transfer, &mut channel, Direction::Decode, scene);
+        for (pixel, decoded) in image.iter().zip(&channel) {
+            let linear = transfer.to_optical(scene, *pixel);
+            prop_assert_eq!(*decoded, linear[0]);
+        }
+        apply_transfer_channel_in_domain(transfer, &mut channel, Direction::Encode, scene);
+        for (pixel, encoded) in image.iter().zip(&channel) {
+            let linear = transfer.to_optical(scene, *pixel);
+            prop_assert_eq!(*encoded, transfer.from_optical(scene, linear)[0]);
+        }
+
+        // By default it is in display light, which matches the RGBA path,
+        // OOTF and all, for grays.
+        let hlg = Transfer::Hlg;
+        let grays: Vec<[f32; 4]> = image.iter().map(|&[v, _, _, alpha]| [v, v, v, alpha]).collect();
+        let mut channel: Vec<f32> = grays.iter().map(|pixel| pixel[0]).collect();
+        apply_transfer_channel(hlg, &mut channel, Direction::Decode);
+        for (gray, decoded) in grays.iter().zip(&channel) {
+            prop_assert_eq!(*decoded, hlg.to_optical_display(*gray)[0]);
+        }
+        apply_transfer_channel(hlg, &mut channel, Direction::Encode);
+        for (gray, encoded) in grays.iter().zip(&channel) {
+            prop_assert!((*encoded - gray[0]).abs() <= TRANSFER_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn try_convert_never_panics_for_custom_whites(
+        pixel in unit_pixel(),
+        white in uniform2(prop_oneof![any::<f32>(), -1.0_f32..=1.0, Just(0.0)]),
+    ) {
+        let custom = [
+            ColorSpace::Rgb(RgbColorSpace {
+                whitepoint: Whitepoint::Custom(white),
+                ..RgbColorSpace::SRGB
+            }),
+            ColorSpace::Xyz(Whitepoint::Custom(white)),
+        ];
+        let others = [
+            ColorSpace::Rgb(RgbColorSpace::SRGB),
+            ColorSpace::Xyz(Whitepoint::D65),
+            ColorSpace::Oklab,
+        ];
+        for custom in &custom {
+            for other in &others {
+                for (from, to) in [(custom, other), (other, custom)] {
+                    if let Ok(converted) = try_convert(pixel, from, to) {
+                        prop_assert!(converted.iter().all(|c| c.is_finite()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn aces_log_curves_match_anchor_values() {
+    // 18% gray, where ACEScc and ACEScct agree, from S-2014-003 and
+    // S-2016-001.
+    for transfer in [Transfer::AcesCc, Transfer::AcesCct] {
+        assert_near(transfer.from_optical_scalar(0.18), 0.413_588_4, 1e-6);
+        assert_near(transfer.to_optical_scalar(0.413_588_4), 0.18, 1e-6);
+    }
+    // ACEScc clamps to 2^-16 below zero, ACEScct has a linear toe instead.
+    assert_near(
+        Transfer::AcesCc.from_optical_scalar(0.0),
+        -0.358_447_5,
+        1e-6,
+    );
+    assert_near(
+        Transfer::AcesCct.from_optical_scalar(0.0),
+        0.072_905_53,
+        1e-6,
+    );
+
+    // The toe meets the log curve at the breakpoint in value and slope.
+    let above = ACESCCT_X_BRK * (1.0 + f32::EPSILON);
+    assert_near(
+        Transfer::AcesCct.from_optical_scalar(ACESCCT_X_BRK),
+        ACESCCT_Y_BRK,
+        1e-6,
+    );
+    assert_near(
+        Transfer::AcesCct.from_optical_scalar(above),
+        ACESCCT_Y_BRK,
+        1e-6,
+    );
+    assert_near(
+        Transfer::AcesCct.to_optical_scalar(ACESCCT_Y_BRK),
+        ACESCCT_X_BRK,
+        1e-7,
+    );
+    let log_slope = 1.0 / (ACESCCT_X_BRK * core::f32::consts::LN_2 * 17.52);
+    let toe_slope = Transfer::AcesCct.from_optical_scalar(ACESCCT_X_BRK) / ACESCCT_X_BRK;
+    let toe_slope = toe_slope - Transfer::AcesCct.from_optical_scalar(0.0) / ACESCCT_X_BRK;
+    assert_near(toe_slope, log_slope, 1e-3);
+}
+
+#[test]
+fn camera_log_curves_match_published_code_values() {
+    let log_c = Transfer::LogC3(ArriExposureIndex::Ei800);
+    // ARRI's 18% gray is code 400 of 1023 at EI 800, black is code 95, and
+    // the full signal holds 55.08 times the sensor's 18% gray exposure.
+    assert_near(log_c.from_optical_scalar(0.18), 0.391_007, 1e-5);
+    assert_near(log_c.from_optical_scalar(0.0), 0.092_809, 1e-6);
+    assert_near(log_c.to_optical_scalar(1.0), 55.08, 0.01);
+
+    // Sony's 18% gray is code 420 of 1023, 90% white 598 and black 95, and
+    // the full signal decodes to 38.42.
+    let code = |v: f32| Transfer::SLog3.from_optical_scalar(v) * 1023.0;
+    assert_near(code(0.18), 420.0, 1e-3);
+    assert_eq!(code(0.9).round(), 598.0);
+    assert_near(code(0.0), 95.0, 1e-3);
+    assert_near(Transfer::SLog3.to_optical_scalar(1.0), 38.42, 0.01);
+}
+
+#[test]
+fn white_balance_warms_toward_lower_temperatures() {
+    let image = vec![
+        [0.5, 0.5, 0.5, 1.0],
+        [0.2, 0.4, 0.6, 0.5],
+        [0.8, 0.3, 0.1, 0.25],
+    ];
+    for to_temp_k in [5000.0, 3200.0] {
+        let mut warmed = image.clone();
+        white_balance(
+            &mut warmed,
+            6500.0,
+            to_temp_k,
+            0.0,
+            &RgbColorSpace::LINEAR_SRGB,
+        );
+        for (before, after) in image.iter().zip(&warmed) {
+            assert!(
+                after[0] / after[2] > before[0] / before[2],
+                "{after:?} is not warmer than {before:?}"
+            );
+            assert_eq!(after[3], before[3]);
+        }
+    }
+
+    let mut unchanged = image.clone();
+    white_balance(
+        &mut unchanged,
+        5000.0,
+        5000.0,
+        0.0,
+        &RgbColorSpace::LINEAR_SRGB,
+    );
+    assert_eq!(unchanged, image);
+}
+
+#[test]
+fn mccamy_cct_matches_daylight_whites() {
+    let cct = |whitepoint: Whitepoint| {
+        let [x, y] = whitepoint.to_xy();
+        cct_from_xy(x, y)
+    };
+    assert_near(cct(Whitepoint::D65), 6504.0, 2.0);
+    assert_near(cct(Whitepoint::D50), 5003.0, 3.0);
+}
+
+#[test]
+fn blackbody_colors_run_from_orange_to_white() {
+    // 6500K sits just off D65, so it is white to within a few percent.
+    let [r, g, b, alpha] = blackbody_srgb(6500.0);
+    for c in [r, g, b] {
+        assert_near(c, 1.0, 0.03);
+    }
+    assert_eq!(alpha, 1.0);
+
+    // Candlelight is orange: full red, about half green and little blue.
+    let [r, g, b, _] = blackbody_srgb(2000.0);
+    assert_near(r, 1.0, 1e-6);
+    assert!((0.4..0.7).contains(&g), "{g}");
+    assert!(b < 0.2, "{b}");
+}
+
+#[test]
+fn flat_spectrum_integrates_to_illuminant_e() {
+    let xyz = spectrum_to_xyz(&[(360.0, 1.0), (830.0, 1.0)], Observer::Cie1931);
+    for (actual, expected) in xyz.iter().zip(Whitepoint::E.to_xyz()) {
+        assert_near(*actual, expected, 1e-4);
+    }
+}
+
+#[test]
+fn srgb_primaries_have_red_green_and_blue_wavelengths() {
+    let matrix = RgbColorSpace::SRGB.to_xyz_matrix().0;
+    let bands = [610.0..=620.0, 540.0..=560.0, 460.0..=470.0];
+    for (c, band) in bands.into_iter().enumerate() {
+        let xyz = [matrix[0][c], matrix[1][c], matrix[2][c]];
+        match dominant_wavelength(xyz, Whitepoint::D65) {
+            Some(DominantWavelength::Dominant(nm)) => assert!(band.contains(&nm), "{nm}"),
+            other => panic!("primary {c} has {other:?}"),
+        }
+        // The primaries lie inside the spectral locus.
+        let purity = excitation_purity(xyz, Whitepoint::D65);
+        assert!(purity > 0.5 && purity < 1.0, "{purity}");
+    }
+}
+
+#[test]
+fn cvd_simulation_at_zero_severity_is_the_identity() {
+    let pixels = [
+        [0.8, 0.2, 0.1, 0.5],
+        [0.1, 0.7, 0.3, 1.0],
+        [0.2, 0.3, 0.9, 0.0],
+    ];
+    for kind in [
+        CvdKind::Protanopia,
+        CvdKind::Deuteranopia,
+        CvdKind::Tritanopia,
+    ] {
+        for pixel in pixels {
+            let simulated = simulate_cvd(pixel, kind, 0.0, &RgbColorSpace::SRGB);
+            for (actual, expected) in simulated.iter().zip(pixel) {
+                assert_near(*actual, expected, TRANSFER_TOLERANCE);
+            }
+        }
+        // Full dichromacy keeps white.
+        let white = simulate_cvd([1.0; 4], kind, 1.0, &RgbColorSpace::SRGB);
+        for c in white {
+            assert_near(c, 1.0, CONVERT_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn wcag_contrast_spans_one_to_twenty_one() {
+    let (black, white) = ([0.0, 0.0, 0.0, 1.0], [1.0; 4]);
+    assert_near(
+        wcag_contrast(black, white, &RgbColorSpace::SRGB),
+        21.0,
+        1e-4,
+    );
+    assert_near(
+        wcag_contrast(white, black, &RgbColorSpace::SRGB),
+        21.0,
+        1e-4,
+    );
+    assert_eq!(wcag_contrast(white, white, &RgbColorSpace::SRGB), 1.0);
+}
+
+#[test]
+fn apca_contrast_matches_reference_values() {
+    let gray = |v: f32| [v, v, v, 1.0];
+    let (black, white, gray_888) = (gray(0.0), gray(1.0), gray(136.0 / 255.0));
+    // From the APCA-W3 0.0.98G-4g reference implementation.
+    assert_near(apca_contrast(black, white), 106.04, 0.01);
+    assert_near(apca_contrast(white, black), -107.88, 0.01);
+    assert_near(apca_contrast(gray_888, white), 63.06, 0.01);
+    assert_near(apca_contrast(white, gray_888), -68.54, 0.01);
+    assert_eq!(apca_contrast(gray_888, gray_888), 0.0);
+}
+
+#[test]
+fn quantize_keeps_two_colors_exactly() {
+    let (red, blue) = ([0.9, 0.1, 0.2, 1.0], [0.1, 0.2, 0.8, 0.5]);
+    let image: Vec<[f32; 4]> = (0..10)
+        .map(|i| if i % 3 == 0 { red } else { blue })
+        .collect();
+    for space in [ColorSpace::Rgb(RgbColorSpace::SRGB), ColorSpace::Oklab] {
+        let (palette, indices) = quantize(&image, 2, &space);
+        assert_eq!(palette.len(), 2);
+        for (pixel, index) in image.iter().zip(indices) {
+            for (actual, expected) in palette[usize::from(index)].iter().zip(pixel) {
+                assert_near(*actual, *expected, CONVERT_TOLERANCE);
+            }
+        }
+    }
+}
+
+#[test]
+fn quantizing_in_oklab_beats_srgb() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let levels = 8;
+    let image: Vec<[f32; 4]> = (0..levels * levels * levels)
+        .map(|i| {
+            let [r, g, b] = [i / (levels * levels), i / levels % levels, i % levels];
+            [r, g, b, levels - 1].map(|c| c as f32 / (levels - 1) as f32)
+        })
+        .collect();
+
+    // The mean Oklab distance from each pixel to its palette color.
+    let error = |space: ColorSpace| {
+        let (palette, indices) = quantize(&image, 32, &space);
+        let total: f32 = image
+            .iter()
+            .zip(indices)
+            .map(|(pixel, index)| {
+                let a = convert(*pixel, &srgb, &ColorSpace::Oklab);
+                let b = convert(palette[usize::from(index)], &srgb, &ColorSpace::Oklab);
+                (0..3).map(|c| (a[c] - b[c]).powi(2)).sum::<f32>().sqrt()
+            })
+            .sum();
+        total / image.len() as f32
+    };
+    let (oklab, encoded) = (error(ColorSpace::Oklab), error(srgb));
+    assert!(
+        oklab < encoded,
+        "Oklab error {oklab} is not below sRGB's {encoded}"
+    );
+}
+
+#[test]
+fn nearest_in_palette_finds_the_closest_color() {
+    let palette = [
+        [0.0, 0.0, 0.0, 1.0],
+        [1.0, 1.0, 1.0, 1.0],
+        [1.0, 0.0, 0.0, 1.0],
+        [0.0, 1.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0, 1.0],
+    ];
+    let pixels = [
+        ([0.1, 0.05, 0.1, 1.0], 0),
+        ([0.9, 0.95, 0.9, 1.0], 1),
+        ([0.8, 0.2, 0.1, 1.0], 2),
+        ([0.2, 0.7, 0.3, 1.0], 3),
+        ([0.1, 0.2, 0.9, 1.0], 4),
+    ];
+    for space in [ColorSpace::Rgb(RgbColorSpace::SRGB), ColorSpace::Oklab] {
+        let index = PaletteIndex::new(&palette, &space);
+        for (pixel, expected) in pixels {
+            assert_eq!(nearest_in_palette(pixel, &palette, &space), expected);
+            assert_eq!(index.nearest(pixel), expected);
+        }
+
+        // Pixels without a finite distance still get the same match.
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            for pixel in [[value, 0.5, 0.5, 1.0], [value; 4]] {
+                let expected = nearest_in_palette(pixel, &palette, &space);
+                assert_eq!(index.nearest(pixel), expected, "{pixel:?} in {space:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn try_convert_reports_out_of_domain_values() {
+    let pq = ColorSpace::Rgb(RgbColorSpace {
+        transfer: Transfer::Pq,
+        ..RgbColorSpace::BT2020
+    });
+    let xyz = ColorSpace::Xyz(Whitepoint::D65);
+    let result = try_convert([2.0, 0.5, 0.5, 1.0], &pq, &xyz);
+    assert_eq!(
+        result,
+        Err(ConvertError::OutOfDomain {
+            index: 0,
+            error: DomainError {
+                transfer: Transfer::Pq,
+                direction: Direction::Decode,
+                channel: 0,
+                value: 2.0,
+            },
+        })
+    );
+
+    // Negative light is outside what a log curve encodes.
+    let log_c = ColorSpace::Rgb(RgbColorSpace {
+        transfer: Transfer::LogC3(ArriExposureIndex::Ei800),
+        ..RgbColorSpace::LINEAR_SRGB
+    });
+    let linear = ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB);
+    let result = try_convert([0.5, -0.1, 0.5, 1.0], &linear, &log_c);
+    assert!(
+        matches!(
+            result,
+            Err(ConvertError::OutOfDomain { error, .. })
+                if error.direction == Direction::Encode && error.channel == 1
+        ),
+        "{result:?}"
+    );
+
+    assert!(try_convert([1.0, 0.5, 0.0, 1.0], &pq, &xyz).is_ok());
+    assert!(try_convert([0.5, 0.1, 0.5, 1.0], &linear, &log_c).is_ok());
+}
+
+#[test]
+fn primaries_matrices_invert() {
+    for primaries in PRIMARIES {
+        for whitepoint in [Whitepoint::D50, Whitepoint::D65, Whitepoint::E] {
+            let to_xyz = primaries.to_xyz(whitepoint);
+            let inverse = to_xyz.try_inv();
+            assert!(inverse.is_some(), "{primaries:?} gave a singular matrix");
+            assert!((inverse.unwrap() * to_xyz).is_near_identity());
+        }
+    }
+}
+
+#[test]
+fn black_point_compensation_maps_black_to_black() {
+    let options = ConversionOptions {
+        black_point_compensation: true,
+        ..ConversionOptions::default()
+    };
+    for to in D65_SPACES {
+        for alpha in [0.0, 0.5, 1.0] {
+            let black = [0.0, 0.0, 0.0, alpha];
+            let converted = convert_with_options(black, &LIFTED_SRGB, &to, options);
+            for (actual, expected) in converted.into_iter().zip(black) {
+                assert_near(actual, expected, TRANSFER_TOLERANCE);
+            }
+
+            // Encoding the lifted black magnifies rounding error near it, so
+            // this direction is checked in linear light.
+            let lifted = convert_with_options(black, &to, &LIFTED_SRGB, options);
+            let luminance = convert(lifted, &LIFTED_SRGB, &ColorSpace::Xyz(Whitepoint::D65))[1];
+            assert_near(luminance, LIFTED_SRGB.black_luminance(), TRANSFER_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn display_transform_shows_mid_gray_as_a_mid_tone() {
+    for alpha in [0.0, 0.5, 1.0] {
+        let [r, g, b, a] = DisplayTransform::default().apply([0.18, 0.18, 0.18, alpha]);
+        assert!((0.35..=0.5).contains(&r), "{r} is not a mid tone");
+        assert!(r == g && g == b);
+        assert_eq!(a, alpha);
+    }
+}
+
+#[test]
+fn signal_range_codes_round_trip() {
+    let ten = BitDepth(10);
+    assert_eq!(SignalRange::Narrow.to_normalized(64, ten), 0.0);
+    assert_eq!(SignalRange::Narrow.to_normalized(940, ten), 1.0);
+    assert_eq!(SignalRange::Full.to_normalized(0, ten), 0.0);
+    assert_eq!(SignalRange::Full.to_normalized(1023, ten), 1.0);
+
+    for transfer in [Transfer::Pq, Transfer::Hlg, Transfer::Srgb] {
+        assert_eq!(
+            transfer.to_optical_codes([64; 3], ten, SignalRange::Narrow),
+            [0.0; 3]
+        );
+        for range in [SignalRange::Narrow, SignalRange::Full] {
+            let [black, white] = range.black_and_white(ten);
+            for code in black..=white {
+                let linear = transfer.to_optical_codes([code; 3], ten, range);
+                assert_eq!(
+                    transfer.from_optical_codes(linear, ten, range, Rounding::Nearest),
+                    [code; 3],
+                    "{transfer:?} {range:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn working_space_encloses_both_gamuts() {
+    let space = |primaries| RgbColorSpace {
+        primaries,
+        ..RgbColorSpace::SRGB
+    };
+    for (input, output) in PRIMARIES
+        .into_iter()
+        .flat_map(|i| PRIMARIES.map(|o| (i, o)))
+    {
+        let (input, output) = (space(input), space(output));
+        let working = recommend_working_space(&input, &output);
+        assert_eq!(working.transfer, Transfer::Linear);
+        assert!(gamut_area(&working) >= gamut_area(&input).max(gamut_area(&output)));
+
+        // Only ACEScg's red, outside the spectral locus, can leave nothing
+        // enclosing both.
+        let candidates = [
+            RgbColorSpace::BT2020,
+            RgbColorSpace::ACES_CG,
+            RgbColorSpace::ACES2065_1,
+            input,
+            output,
+        ];
+        if candidates
+            .iter()
+            .any(|c| gamut_encloses(c, &input) && gamut_encloses(c, &output))
+        {
+            assert!(gamut_encloses(&working, &input), "{input:?} {output:?}");
+            assert!(gamut_encloses(&working, &output), "{input:?} {output:?}");
+        }
+    }
+
+    let srgb = RgbColorSpace::SRGB;
+    assert_eq!(
+        recommend_working_space(&srgb, &srgb).primaries,
+        Primaries::Bt2020
+    );
+    assert_eq!(
+        recommend_working_space(&RgbColorSpace::BT2020, &RgbColorSpace::DISPLAY_P3),
+        RgbColorSpace::ACES_CG
+    );
+    assert_eq!(
+        recommend_working_space(&RgbColorSpace::ACES_CG, &RgbColorSpace::BT2020),
+        RgbColorSpace::ACES_CG
+    );
+}
+
+#[test]
+fn h273_code_points_round_trip() {
+    assert_eq!(Primaries::from_h273(1), Some(Primaries::Bt709));
+    assert_eq!(Primaries::from_h273(9), Some(Primaries::Bt2020));
+    assert_eq!(Transfer::from_h273(1), Some(Transfer::Bt709));
+    assert_eq!(Transfer::from_h273(13), Some(Transfer::Srgb));
+    assert_eq!(Transfer::from_h273(16), Some(Transfer::Pq));
+    assert_eq!(Transfer::from_h273(18), Some(Transfer::Hlg));
+    assert_eq!(Differencing::from_h273(1), Some(Differencing::Bt709));
+    assert_eq!(Differencing::from_h273(9), Some(Differencing::Bt2020));
+    assert_eq!(Differencing::from_h273(0), None);
+
+    // Several code points can share a variant, which then gives one of them
+    // back.
+    for value in 0..=u8::MAX {
+        if let Some(primaries) = Primaries::from_h273(value) {
+            assert_eq!(
+                Primaries::from_h273(primaries.to_h273().unwrap()),
+                Some(primaries)
+            );
+        }
+        if let Some(transfer) = Transfer::from_h273(value) {
+            assert_eq!(
+                Transfer::from_h273(transfer.to_h273().unwrap()),
+                Some(transfer)
+            );
+        }
+        if let Some(differencing) = Differencing::from_h273(value) {
+            assert_eq!(differencing.to_h273(), Some(value));
+        }
+    }
+    for primaries in PRIMARIES {
+        if let Some(code) = primaries.to_h273() {
+            assert_eq!(Primaries::from_h273(code), Some(primaries));
+        }
+    }
+    for transfer in TRANSFERS {
+        if let Some(code) = transfer.to_h273() {
+            assert_eq!(Transfer::from_h273(code), Some(transfer));
+        }
+    }
+}
+
+#[test]
+fn icc_v2_profile_round_trips() {
+    for primaries in PRIMARIES {
+        for whitepoint in [
+            Whitepoint::D65,
+            Whitepoint::D50,
+            Whitepoint::Dci,
+            Whitepoint::E,
+        ] {
+            for transfer in [
+                Transfer::Linear,
+                Transfer::Srgb,
+                Transfer::Bt709,
+                Transfer::Dci,
+            ] {
+                let space = RgbColorSpace {
+                    primaries,
+                    whitepoint,
+                    transfer,
+                };
+                let profile = space.to_icc_v2();
+                assert_eq!(
+                    u32::from_be_bytes(profile[..4].try_into().unwrap()) as usize,
+                    profile.len()
+                );
+                let parsed = IccProfile::parse(&profile).unwrap();
+                assert_eq!(parsed.to_rgb_color_space(), Ok(space));
+            }
+        }
+    }
+}
+
+#[test]
+fn transfer_parameters_regenerate_the_curve() {
+    let TransferParams::Power(srgb) = Transfer::Srgb.parameters() else {
+        panic!("sRGB is a power curve");
+    };
+    assert_near(srgb.threshold, 0.003_130_8, 1e-6);
+    assert_eq!(
+        (srgb.slope, srgb.power, srgb.scale, srgb.offset),
+        (12.92, 2.4, 1.055, 0.055)
+    );
+
+    for transfer in TRANSFERS {
+        for i in 0..=1024 {
+            let v = i as f32 / 1024.0;
+            let regenerated = match transfer.parameters() {
+                TransferParams::Linear => v,
+                TransferParams::Power(p) if v <= p.threshold => p.slope * v,
+                TransferParams::Power(p) => p.scale * v.powf(1.0 / p.power) - p.offset,
+                TransferParams::LogC3(p) if v > p.cut => p.c * (p.a * v + p.b).log10() + p.d,
+                TransferParams::LogC3(p) => p.e * v + p.f,
+                TransferParams::Pq(p) => {
+                    let y = f64::from(v).powf(p.m1);
+                    ((p.c1 + p.c2 * y) / (1.0 + p.c3 * y)).powf(p.m2) as f32
+                }
+                TransferParams::Hlg(_) if v <= 1.0 / 12.0 => (3.0 * v).sqrt(),
+                TransferParams::Hlg(p) => p.a * (12.0 * v - p.b).ln() + p.c,
+                TransferParams::Other => break,
+            };
+            let mut encoded = [v];
+            apply_transfer_channel_in_domain(
+                transfer,
+                &mut encoded,
+                Direction::Encode,
+                LightDomain::Scene,
+            );
+            assert_near(regenerated, encoded[0], 1e-5);
+        }
+    }
+}
+
+#[test]
+fn gamut_intents_desaturate_or_clip_into_gamut() {
+    let bt2020 = ColorSpace::Rgb(RgbColorSpace::BT2020);
+    let linear_srgb = ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB);
+    let xyz = ColorSpace::Xyz(Whitepoint::D65);
+    let in_gamut = |[r, g, b, _]: [f32; 4]| [r, g, b].iter().all(|c| (0.0..=1.0).contains(c));
+
+    // BT.2020 green is outside sRGB, with negative red and blue.
+    let green = [0.0, 1.0, 0.0, 0.5];
+    let relative = convert_with_intent(
+        green,
+        &bt2020,
+        &linear_srgb,
+        RenderingIntent::RelativeColorimetric,
+    );
+    assert!(!in_gamut(relative), "{relative:?} is in gamut");
+
+    // Perceptual keeps the luminance and moves every channel toward it, so the
+    // color is desaturated along a line through gray.
+    let perceptual = convert_with_intent(green, &bt2020, &linear_srgb, RenderingIntent::Perceptual);
+    assert!(in_gamut(perceptual), "{perceptual:?} is out of gamut");
+    let luminance = convert(relative, &linear_srgb, &xyz)[1];
+    assert_near(
+        convert(perceptual, &linear_srgb, &xyz)[1],
+        luminance,
+        TRANSFER_TOLERANCE,
+    );
+    let t = (perceptual[1] - luminance) / (relative[1] - luminance);
+    assert!(0.0 < t && t < 1.0, "{t} does not desaturate");
+    for c in 0..3 {
+        assert_near(
+            perceptual[c],
+            luminance + t * (relative[c] - luminance),
+            TRANSFER_TOLERANCE,
+        );
+    }
+    assert_eq!(perceptual[3], green[3]);
+
+    // Saturation clips each channel on its own, keeping the others.
+    let saturation = convert_with_intent(green, &bt2020, &linear_srgb, RenderingIntent::Saturation);
+    assert_eq!(saturation, relative.map(|c| c.clamp(0.0, 1.0)));
+
+    // Colors already in gamut pass through both unchanged.
+    let gray = [0.3, 0.3, 0.3, 1.0];
+    let relative = convert_with_intent(
+        gray,
+        &bt2020,
+        &linear_srgb,
+        RenderingIntent::RelativeColorimetric,
+    );
+    for intent in [RenderingIntent::Perceptual, RenderingIntent::Saturation] {
+        assert_eq!(
+            convert_with_intent(gray, &bt2020, &linear_srgb, intent),
+            relative
+        );
+    }
+}
+
+#[test]
+fn oklch_adjustments_keep_grays_and_full_turns() {
+    let srgb = RgbColorSpace::SRGB;
+    for level in [0.2, 0.5, 0.8] {
+        let gray = [level, level, level, 0.5];
+        for (actual, expected) in saturate(gray, 0.5, &srgb).into_iter().zip(gray) {
+            assert_near(actual, expected, CONVERT_TOLERANCE);
+        }
+    }
+
+    let orange = [0.9, 0.5, 0.1, 0.5];
+    for degrees in [360.0, -360.0, 720.0] {
+        for (actual, expected) in rotate_hue(orange, degrees, &srgb).into_iter().zip(orange) {
+            assert_near(actual, expected, CONVERT_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn multiply_by_white_or_black_is_identity_or_black() {
+    let colors = [
+        [0.9, 0.5, 0.1, 1.0],
+        [0.2, 0.4, 0.6, 0.5],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    for space in [RgbColorSpace::LINEAR_SRGB, RgbColorSpace::SRGB] {
+        for base in colors {
+            let multiplied = blend(base, [1.0, 1.0, 1.0, 1.0], BlendMode::Multiply, &space);
+            for c in 0..3 {
+                assert_near(multiplied[c], base[c], TRANSFER_TOLERANCE);
+            }
+            let multiplied = blend(base, [0.0, 0.0, 0.0, 1.0], BlendMode::Multiply, &space);
+            assert_eq!(&multiplied[..3], &[0.0; 3]);
+            assert_eq!(multiplied[3], 1.0);
+        }
+    }
+}
+
+#[test]
+fn srgb_covers_about_a_third_of_the_visible_chromaticities() {
+    // The commonly quoted ~36% is roughly sRGB's share of the whole spectral
+    // locus on the xy diagram, rather than of BT.2020, which covers about
+    // twice as much.
+    let locus: Vec<[f32; 2]> = (380..=780)
+        .map(|wavelength| {
+            let [x, y, z] = Observer::Cie1931.color_matching(wavelength as f32);
+            [x / (x + y + z), y / (x + y + z)]
+        })
+        .collect();
+    let locus_area = locus
+        .iter()
+        .zip(locus.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum::<f32>()
+        .abs()
+        / 2.0;
+    assert_near(gamut_area(&RgbColorSpace::SRGB) / locus_area, 0.335, 0.005);
+    assert_near(
+        gamut_area(&RgbColorSpace::BT2020) / locus_area,
+        0.634,
+        0.005,
+    );
+
+    assert_near(
+        gamut_coverage(&RgbColorSpace::SRGB, &RgbColorSpace::BT2020),
+        0.53,
+        0.01,
+    );
+    assert_near(
+        gamut_coverage(&RgbColorSpace::BT2020, &RgbColorSpace::SRGB),
+        1.0,
+        1e-4,
+    );
+    for primaries in PRIMARIES {
+        let space = RgbColorSpace {
+            primaries,
+            ..RgbColorSpace::SRGB
+        };
+        assert_near(gamut_coverage(&space, &space), 1.0, 1e-4);
+    }
+}
+
+#[test]
+fn bt601_line_standards_use_their_own_primaries() {
+    let standards = [
+        (
+            YuvColorSpace::BT601_525,
+            Differencing::Bt601_525,
+            [0.630, 0.340],
+        ),
+        (
+            YuvColorSpace::BT601_625,
+            Differencing::Bt601_625,
+            [0.640, 0.330],
+        ),
+    ];
+    for (yuv, differencing, red) in standards {
+        assert_eq!(yuv.differencing(), differencing);
+        assert_eq!(yuv.rgb().primaries, differencing.primaries());
+        assert_eq!(yuv.rgb().primaries.chromaticities()[0], red);
+
+        // Both standards publish the luma weights of the 1953 NTSC primaries.
+        let expected = [
+            [0.299, 0.587, 0.114],
+            [-0.168_736, -0.331_264, 0.5],
+            [0.5, -0.418_688, -0.081_312],
+        ];
+        for (row, expected) in differencing.rgb_to_yuv_matrix().0.iter().zip(expected) {
+            for (actual, expected) in row.iter().zip(expected) {
+                assert_near(*actual, expected, 1e-6);
+            }
+        }
+    }
+
+    // The same Y'CbCr red decodes to a different XYZ in each standard.
+    let red = convert(
+        [1.0, 0.0, 0.0, 1.0],
+        &ColorSpace::Rgb(RgbColorSpace::BT601_525),
+        &ColorSpace::Yuv(YuvColorSpace::BT601_525),
+    );
+    let xyz = ColorSpace::Xyz(Whitepoint::D65);
+    let xyz_525 = convert(red, &ColorSpace::Yuv(YuvColorSpace::BT601_525), &xyz);
+    let xyz_625 = convert(red, &ColorSpace::Yuv(YuvColorSpace::BT601_625), &xyz);
+    assert!(
+        (xyz_525[0] - xyz_625[0]).abs() > 0.01,
+        "{xyz_525:?} {xyz_625:?}"
+    );
+
+    assert!(YuvColorSpace::new(RgbColorSpace::BT601_525, Differencing::Bt601_625).is_err());
+    assert!(YuvColorSpace::new(RgbColorSpace::BT601_625, Differencing::Bt601_525).is_err());
+    assert!(YuvColorSpace::new(RgbColorSpace::BT601_625, Differencing::Bt601_625).is_ok());
+    assert!(YuvColorSpace::new(RgbColorSpace::SRGB, Differencing::Bt601_525).is_ok());
+}
+
+#[test]
+fn condition_number_grows_as_matrices_near_singularity() {
+    assert_near(RowMatrix::IDENTITY.condition_number(), 1.0, 1e-6);
+    assert_near(
+        RowMatrix::diagonal([2.0, 1.0, 0.5]).condition_number(),
+        4.0,
+        1e-5,
+    );
+
+    let srgb = RgbColorSpace::SRGB.to_xyz_matrix().condition_number();
+    assert!(
+        (1.0..20.0).contains(&srgb),
+        "sRGB to XYZ has condition number {srgb}"
+    );
+
+    // Primaries almost on a line give almost dependent rows.
+    let nearly_singular = RowMatrix([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 1e-4]]);
+    assert!(nearly_singular.condition_number() > 1e4);
+    let singular = RowMatrix([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]]);
+    assert_eq!(singular.condition_number(), f32::INFINITY);
+}
+
+#[test]
+fn gamut_compression_keeps_inner_colors_and_is_monotonic() {
+    let srgb = RgbColorSpace::SRGB;
+    let (oklch, to) = (ColorSpace::Oklch, ColorSpace::Rgb(srgb));
+    let threshold = 0.8;
+    for (l, h) in [(0.7, 140.0), (0.5, 30.0), (0.4, 265.0)] {
+        let boundary = max_chroma(l, h, &srgb);
+        let compressed_chroma = |c: f32| {
+            let compressed = compress_to_gamut([l, c, h, 1.0], &oklch, &srgb, threshold);
+            assert!(
+                compressed[..3].iter().all(|c| (0.0..=1.0).contains(c)),
+                "{compressed:?}"
+            );
+            convert(compressed, &to, &oklch)[1]
+        };
+
+        // Inside the threshold, colors pass through as plain conversions.
+        for fraction in [0.0, 0.25, 0.5, 0.75] {
+            let pixel = [l, fraction * boundary, h, 1.0];
+            let compressed = compress_to_gamut(pixel, &oklch, &srgb, threshold);
+            let converted = convert(pixel, &oklch, &to);
+            for (actual, expected) in compressed.into_iter().zip(converted) {
+                assert_near(actual, expected, CONVERT_TOLERANCE);
+            }
+        }
+
+        // Far outside, colors land on the boundary.
+        assert_near(compressed_chroma(3.0 * boundary), boundary, 1e-3);
+
+        // In between, more chroma in never gives less chroma out.
+        let mut previous = 0.0;
+        for i in 0..=60 {
+            let chroma = compressed_chroma(i as f32 / 20.0 * boundary);
+            assert!(
+                chroma >= previous - 1e-4,
+                "{chroma} after {previous} at {l} {h}"
+            );
+            assert!(chroma <= boundary + 1e-3);
+            previous = chroma;
+        }
+    }
+}
+
+#[test]
+fn precomputed_matrices_match_the_primaries() {
+    let precomputed = [
+        (
+            Primaries::Bt709,
+            RgbColorSpace::BT709_TO_XYZ,
+            RgbColorSpace::XYZ_TO_BT709,
+        ),
+        (
+            Primaries::Bt2020,
+            RgbColorSpace::BT2020_TO_XYZ,
+            RgbColorSpace::XYZ_TO_BT2020,
+        ),
+        (
+            Primaries::DciP3,
+            RgbColorSpace::DISPLAY_P3_TO_XYZ,
+            RgbColorSpace::XYZ_TO_DISPLAY_P3,
+        ),
+    ];
+    for (primaries, to_xyz, from_xyz) in precomputed {
+        let runtime = primaries.to_xyz(Whitepoint::D65);
+        let pairs = [(to_xyz, runtime, 1e-6), (from_xyz, runtime.inv(), 1e-5)];
+        for (constant, runtime, tolerance) in pairs {
+            for (constant, runtime) in constant.0.iter().flatten().zip(runtime.0.iter().flatten()) {
+                assert_near(*constant, *runtime, tolerance);
+            }
+        }
+    }
+}
+
+#[test]
+fn convert_linear_rgb_matches_convert_in_linear_light() {
+    let linear = |space: RgbColorSpace| RgbColorSpace {
+        transfer: Transfer::Linear,
+        ..space
+    };
+    let spaces = [
+        RgbColorSpace::SRGB,
+        RgbColorSpace::BT2020,
+        RgbColorSpace::DISPLAY_P3,
+        RgbColorSpace::DCI_P3,
+        RgbColorSpace::ACES_CG,
+    ];
+    let pixels = [
+        [0.9, 0.5, 0.1],
+        [0.2, 0.4, 0.6],
+        [1.0, 1.0, 1.0],
+        [0.0, 0.0, 0.0],
+    ];
+    for from in spaces {
+        for to in spaces {
+            for [r, g, b] in pixels {
+                let expected = convert(
+                    [r, g, b, 1.0],
+                    &ColorSpace::Rgb(linear(from)),
+                    &ColorSpace::Rgb(linear(to)),
+                );
+                // The transfers are ignored, so the encoded spaces give the
+                // same result.
+                let converted = convert_linear_rgb([r, g, b], &from, &to);
+                for (actual, expected) in converted.into_iter().zip(expected) {
+                    assert_near(actual, expected, CONVERT_TOLERANCE);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn convert_iter_matches_apply_slice() {
+    let image = sample_image();
+    let pairs = [
+        (
+            ColorSpace::Rgb(RgbColorSpace::SRGB),
+            ColorSpace::Rgb(RgbColorSpace::BT2020),
+        ),
+        (
+            ColorSpace::Rgb(RgbColorSpace::DCI_P3),
+            ColorSpace::CieLab(Whitepoint::D50),
+        ),
+        (ColorSpace::Oklch, ColorSpace::Okhsv),
+    ];
+    for (from, to) in pairs {
+        let plan = ConversionPlan::new(&from, &to);
+        let mut converted = image.clone();
+        plan.apply_slice(&mut converted);
+        assert_eq!(
+            convert_iter(image.iter().copied(), &plan).collect::<Vec<_>>(),
+            converted
+        );
+    }
+}
+
+#[test]
+fn plan_then_its_inverse_fuses_away() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let xyz = ColorSpace::Xyz(Whitepoint::D65);
+    let (to_xyz, from_xyz) = (
+        ConversionPlan::new(&srgb, &xyz),
+        ConversionPlan::new(&xyz, &srgb),
+    );
+    let naive = to_xyz.steps().len() + from_xyz.steps().len();
+
+    let round_trip = to_xyz.then(from_xyz);
+    assert!(naive > 0);
+    assert!(round_trip.steps().is_empty(), "{:?}", round_trip.steps());
+    for pixel in sample_image() {
+        let converted = round_trip.apply(pixel);
+        for (actual, expected) in converted.into_iter().zip(pixel) {
+            assert_near(actual, expected, TRANSFER_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn chained_plans_drop_the_transfers_between_them() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let bt2020 = ColorSpace::Rgb(RgbColorSpace::BT2020);
+    let (there, back) = (
+        ConversionPlan::new(&srgb, &bt2020),
+        ConversionPlan::new(&bt2020, &srgb),
+    );
+    assert!(there
+        .steps()
+        .contains(&Step::EncodeTransfer(Transfer::Bt709)));
+    assert!(back
+        .steps()
+        .contains(&Step::DecodeTransfer(Transfer::Bt709)));
+
+    let chained = there.clone().then(back.clone());
+    let bt709 = [
+        Step::EncodeTransfer(Transfer::Bt709),
+        Step::DecodeTransfer(Transfer::Bt709),
+    ];
+    assert!(
+        !chained.steps().iter().any(|step| bt709.contains(step)),
+        "{:?}",
+        chained.steps()
+    );
+
+    // Without the junction's encoding and decoding the round trip loses less.
+    let error = |converted: [f32; 4], pixel: [f32; 4]| {
+        converted
+            .iter()
+            .zip(pixel)
+            .map(|(c, p)| (c - p).abs())
+            .sum::<f32>()
+    };
+    let (mut fused_error, mut unfused_error) = (0.0, 0.0);
+    for pixel in sample_image() {
+        fused_error += error(chained.apply(pixel), pixel);
+        unfused_error += error(back.apply(there.apply(pixel)), pixel);
+    }
+    assert!(
+        fused_error < unfused_error,
+        "{fused_error} is not below {unfused_error}"
+    );
+}
+
+#[test]
+fn scalar_transfers_match_the_rgba_red_channel() {
+    let values: Vec<f32> = (0..=32).map(|i| i as f32 / 32.0).collect();
+    for transfer in TRANSFERS {
+        let mut decoded = values.clone();
+        transfer.to_optical_slice(&mut decoded);
+        let mut encoded = values.clone();
+        transfer.from_optical_slice(&mut encoded);
+
+        for ((&v, decoded), encoded) in values.iter().zip(decoded).zip(encoded) {
+            // HLG's OOTF mixes channels, so a scalar is decoded as a gray,
+            // while every other transfer decodes channels independently.
+            let pixel = match transfer {
+                Transfer::Hlg => [v, v, v, 0.5],
+                _ => [v, 0.3, 0.7, 0.5],
+            };
+            assert_eq!(
+                transfer.to_optical_scalar(v),
+                transfer.to_optical_display(pixel)[0]
+            );
+            assert_eq!(
+                transfer.from_optical_scalar(v),
+                transfer.from_optical_display(pixel)[0]
+            );
+            assert_eq!(decoded, transfer.to_optical_scalar(v));
+            assert_eq!(encoded, transfer.from_optical_scalar(v));
+        }
+    }
+}
+
+#[test]
+fn plan_as_fn_matches_apply_slice() {
+    let image = sample_image();
+    for to in SPACES {
+        let plan = ConversionPlan::new(&ColorSpace::Rgb(RgbColorSpace::SRGB), &to);
+        let mut converted = image.clone();
+        plan.apply_slice(&mut converted);
+        assert_eq!(
+            image.iter().copied().map(plan.as_fn()).collect::<Vec<_>>(),
+            converted
+        );
+    }
+}
+
+#[test]
+fn plan_cache_plans_each_pair_once() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let mut cache = PlanCache::new();
+    assert!(cache.is_empty());
+
+    for pixel in sample_image() {
+        let cached = convert_cached(pixel, &srgb, &ColorSpace::Oklab, &mut cache);
+        assert_eq!(cached, convert(pixel, &srgb, &ColorSpace::Oklab));
+    }
+    assert_eq!(cache.len(), 1);
+
+    convert_cached([0.5; 4], &ColorSpace::Oklab, &srgb, &mut cache);
+    convert_cached([0.5; 4], &srgb, &ColorSpace::Oklab, &mut cache);
+    assert_eq!(cache.len(), 2);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn whitepoints_round_trip_through_chromaticities() {
+    let [x, y] = Whitepoint::D65.to_xy();
+    assert_near(x, 0.3127, 1e-4);
+    assert_near(y, 0.3290, 1e-4);
+    let [big_x, big_y, big_z] = Whitepoint::D65.to_xyz();
+    assert_near(big_x / (big_x + big_y + big_z), x, 1e-6);
+    assert_near(big_y / (big_x + big_y + big_z), y, 1e-6);
+
+    // Chromaticities rounded to four places, as specs give them, still name
+    // the white.
+    assert_eq!(Whitepoint::from_xy([0.3127, 0.3290]), Whitepoint::D65);
+    assert_eq!(Whitepoint::from_xy([0.3457, 0.3585]), Whitepoint::D50);
+    for white in [
+        Whitepoint::D50,
+        Whitepoint::D65,
+        Whitepoint::E,
+        Whitepoint::F11,
+        Whitepoint::Dci,
+    ] {
+        assert_eq!(Whitepoint::from_xy(white.to_xy()), white);
+    }
+
+    let custom = Whitepoint::from_xy([0.30, 0.31]);
+    assert_eq!(custom, Whitepoint::Custom([0.30, 0.31]));
+    assert_eq!(custom.to_xy(), [0.30, 0.31]);
+    assert_eq!(Whitepoint::from_xy(custom.to_xy()), custom);
+}
+
+#[test]
+fn fluorescent_whites_are_used_in_adaptation() {
+    let method = ChromaticAdaptation::Bradford;
+    let color = [0.3, 0.4, 0.2];
+    let from_f11 = adapt_xyz(color, Whitepoint::F11, Whitepoint::D65, method);
+    let from_d50 = adapt_xyz(color, Whitepoint::D50, Whitepoint::D65, method);
+    let difference: f32 = from_f11
+        .iter()
+        .zip(from_d50)
+        .map(|(a, b)| (a - b).abs())
+        .sum();
+    assert!(
+        difference > 0.01,
+        "{from_f11:?} is too close to {from_d50:?}"
+    );
+
+    // The CIE 15 chromaticity of F7.
+    assert_eq!(Whitepoint::F7.to_xy(), [0.312_92, 0.329_33]);
+
+    // Each white adapts onto D65's and is neutral in its own CIELAB.
+    for white in [
+        Whitepoint::E,
+        Whitepoint::F2,
+        Whitepoint::F7,
+        Whitepoint::F11,
+    ] {
+        let adapted = adapt_xyz(white.to_xyz(), white, Whitepoint::D65, method);
+        for (actual, expected) in adapted.into_iter().zip(Whitepoint::D65.to_xyz()) {
+            assert_near(actual, expected, TRANSFER_TOLERANCE);
+        }
+        let [x, y, z] = white.to_xyz();
+        let [l, a, b, _] = convert(
+            [x, y, z, 1.0],
+            &ColorSpace::Xyz(white),
+            &ColorSpace::CieLab(white),
+        );
+        assert_near(l, 100.0, 1e-3);
+        assert_near(a, 0.0, 1e-3);
+        assert_near(b, 0.0, 1e-3);
+    }
+}
+
+#[test]
+fn primaries_give_their_published_chromaticities() {
+    assert_eq!(
+        Primaries::Bt709.chromaticities(),
+        [[0.64, 0.33], [0.30, 0.60], [0.15, 0.06]]
+    );
+    assert_eq!(
+        Primaries::Bt2020.chromaticities(),
+        [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046]]
+    );
+}
+
+#[test]
+fn encoded_state_tells_linear_single_and_double_srgb_apart() {
+    // A gray ramp averaging the sRGB encoded mid gray of natural images.
+    let ramp: Vec<f32> = (0..=100).map(|i| 0.1 + 0.72 * i as f32 / 100.0).collect();
+    let image = |encode: &dyn Fn(f32) -> f32| -> Vec<[f32; 4]> {
+        ramp.iter()
+            .map(|&v| [encode(v), encode(v), encode(v), 1.0])
+            .collect()
+    };
+    let cases = [
+        (
+            image(&|v| Transfer::Srgb.to_optical_scalar(v)),
+            EncodedState::Linear,
+        ),
+        (image(&|v| v), EncodedState::Srgb),
+        (
+            image(&|v| Transfer::Srgb.from_optical_scalar(v)),
+            EncodedState::DoubleSrgb,
+        ),
+    ];
+    for (image, expected) in cases {
+        let guess = estimate_encoded_state(&image);
+        assert_eq!(guess.state, expected);
+        assert!(guess.confidence > 0.5, "{guess:?}");
+    }
+
+    assert_eq!(
+        estimate_encoded_state(&[]),
+        EncodingGuess {
+            state: EncodedState::Srgb,
+            confidence: 0.0
+        }
+    );
+}
+
+#[test]
+fn white_is_white_in_both_lab_spaces() {
+    for whitepoint in [Whitepoint::D65, Whitepoint::D50] {
+        let [l, a, b, alpha] = oklab_to_cielab([1.0, 0.0, 0.0, 0.5], whitepoint);
+        assert_near(l, 100.0, 1e-2);
+        assert_near(a, 0.0, 1e-2);
+        assert_near(b, 0.0, 1e-2);
+        assert_eq!(alpha, 0.5);
+
+        let [l, a, b, alpha] = cielab_to_oklab([100.0, 0.0, 0.0, 0.5], whitepoint);
+        assert_near(l, 1.0, 1e-4);
+        assert_near(a, 0.0, 1e-4);
+        assert_near(b, 0.0, 1e-4);
+        assert_eq!(alpha, 0.5);
+    }
+}
+
+#[test]
+fn bt2087_lut_keeps_grays_and_matches_the_matrix() {
+    let lut = bt2087_lut(33);
+    let scale = (lut.size() - 1) as f32;
+    for i in 0..lut.size() {
+        let [r, g, b] = lut.get(i, i, i);
+        assert_near(r, i as f32 / scale, 1e-5);
+        assert_near(g, r, 1e-5);
+        assert_near(b, r, 1e-5);
+    }
+
+    let direct = |rgb: [f32; 3]| {
+        BT2087_MATRIX
+            .mul_vec(rgb.map(|c| c.powf(2.4)))
+            .map(|c| c.max(0.0).powf(1.0 / 2.4))
+    };
+    // On the lattice the LUT holds the conversion exactly, and between its
+    // points interpolation stays close.
+    for ([r, g, b], tolerance) in [
+        ([1.0, 0.0, 0.0], 1e-5),
+        ([0.25, 0.5, 0.75], 1e-5),
+        ([0.9, 0.5, 0.1], 5e-3),
+        ([0.3, 0.7, 0.2], 5e-3),
+    ] {
+        let converted = lut.apply([r, g, b, 1.0]);
+        for (actual, expected) in converted.into_iter().zip(direct([r, g, b])) {
+            assert_near(actual, expected, tolerance);
+        }
+    }
+}
+
+#[test]
+fn light_domain_only_changes_hlg() {
+    let pixel = [0.75, 0.5, 0.25, 0.5];
+    let scene = Transfer::Hlg.to_optical(LightDomain::Scene, pixel);
+    let display = Transfer::Hlg.to_optical(LightDomain::Display, pixel);
+    assert!(
+        scene[..3]
+            .iter()
+            .zip(&display[..3])
+            .any(|(s, d)| (s - d).abs() > 1e-3),
+        "{scene:?} {display:?}"
+    );
+    assert_eq!(Transfer::Hlg.to_optical_display(pixel), display);
+    for (domain, linear) in [(LightDomain::Scene, scene), (LightDomain::Display, display)] {
+        let encoded = Transfer::Hlg.from_optical(domain, linear);
+        for (actual, expected) in encoded.into_iter().zip(pixel) {
+            assert_near(actual, expected, TRANSFER_TOLERANCE);
+        }
+    }
+
+    for transfer in TRANSFERS
+        .into_iter()
+        .filter(|&transfer| transfer != Transfer::Hlg)
+    {
+        assert_eq!(
+            transfer.to_optical(LightDomain::Scene, pixel),
+            transfer.to_optical(LightDomain::Display, pixel)
+        );
+        assert_eq!(
+            transfer.from_optical(LightDomain::Scene, pixel),
+            transfer.from_optical(LightDomain::Display, pixel)
+        );
+    }
+}
+
+#[test]
+fn apply_checked_rejects_values_outside_the_srgb_signal() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let xyz = ColorSpace::Xyz(Whitepoint::D65);
+    let expected = DomainError {
+        transfer: Transfer::Srgb,
+        direction: Direction::Decode,
+        channel: 1,
+        value: 2.0,
+    };
+    let pixel = [0.5, 2.0, 0.5, 1.0];
+    assert_eq!(
+        ConversionPlan::new(&srgb, &xyz).apply_checked(pixel),
+        Err(expected)
+    );
+    assert_eq!(convert_checked(pixel, &srgb, &xyz), Err(expected));
+    assert_eq!(
+        expected.to_string(),
+        "value 2 in channel 1 is outside [0, 1], the range the Srgb transfer decodes"
+    );
+
+    let in_range = [0.5, 1.0, 0.0, 1.0];
+    assert_eq!(
+        convert_checked(in_range, &srgb, &xyz),
+        Ok(convert(in_range, &srgb, &xyz))
+    );
+}
+
+#[test]
+fn srgb_display_shows_its_xyz_matrix() {
+    let described = RgbColorSpace::SRGB.to_string();
+    assert!(described.contains("primaries: Bt709"), "{described}");
+    assert!(described.contains("transfer: Srgb"), "{described}");
+
+    // The matrix rows of CSS Color 4, which derives them from the same
+    // primaries and white.
+    let expected = [
+        [0.412_390_8, 0.357_584_34, 0.180_480_8],
+        [0.212_639, 0.715_168_7, 0.072_192_32],
+        [0.019_330_818, 0.119_194_78, 0.950_532_15],
+    ];
+    let rows: Vec<Vec<f32>> = described
+        .lines()
+        .skip_while(|line| *line != "RGB to XYZ:")
+        .skip(1)
+        .map(|line| {
+            line.trim()
+                .trim_matches(['[', ']'])
+                .split(", ")
+                .map(|value| value.parse().unwrap())
+                .collect()
+        })
+        .collect();
+    assert_eq!(rows.len(), 3, "{described}");
+    for (row, expected) in rows.iter().zip(expected) {
+        for (actual, expected) in row.iter().zip(expected) {
+            assert_near(*actual, expected, 1e-6);
+        }
+    }
+}
+
+#[test]
+fn rounding_modes_differ_only_at_halves() {
+    // Halfway between two 8-bit codes the modes disagree.
+    let halves = [
+        (0.5, [0, 1, 0]),
+        (1.5, [1, 2, 2]),
+        (2.5, [2, 3, 2]),
+        (127.5, [127, 128, 128]),
+    ];
+    let modes = [Rounding::Truncate, Rounding::Nearest, Rounding::NearestEven];
+    for (code, expected) in halves {
+        for (rounding, expected) in modes.into_iter().zip(expected) {
+            let [quantized, ..] = quantize_pixel::<u8>([code / 255.0, 0.0, 0.0, 1.0], rounding);
+            assert_eq!(quantized, expected, "{code} with {rounding:?}");
+        }
+    }
+
+    // Truncation never rounds up, and away from halves the other two
+    // agree. No multiple of 1/997 is halfway between 8-bit codes.
+    for i in 0..=997 {
+        let v = i as f32 / 997.0;
+        let quantize = |rounding| quantize_pixel::<u8>([v; 4], rounding)[0];
+        assert!(f32::from(quantize(Rounding::Truncate)) <= v * 255.0);
+        assert_eq!(
+            quantize(Rounding::Nearest),
+            quantize(Rounding::NearestEven),
+            "{v}"
+        );
+    }
+}
+
+#[test]
+fn signed_chroma_is_zero_for_grays_and_symmetric() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let yuv = ColorSpace::Yuv(YuvColorSpace::BT709);
+    for level in [0.0, 0.2, 0.5, 1.0] {
+        let gray = convert([level, level, level, 1.0], &srgb, &yuv);
+        let signed = quantize_yuv::<u8, i8>(gray, Rounding::Nearest);
+        assert_eq!((signed.cb, signed.cr), (0, 0), "{gray:?}");
+        let signed = quantize_yuv::<u16, i16>(gray, Rounding::Nearest);
+        assert_eq!((signed.cb, signed.cr), (0, 0), "{gray:?}");
+        let offset = quantize_yuv::<u8, u8>(gray, Rounding::Nearest);
+        assert_eq!((offset.cb, offset.cr), (128, 128), "{gray:?}");
+    }
+
+    // The extremes of the color differences are equally far from zero, and
+    // two's complement's extra negative code reads as the one above it.
+    let extremes = quantize_yuv::<u8, i8>([0.5, -0.5, 0.5, 1.0], Rounding::Nearest);
+    assert_eq!((extremes.cb, extremes.cr), (-127, 127));
+    let extremes = quantize_yuv::<u16, i16>([0.5, -0.5, 0.5, 1.0], Rounding::Nearest);
+    assert_eq!((extremes.cb, extremes.cr), (-32767, 32767));
+    let sample = YuvSample {
+        y: 255_u8,
+        cb: i8::MIN,
+        cr: i8::MAX,
+        alpha: 255,
+    };
+    assert_eq!(dequantize_yuv(sample), [1.0, -0.5, 0.5, 1.0]);
+}
+
+#[test]
+fn y_scales_agree_on_cielab() {
+    let [x, y, z] = Whitepoint::D65.to_xyz();
+    let unit = [x, y, z, 1.0];
+    let hundred = rescale_xyz(unit, YScale::Unit, YScale::Hundred);
+    assert_near(hundred[1], 100.0, 1e-4);
+
+    let [l, a, b, _] = xyz_to_cielab(unit, Whitepoint::D65, YScale::Unit);
+    assert_near(l, 100.0, 1e-3);
+    assert_near(a, 0.0, 1e-3);
+    assert_near(b, 0.0, 1e-3);
+    let lab = xyz_to_cielab(hundred, Whitepoint::D65, YScale::Hundred);
+    for (actual, expected) in lab.into_iter().zip([l, a, b, 1.0]) {
+        assert_near(actual, expected, 1e-4);
+    }
+
+    let color = [0.2, 0.21, 0.23, 0.5];
+    let lab = xyz_to_cielab(color, Whitepoint::D65, YScale::Unit);
+    let back = cielab_to_xyz(lab, Whitepoint::D65, YScale::Hundred);
+    for (actual, expected) in
+        back.into_iter()
+            .zip(rescale_xyz(color, YScale::Unit, YScale::Hundred))
+    {
+        assert_near(actual, expected, 1e-3);
+    }
+}
+
+#[test]
+fn srgb_segments_meet_at_the_knees() {
+    let power_encode = |v: f32| 1.055 * v.powf(1.0 / 2.4) - 0.055;
+    let power_decode = |v: f32| ((v + 0.055) / 1.055).powf(2.4);
+    assert_near(
+        SRGB_LINEAR_KNEE * 12.92,
+        power_encode(SRGB_LINEAR_KNEE),
+        1e-7,
+    );
+    assert_near(
+        SRGB_ENCODED_KNEE / 12.92,
+        power_decode(SRGB_ENCODED_KNEE),
+        1e-7,
+    );
+    assert_near(SRGB_LINEAR_KNEE * 12.92, SRGB_ENCODED_KNEE, 1e-7);
+
+    // Stepping one ulp over either knee doesn't jump between the segments.
+    let next_up = |v: f32| f32::from_bits(v.to_bits() + 1);
+    let encoded = transfer_oe_srgb(next_up(SRGB_LINEAR_KNEE)) - transfer_oe_srgb(SRGB_LINEAR_KNEE);
+    assert!((0.0..=1e-7).contains(&encoded), "{encoded}");
+    let decoded =
+        transfer_eo_srgb(next_up(SRGB_ENCODED_KNEE)) - transfer_eo_srgb(SRGB_ENCODED_KNEE);
+    assert!((0.0..=1e-7).contains(&decoded), "{decoded}");
+}
+
+#[test]
+fn descriptors_plan_the_conversion_they_describe() {
+    let expected = ConversionPlan::with_intent(
+        &ColorSpace::Rgb(RgbColorSpace::SRGB),
+        &ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3),
+        RenderingIntent::Perceptual,
+    );
+    let toml = "# Web content on a wide gamut display\n\
+                source = \"srgb\"\n\
+                destination = 'Display-P3'\n\
+                intent = \"perceptual\"\n";
+    let json = r#"{ "source": "srgb", "destination": "display-p3", "intent": "perceptual" }"#;
+    assert_eq!(ConversionPlan::from_descriptor(toml), Ok(expected.clone()));
+    assert_eq!(ConversionPlan::from_descriptor(json), Ok(expected));
+
+    let errors = [
+        (
+            "source = \"srgb\"",
+            DescriptorError::MissingKey("destination"),
+        ),
+        (
+            "source = \"srgb\"\ndestination = \"cmyk\"",
+            DescriptorError::UnknownColorSpace("cmyk".into()),
+        ),
+        (
+            "source = \"srgb\"\ndestination = \"srgb\"\nintent = \"vivid\"",
+            DescriptorError::UnknownIntent("vivid".into()),
+        ),
+        (
+            "source = \"srgb\"\ngamma = \"2.2\"",
+            DescriptorError::UnknownKey("gamma".into()),
+        ),
+        (
+            "source = \"srgb\"\nsource = \"srgb\"",
+            DescriptorError::DuplicateKey("source".into()),
+        ),
+        (
+            "source = \"srgb\"\ndestination = srgb",
+            DescriptorError::Syntax { line: 2 },
+        ),
+    ];
+    for (descriptor, error) in errors {
+        assert_eq!(ConversionPlan::from_descriptor(descriptor), Err(error));
+    }
+}
+
+#[test]
+fn byte_buffers_round_trip_in_every_layout() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let p3 = ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3);
+    let (there, back) = (
+        ConversionPlan::new(&srgb, &p3),
+        ConversionPlan::new(&p3, &srgb),
+    );
+    let orders = [
+        ChannelOrder::Rgb,
+        ChannelOrder::Rgba,
+        ChannelOrder::Bgr,
+        ChannelOrder::Bgra,
+        ChannelOrder::Argb,
+    ];
+    for order in orders {
+        for interleaving in [Interleaving::Interleaved, Interleaving::Planar] {
+            for endian in [Endian::Little, Endian::Big] {
+                let layout = PixelLayout {
+                    order,
+                    format: ChannelFormat::U16(endian),
+                    interleaving,
+                };
+                let input: Vec<u8> = (0..order.channels() * 32)
+                    .map(|i| (i * 37 % 256) as u8)
+                    .collect();
+                let (mut converted, mut round_trip) = (Vec::new(), Vec::new());
+                convert_bytes(&input, layout, &there, &mut converted);
+                convert_bytes(&converted, layout, &back, &mut round_trip);
+                assert_eq!(converted.len(), input.len());
+                assert_ne!(converted, input);
+
+                let read = |bytes: &[u8]| -> Vec<u16> {
+                    bytes
+                        .chunks(2)
+                        .map(|c| endian.read_u16([c[0], c[1]]))
+                        .collect()
+                };
+                // Rounding to 16 bits in Display P3 moves dark sRGB channels,
+                // where its curve is steep, by a few codes.
+                for (actual, expected) in read(&round_trip).into_iter().zip(read(&input)) {
+                    assert!(
+                        actual.abs_diff(expected) <= 8,
+                        "{layout:?}: {actual} is not {expected}"
+                    );
+                }
+            }
+        }
+    }
+
+    // Decoding to linear light moves each channel by its own amount, which
+    // shows the channels are read and written in place, alpha untouched.
+    let to_linear = ConversionPlan::new(&srgb, &ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB));
+    let layout = PixelLayout {
+        order: ChannelOrder::Argb,
+        format: ChannelFormat::U8,
+        interleaving: Interleaving::Interleaved,
+    };
+    let mut output = Vec::new();
+    convert_bytes(&[128, 255, 128, 0], layout, &to_linear, &mut output);
+    assert_eq!(output, [128, 255, 55, 0]);
+}
+
+#[test]
+fn big_endian_buffers_convert_like_their_little_endian_twins() {
+    let plan = ConversionPlan::new(
+        &ColorSpace::Rgb(RgbColorSpace::SRGB),
+        &ColorSpace::Rgb(RgbColorSpace::BT2020),
+    );
+    let layout = |endian| PixelLayout {
+        order: ChannelOrder::Rgba,
+        format: ChannelFormat::U16(endian),
+        interleaving: Interleaving::Interleaved,
+    };
+    let swap = |bytes: &[u8]| -> Vec<u8> { bytes.chunks(2).flat_map(|c| [c[1], c[0]]).collect() };
+
+    // Alpha codes with distinct bytes show it is swapped too.
+    let big: Vec<u8> = [
+        0x1234_u16, 0xABCD, 0x00FF, 0x8001, 0xFFFF, 0x0102, 0x7F80, 0x4321,
+    ]
+    .iter()
+    .flat_map(|v| v.to_be_bytes())
+    .collect();
+    let little = swap(&big);
+    let (mut from_big, mut from_little) = (Vec::new(), Vec::new());
+    convert_bytes(&big, layout(Endian::Big), &plan, &mut from_big);
+    convert_bytes(&little, layout(Endian::Little), &plan, &mut from_little);
+    assert_eq!(from_big, swap(&from_little));
+    assert_eq!(from_big[6..8], big[6..8]);
+    assert_eq!(from_big[14..16], big[14..16]);
+
+    let native = if cfg!(target_endian = "big") {
+        &big
+    } else {
+        &little
+    };
+    let mut from_native = Vec::new();
+    convert_bytes(native, layout(Endian::Native), &plan, &mut from_native);
+    assert_eq!(
+        &from_native,
+        if cfg!(target_endian = "big") {
+            &from_big
+        } else {
+            &from_little
+        }
+    );
+}
+
+#[test]
+fn numeric_eotf_inverse_matches_the_closed_forms() {
+    for i in 0..=100 {
+        let y = i as f32 / 100.0;
+        assert_near(
+            invert_eotf(Transfer::Srgb, y),
+            Transfer::Srgb.from_optical_scalar(y),
+            1e-5,
+        );
+        assert_near(
+            invert_eotf(Transfer::Pq, y),
+            Transfer::Pq.from_optical_scalar(y),
+            1e-5,
+        );
+    }
+
+    /// A smoothstep EOTF with no encoding to fall back on.
+    #[derive(Debug)]
+    struct EotfOnly;
+
+    impl CustomTransfer for EotfOnly {
+        fn eotf(&self, v: f32) -> f32 {
+            v * v * (3.0 - 2.0 * v)
+        }
+
+        fn oetf(&self, _: f32) -> f32 {
+            unreachable!("only the EOTF is known")
+        }
+    }
+
+    let transfer = Transfer::Custom(CustomCurve(&EotfOnly));
+    for i in 0..=100 {
+        let y = i as f32 / 100.0;
+        assert_near(EotfOnly.eotf(invert_eotf(transfer, y)), y, 1e-5);
+    }
+    assert_eq!(invert_eotf(transfer, 2.0), 1.0);
+    assert_eq!(invert_eotf(transfer, -1.0), 0.0);
+}
+
+#[test]
+fn cone_responses_change_oklab_but_keep_white() {
+    assert_eq!(ConeResponse::default(), ConeResponse::OklabM1);
+    let violet = [0.3, 0.2, 0.6, 1.0];
+    let oklab = convert(
+        violet,
+        &ColorSpace::Xyz(Whitepoint::D65),
+        &ColorSpace::Oklab,
+    );
+    assert_eq!(
+        xyz_to_oklab_with_cones(violet, ConeResponse::default()),
+        oklab
+    );
+
+    let [x, y, z] = Whitepoint::D65.to_xyz();
+    for cones in [
+        ConeResponse::Hpe,
+        ConeResponse::Cat02,
+        ConeResponse::Cat16,
+        ConeResponse::OklabM1,
+    ] {
+        let [l, a, b, _] = xyz_to_oklab_with_cones([x, y, z, 1.0], cones);
+        assert_near(l, 1.0, 1e-5);
+        assert_near(a, 0.0, 1e-5);
+        assert_near(b, 0.0, 1e-5);
+
+        if cones != ConeResponse::OklabM1 {
+            let other = xyz_to_oklab_with_cones(violet, cones);
+            let difference: f32 = other.iter().zip(oklab).map(|(o, e)| (o - e).abs()).sum();
+            assert!(
+                difference > 0.01,
+                "{cones:?} gives {other:?}, too close to {oklab:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn adapt_slice_matches_adapt_xyz() {
+    let image = sample_image();
+    let methods = [
+        ChromaticAdaptation::XyzScaling,
+        ChromaticAdaptation::VonKries,
+        ChromaticAdaptation::Bradford,
+    ];
+    for method in methods {
+        for (from, to) in [
+            (Whitepoint::D50, Whitepoint::D65),
+            (Whitepoint::A, Whitepoint::F11),
+        ] {
+            let mut adapted = image.clone();
+            adapt_slice(&mut adapted, from, to, method);
+            for (pixel, adapted) in image.iter().zip(&adapted) {
+                let [x, y, z] = adapt_xyz([pixel[0], pixel[1], pixel[2]], from, to, method);
+                assert_eq!(*adapted, [x, y, z, pixel[3]]);
+            }
+        }
+    }
+}
+
+#[test]
+fn apply_with_alpha_handles_every_pair_of_modes() {
+    use AlphaMode::{Premultiplied, Straight};
+
+    let plan = ConversionPlan::new(
+        &ColorSpace::Rgb(RgbColorSpace::SRGB),
+        &ColorSpace::Rgb(RgbColorSpace::LINEAR_SRGB),
+    );
+    let premultiply = |[r, g, b, a]: [f32; 4]| [r * a, g * a, b * a, a];
+    let straight = [0.5, 0.25, 1.0, 0.5];
+    let converted = plan.apply(straight);
+    let cases = [
+        ((Straight, Straight), straight, converted),
+        ((Premultiplied, Straight), premultiply(straight), converted),
+        ((Straight, Premultiplied), straight, premultiply(converted)),
+        (
+            (Premultiplied, Premultiplied),
+            premultiply(straight),
+            premultiply(converted),
+        ),
+    ];
+    for (modes, input, expected) in cases {
+        let actual = plan.apply_with_alpha(input, modes);
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert_near(actual, expected, TRANSFER_TOLERANCE);
+        }
+        let mut pixels = [input; 3];
+        plan.apply_slice_with_alpha(&mut pixels, modes);
+        assert_eq!(pixels, [plan.apply_with_alpha(input, modes); 3]);
+
+        // Transparent pixels have no color, which comes out as black.
+        assert_eq!(plan.apply_with_alpha([0.0; 4], modes), [0.0; 4]);
+    }
+    assert_eq!(
+        plan.apply_with_alpha([0.3, 0.3, 0.3, 0.0], (Premultiplied, Straight)),
+        [0.0; 4]
+    );
+}
+
+#[test]
+fn yuv_coefficient_mismatch_is_the_worst_corner_error() {
+    let delta = yuv_coefficient_mismatch_delta(Differencing::Bt709, Differencing::Bt601_625);
+    assert!(delta > 0.05, "{delta}");
+    assert_eq!(
+        yuv_coefficient_mismatch_delta(Differencing::Bt709, Differencing::Bt709),
+        0.0
+    );
+    assert_eq!(
+        yuv_coefficient_mismatch_delta(Differencing::Bt601_525, Differencing::Bt601_625),
+        0.0
+    );
+
+    let pairs = [
+        (Differencing::Bt709, Differencing::Bt601_625),
+        (Differencing::Bt601_525, Differencing::Bt709),
+        (Differencing::Bt2020, Differencing::Bt709),
+    ];
+    for (actual, assumed) in pairs {
+        let worst = (0..8)
+            .map(|corner| [corner & 1, corner >> 1 & 1, corner >> 2 & 1].map(|c| c as f32))
+            .flat_map(|rgb| {
+                let yuv = actual.rgb_to_yuv_matrix().mul_vec(rgb);
+                let decoded = assumed.yuv_to_rgb_matrix().mul_vec(yuv);
+                decoded.into_iter().zip(rgb).map(|(d, c)| (d - c).abs())
+            })
+            .fold(0.0, f32::max);
+        assert_near(yuv_coefficient_mismatch_delta(actual, assumed), worst, 1e-5);
+    }
+}
+
+#[test]
+fn precision_report_is_exact_for_identity_and_measurable_for_chains() {
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let identity = precision_report(&srgb, &srgb, 4096);
+    assert!(identity.max < 1e-12, "{identity:?}");
+
+    let chain = precision_report(
+        &ColorSpace::Rgb(RgbColorSpace::BT2020),
+        &ColorSpace::CieLab(Whitepoint::D50),
+        4096,
+    );
+    assert!(chain.max > 1e-6 && chain.max < 1e-3, "{chain:?}");
+    assert!(
+        chain.mean <= chain.rms && chain.rms <= chain.max,
+        "{chain:?}"
+    );
+
+    assert_eq!(
+        precision_report(&srgb, &srgb, 0),
+        PrecisionReport::default()
+    );
+}
+
+#[test]
+fn gamut_plot_draws_a_polygon_and_whitepoint_per_space() {
+    let mut svg = String::new();
+    plot_gamuts_svg(&[&RgbColorSpace::SRGB, &RgbColorSpace::DCI_P3], &mut svg).unwrap();
+
+    assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+    // The spectral locus and one gamut per space.
+    assert_eq!(svg.matches("<polygon").count(), 3);
+    assert_eq!(svg.matches("<circle").count(), 2);
+    assert!(svg.contains(r#"<circle cx="312.7" cy="571.0""#), "{svg}");
+    assert!(svg.contains(r#"<circle cx="314.0" cy="549.0""#), "{svg}");
+}
+
+#[test]
+fn max_chroma_lies_on_the_gamut_boundary() {
+    let in_gamut = |pixel: [f32; 4]| pixel[..3].iter().all(|c| (-1e-4..=1.0 + 1e-4).contains(c));
+    for space in [RgbColorSpace::SRGB, RgbColorSpace::BT2020] {
+        let linear = ColorSpace::Rgb(RgbColorSpace {
+            transfer: Transfer::Linear,
+            ..space
+        });
+        for (l, h) in [(0.7, 140.0), (0.5, 30.0), (0.4, 265.0), (0.9, 100.0)] {
+            let boundary = max_chroma(l, h, &space);
+            assert!(boundary > 0.0, "{l} {h}");
+
+            let inside = convert([l, boundary, h, 1.0], &ColorSpace::Oklch, &linear);
+            assert!(in_gamut(inside), "{inside:?}");
+            let outside = convert([l, boundary + 1e-3, h, 1.0], &ColorSpace::Oklch, &linear);
+            assert!(!in_gamut(outside), "{outside:?}");
+        }
+    }
+
+    assert_eq!(max_chroma(0.0, 30.0, &RgbColorSpace::SRGB), 0.0);
+    assert_eq!(max_chroma(1.0, 30.0, &RgbColorSpace::SRGB), 0.0);
+}
+
+#[test]
+fn bt2390_eetf_keeps_black_and_mid_tones_and_reaches_the_peak() {
+    let (src_peak, dst_peak) = (1000.0, 100.0);
+    assert_near(bt2390_eetf(src_peak, src_peak, dst_peak), dst_peak, 1e-2);
+    assert_eq!(bt2390_eetf(0.0, src_peak, dst_peak), 0.0);
+    for nits in [0.1, 1.0, 5.0, 10.0] {
+        assert_near(bt2390_eetf(nits, src_peak, dst_peak), nits, 1e-3 * nits);
+    }
+    let mut previous = 0.0;
+    for i in 1..=100 {
+        let mapped = bt2390_eetf(i as f32 * 10.0, src_peak, dst_peak);
+        assert!(
+            mapped >= previous && mapped <= dst_peak + 1e-2,
+            "{i} {mapped}"
+        );
+        previous = mapped;
+    }
+    assert_eq!(bt2390_eetf(500.0, dst_peak, src_peak), 500.0);
+}
+
+#[test]
+fn tone_mapped_plans_bring_the_source_peak_to_the_destination_white() {
+    let linear = |space: RgbColorSpace| {
+        ColorSpace::Rgb(RgbColorSpace {
+            transfer: Transfer::Linear,
+            ..space
+        })
+    };
+    let (from, to) = (linear(RgbColorSpace::BT2020), linear(RgbColorSpace::SRGB));
+    let options = ConversionOptions {
+        source_luminance: Luminance::DciP3,
+        destination_luminance: Luminance::Sdr,
+        ..ConversionOptions::default()
+    };
+    let scaled = ConversionPlan::with_options(&from, &to, options);
+    let tone_mapped = ConversionPlan::with_options(
+        &from,
+        &to,
+        ConversionOptions {
+            tone_map: true,
+            ..options
+        },
+    );
+
+    for (actual, expected) in tone_mapped
+        .apply([1.0, 1.0, 1.0, 0.5])
+        .into_iter()
+        .zip([1.0, 1.0, 1.0, 0.5])
+    {
+        assert_near(actual, expected, 1e-3);
+    }
+    assert_eq!(
+        tone_mapped.apply([0.0, 0.0, 0.0, 1.0]),
+        [0.0, 0.0, 0.0, 1.0]
+    );
+    // Mid-tones below the knee keep their absolute luminance.
+    for gray in [0.001, 0.005, 0.01] {
+        let pixel = [gray, gray, gray, 1.0];
+        for (actual, expected) in tone_mapped
+            .apply(pixel)
+            .into_iter()
+            .zip(scaled.apply(pixel))
+        {
+            assert_near(actual, expected, 1e-3 * expected.max(1e-3));
+        }
+    }
+
+    // A source no brighter than the destination is unaffected.
+    let brighter = ConversionOptions {
+        source_luminance: Luminance::Sdr,
+        destination_luminance: Luminance::DciP3,
+        ..options
+    };
+    assert_eq!(
+        ConversionPlan::with_options(
+            &from,
+            &to,
+            ConversionOptions {
+                tone_map: true,
+                ..brighter
+            }
+        ),
+        ConversionPlan::with_options(&from, &to, brighter)
+    );
+}
+
+#[test]
+fn sdr_white_is_a_tenth_of_the_hdr_reference() {
+    assert_eq!(
+        convert_luminance(1.0, Luminance::Sdr, Luminance::DciP3),
+        0.1
+    );
+    assert_eq!(
+        convert_luminance(0.1, Luminance::DciP3, Luminance::Sdr),
+        1.0
+    );
+}
+
+#[test]
+fn cvd_simulation_is_a_projection_keeping_its_anchor() {
+    let srgb = RgbColorSpace::SRGB;
+    for kind in [
+        CvdKind::Protanopia,
+        CvdKind::Deuteranopia,
+        CvdKind::Tritanopia,
+    ] {
+        let simulation = CvdSimulation::new(kind, &srgb);
+        for pixel in sample_image() {
+            let once = simulation.apply(pixel, 1.0);
+            assert_eq!(once, simulate_cvd(pixel, kind, 1.0, &srgb));
+            // Dichromats see the simulated colors as they are.
+            let twice = simulation.apply(once, 1.0);
+            for (actual, expected) in twice.into_iter().zip(once) {
+                assert_near(actual, expected, CONVERT_TOLERANCE);
+            }
+        }
+
+        let anchor = match kind {
+            CvdKind::Tritanopia => [1.0, 0.0, 0.0, 1.0],
+            _ => [0.0, 0.0, 1.0, 1.0],
+        };
+        for (actual, expected) in simulation.apply(anchor, 1.0).into_iter().zip(anchor) {
+            assert_near(actual, expected, CONVERT_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn degenerate_custom_whites_are_errors_rather_than_panics() {
+    let xyz = ColorSpace::Xyz(Whitepoint::D65);
+    for white in [[0.3, 0.0], [f32::NAN, 0.3], [0.3, f32::INFINITY]] {
+        let degenerate = ColorSpace::Rgb(RgbColorSpace {
+            whitepoint: Whitepoint::Custom(white),
+            ..RgbColorSpace::SRGB
+        });
+        // NaN whites never compare equal, so match rather than compare.
+        for (from, to) in [(&xyz, &degenerate), (&degenerate, &xyz)] {
+            let result = try_convert([0.5; 4], from, to);
+            let reported = matches!(
+                result,
+                Err(ConvertError::DegenerateSpace(ColorSpace::Rgb(_)))
+            );
+            assert!(reported, "{result:?}");
+        }
+        assert!(ConversionPlan::try_new(&xyz, &degenerate).is_err());
+    }
+
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    assert_eq!(
+        ConversionPlan::try_new(&xyz, &srgb),
+        Ok(ConversionPlan::new(&xyz, &srgb))
+    );
+}
+
+#[test]
+fn self_test_passes() {
+    assert_eq!(self_test(), Ok(()));
+}
+
+/// Builds a version 4 sRGB matrix/TRC profile with the colorants, white and
+/// adaptation of the widely shipped compact sRGB profiles and `trc` as the
+/// data of every tone curve tag.
+fn srgb_icc_profile(trc: &[u8]) -> Vec<u8> {
+    fn tag_data(kind: &[u8; 4], words: &[u32]) -> Vec<u8> {
+        let mut data = kind.to_vec();
+        data.extend([0; 4]);
+        data.extend(words.iter().flat_map(|word| word.to_be_bytes()));
+        data
+    }
+
+    let chad = [
+        0x1_0C42,
+        0x05DE,
+        0xFFFF_F325,
+        0x0793,
+        0xFD90,
+        0xFFFF_FBA1,
+        0xFFFF_FDA2,
+        0x03DC,
+        0xC06E,
+    ];
+    let tags: [(&[u8; 4], Vec<u8>); 8] = [
+        (b"wtpt", tag_data(b"XYZ ", &[0xF6D6, 0x1_0000, 0xD32D])),
+        (b"chad", tag_data(b"sf32", &chad)),
+        (b"rXYZ", tag_data(b"XYZ ", &[0x6FA2, 0x38F5, 0x0390])),
+        (b"gXYZ", tag_data(b"XYZ ", &[0x6299, 0xB785, 0x18DA])),
+        (b"bXYZ", tag_data(b"XYZ ", &[0x24A0, 0x0F84, 0xB6CF])),
+        (b"rTRC", trc.to_vec()),
+        (b"gTRC", trc.to_vec()),
+        (b"bTRC", trc.to_vec()),
+    ];
+
+    let mut header = vec![0; 128];
+    header[8..12].copy_from_slice(&0x0430_0000_u32.to_be_bytes());
+    header[12..16].copy_from_slice(b"mntr");
+    header[16..20].copy_from_slice(b"RGB ");
+    header[20..24].copy_from_slice(b"XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let start = header.len() + 4 + 12 * tags.len();
+    for (name, tag) in &tags {
+        table.extend(*name);
+        table.extend(((start + data.len()) as u32).to_be_bytes());
+        table.extend((tag.len() as u32).to_be_bytes());
+        data.extend(tag);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    let mut profile = [header, table, data].concat();
+    let len = (profile.len() as u32).to_be_bytes();
+    profile[..4].copy_from_slice(&len);
+    profile
+}
+
+#[test]
+fn icc_profile_reconstructs_srgb() {
+    // The sRGB curve as a parametric curve of function type 3, and as the
+    // 1024 entry table of older profiles.
+    let mut para = b"para\0\0\0\0\0\x03\0\0".to_vec();
+    para.extend(
+        [0x2_6666_u32, 0xF2A7, 0x0D59, 0x13D0, 0x0A5B]
+            .iter()
+            .flat_map(|word| word.to_be_bytes()),
+    );
+    let mut curv = b"curv\0\0\0\0".to_vec();
+    curv.extend(1024_u32.to_be_bytes());
+    curv.extend((0..1024).flat_map(|i| {
+        let linear = Transfer::Srgb.to_optical_scalar(i as f32 / 1023.0);
+        ((linear * 65535.0).round() as u16).to_be_bytes()
+    }));
+
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let expected = ChromaticAdaptation::Bradford.matrix(Whitepoint::D65, Whitepoint::D50)
+        * RgbColorSpace::SRGB.to_xyz_matrix();
+    for trc in [para, curv] {
+        let profile = IccProfile::parse(&srgb_icc_profile(&trc)).unwrap();
+        for (actual, expected) in profile
+            .colorants
+            .0
+            .iter()
+            .flatten()
+            .zip(expected.0.iter().flatten())
+        {
+            assert!((actual - expected).abs() < 1e-4, "{actual} {expected}");
+        }
+        assert_eq!(profile.to_rgb_color_space(), Ok(RgbColorSpace::SRGB));
+
+        let pixel = [0.8, 0.4, 0.1, 0.5];
+        let xyz = convert(pixel, &srgb, &ColorSpace::Xyz(Whitepoint::D50));
+        for (actual, expected) in profile.to_xyz(pixel).iter().zip(xyz) {
+            assert!((actual - expected).abs() < 1e-4, "{actual} {expected}");
+        }
+    }
+
+    let profile = srgb_icc_profile(b"XYZ \0\0\0\0");
+    assert_eq!(
+        IccProfile::parse(&profile),
+        Err(IccError::UnsupportedTagType {
+            tag: *b"rTRC",
+            kind: *b"XYZ "
+        })
+    );
+    assert_eq!(IccProfile::parse(&profile[..200]), Err(IccError::Truncated));
+}
+
+#[test]
+fn icc_profiles_keep_custom_primaries_and_curves() {
+    // Adobe RGB stores its gamma as a single u8Fixed8 entry, 563/256.
+    let mut gamma = b"curv\0\0\0\0".to_vec();
+    gamma.extend(1_u32.to_be_bytes());
+    gamma.extend(563_u16.to_be_bytes());
+    let profile = IccProfile::parse(&srgb_icc_profile(&gamma)).unwrap();
+    let expected = RgbColorSpace {
+        transfer: Transfer::Gamma(563.0 / 256.0),
+        ..RgbColorSpace::SRGB
+    };
+    assert_eq!(profile.to_rgb_color_space(), Ok(expected));
+
+    let prophoto = [[0.734_7, 0.265_3], [0.159_6, 0.840_4], [0.036_6, 0.000_1]];
+    let space = RgbColorSpace {
+        primaries: Primaries::Custom(prophoto),
+        whitepoint: Whitepoint::D50,
+        transfer: Transfer::Gamma(1.8),
+    };
+    let parsed = IccProfile::parse(&space.to_icc_v2())
+        .unwrap()
+        .to_rgb_color_space()
+        .unwrap();
+    let Primaries::Custom(chromaticities) = parsed.primaries else {
+        panic!("{:?} aren't custom", parsed.primaries);
+    };
+    for (actual, expected) in chromaticities
+        .iter()
+        .flatten()
+        .zip(prophoto.iter().flatten())
+    {
+        assert!((actual - expected).abs() < 1e-3, "{actual} {expected}");
+    }
+    assert_eq!(parsed.whitepoint, Whitepoint::D50);
+    let Transfer::Gamma(gamma) = parsed.transfer else {
+        panic!("{:?} isn't a gamma", parsed.transfer);
+    };
+    assert_near(gamma, 1.8, 1e-3);
+
+    // A measured display curve matching no transfer.
+    let mut measured = b"curv\0\0\0\0".to_vec();
+    measured.extend(256_u32.to_be_bytes());
+    measured.extend((0..256).flat_map(|i| {
+        let x = i as f32 / 255.0;
+        let linear = 0.5 * x * x + 0.5 * x.powi(3);
+        ((linear * 65535.0).round() as u16).to_be_bytes()
+    }));
+    let profile: &'static IccProfile = Box::leak(Box::new(
+        IccProfile::parse(&srgb_icc_profile(&measured)).unwrap(),
+    ));
+    assert_eq!(profile.to_rgb_color_space(), Err(IccError::UnknownTransfer));
+    let rgb = profile.to_rgb_color_space_with_curve().unwrap();
+    assert!(matches!(rgb.transfer, Transfer::Custom(_)));
+    let pixel = [0.8, 0.4, 0.1, 0.5];
+    let xyz = convert(
+        pixel,
+        &ColorSpace::Rgb(rgb),
+        &ColorSpace::Xyz(Whitepoint::D50),
+    );
+    for (actual, expected) in xyz.iter().zip(profile.to_xyz(pixel)) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} {expected}");
+    }
+}
+
+#[test]
+fn png_color_chunks_map_to_color_spaces() {
+    let cicp = |data: [u8; 4]| Cicp::parse(&data).unwrap().color_space();
+    assert_eq!(
+        cicp([1, 13, 0, 1]),
+        Ok(ColorSpace::Rgb(RgbColorSpace::SRGB))
+    );
+    let bt2100_pq = RgbColorSpace {
+        transfer: Transfer::Pq,
+        ..RgbColorSpace::BT2020
+    };
+    assert_eq!(cicp([9, 16, 0, 1]), Ok(ColorSpace::Rgb(bt2100_pq)));
+    assert_eq!(
+        cicp([9, 14, 9, 0]),
+        Ok(ColorSpace::Yuv(YuvColorSpace::BT2020))
+    );
+    assert_eq!(
+        cicp([1, 1, 1, 0]),
+        Ok(ColorSpace::Yuv(YuvColorSpace::BT709))
+    );
+    assert_eq!(
+        Cicp::parse(&[9, 14, 9, 0]).unwrap().signal_range(),
+        SignalRange::Narrow
+    );
+
+    // The `gAMA` and `cHRM` chunks written alongside `sRGB` describe a pure
+    // 2.2 gamma on their own, and give way to the `sRGB` chunk.
+    let gama = 45_455_u32.to_be_bytes();
+    let chrm: Vec<u8> = [
+        31_270_u32, 32_900, 64_000, 33_000, 30_000, 60_000, 15_000, 6_000,
+    ]
+    .iter()
+    .flat_map(|v| v.to_be_bytes())
+    .collect();
+    let chunks = PngColorChunks {
+        gama: Some(&gama),
+        chrm: Some(&chrm),
+        ..PngColorChunks::default()
+    };
+    let gamma_2_2 = RgbColorSpace {
+        transfer: Transfer::Gamma(100_000.0 / 45_455.0),
+        ..RgbColorSpace::SRGB
+    };
+    assert_eq!(chunks.color_space(), Ok(ColorSpace::Rgb(gamma_2_2)));
+    let with_srgb = PngColorChunks {
+        srgb: Some(&[0]),
+        ..chunks
+    };
+    assert_eq!(
+        with_srgb.color_space(),
+        Ok(ColorSpace::Rgb(RgbColorSpace::SRGB))
+    );
+
+    // Chunks that can't be interpreted give way to the next one present.
+    let truncated = [0; 16];
+    let unreadable = PngColorChunks {
+        cicp: Some(&[2, 2, 0, 1]),
+        icc_profile: Some(&truncated),
+        ..chunks
+    };
+    assert_eq!(unreadable.color_space(), Ok(ColorSpace::Rgb(gamma_2_2)));
+    let alone = PngColorChunks {
+        gama: None,
+        chrm: None,
+        ..unreadable
+    };
+    assert_eq!(
+        alone.color_space(),
+        Err(PngColorError::UnsupportedCodePoint {
+            field: "color primaries",
+            value: 2
+        })
+    );
+
+    // `iCCP` takes precedence over them, and `cICP` over everything.
+    let profile = RgbColorSpace::DISPLAY_P3.to_icc_v2();
+    let chunks = PngColorChunks {
+        icc_profile: Some(&profile),
+        ..chunks
+    };
+    assert_eq!(
+        chunks.color_space(),
+        Ok(ColorSpace::Rgb(RgbColorSpace::DISPLAY_P3))
+    );
+    let cicp = [9, 16, 0, 1];
+    let chunks = PngColorChunks {
+        cicp: Some(&cicp),
+        ..chunks
+    };
+    assert_eq!(chunks.color_space(), Ok(ColorSpace::Rgb(bt2100_pq)));
+}
+
+#[test]
+fn approximate_oklab_stays_within_its_bound() {
+    let mut x = 1e-6_f32;
+    while x < 1e6 {
+        let error = (approx_cbrt(x) / x.cbrt() - 1.0).abs();
+        assert!(error <= APPROX_CBRT_ERROR, "cbrt({x}) is off by {error}");
+        assert_eq!(approx_cbrt(-x), -approx_cbrt(x));
+        x *= 1.001;
+    }
+    assert_eq!(approx_cbrt(0.0), 0.0);
+
+    // The largest error over a grid spanning the sRGB gamut.
+    let srgb = ColorSpace::Rgb(RgbColorSpace::SRGB);
+    let exact = ConversionPlan::new(&srgb, &ColorSpace::Oklab);
+    let options = ConversionOptions {
+        approximate_cbrt: true,
+        ..ConversionOptions::default()
+    };
+    let approximate = ConversionPlan::with_options(&srgb, &ColorSpace::Oklab, options);
+    let steps = 32;
+    let mut max_error = 0.0_f32;
+    for r in 0..=steps {
+        for g in 0..=steps {
+            for b in 0..=steps {
+                let pixel = [r, g, b, steps].map(|c| c as f32 / steps as f32);
+                let (exact, approximate) = (exact.apply(pixel), approximate.apply(pixel));
+                assert_eq!(approximate[3], pixel[3]);
+                for (exact, approximate) in exact.iter().zip(approximate).take(3) {
+                    max_error = max_error.max((exact - approximate).abs());
+                }
+            }
+        }
+    }
+    assert!(
+        max_error <= OKLAB_APPROX_ERROR,
+        "approximate Oklab is off by up to {max_error}"
+    );
+}
+
+#[test]
+fn shorter_hue_lerp_crosses_the_seam() {
+    assert_eq!(hue_lerp(350.0, 10.0, 0.5, HueInterpolation::Shorter), 0.0);
+    assert_eq!(hue_lerp(10.0, 350.0, 0.5, HueInterpolation::Shorter), 0.0);
+    for i in 0..=20 {
+        let hue = hue_lerp(350.0, 10.0, i as f32 / 20.0, HueInterpolation::Shorter);
+        assert!(
+            hue_distance(hue, 0.0) <= 10.0,
+            "{hue} is not between 350° and 10°"
+        );
+    }
+    // The longer arc goes the other way, through 180°.
+    assert_eq!(hue_lerp(350.0, 10.0, 0.5, HueInterpolation::Longer), 180.0);
+
+    assert_eq!(hue_add(350.0, 20.0), 10.0);
+    assert_eq!(hue_add(10.0, -20.0), 350.0);
+    assert_eq!(hue_add(-1e-6, 0.0), 0.0);
+    assert_eq!(hue_distance(350.0, 10.0), 20.0);
+}